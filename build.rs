@@ -26,17 +26,82 @@ fn main() {
     // cause linker errors.
     build.define("VMA_DYNAMIC_VULKAN_FUNCTIONS", "0");
 
-    // TODO: Add some configuration options under crate features
-    //#define VMA_HEAVY_ASSERT(expr) assert(expr)
-    //#define VMA_USE_STL_CONTAINERS 1
-    //#define VMA_DEDICATED_ALLOCATION 0
-    //#define VMA_DEBUG_MARGIN 16
-    //#define VMA_DEBUG_DETECT_CORRUPTION 1
-    //#define VMA_DEBUG_INITIALIZE_ALLOCATIONS 1
-    //#define VMA_DEBUG_MIN_BUFFER_IMAGE_GRANULARITY 256
-
-    #[cfg(feature = "recording")]
-    build.define("VMA_RECORDING_ENABLED", "1");
+    // VMA debug configuration, enabled through crate features. The numeric values can be
+    // overridden with environment variables of the same name as the macros.
+    // Everything is passed on to `vk_mem::build_config()` through `rustc-env`.
+    let debug_margin =
+        debug_value("VMA_DEBUG_MARGIN", cfg!(feature = "debug_margin"), 16).unwrap_or(0);
+    if debug_margin % 4 != 0 {
+        panic!(
+            "VMA_DEBUG_MARGIN must be a multiple of 4, got {}",
+            debug_margin
+        );
+    }
+    let detect_corruption = cfg!(feature = "detect_corruption");
+    if detect_corruption && debug_margin == 0 {
+        panic!(
+            "the detect_corruption feature needs a debug margin: enable the debug_margin feature or set VMA_DEBUG_MARGIN to a non-zero value"
+        );
+    }
+    let min_buffer_image_granularity = debug_value(
+        "VMA_DEBUG_MIN_BUFFER_IMAGE_GRANULARITY",
+        cfg!(feature = "debug_min_buffer_image_granularity"),
+        256,
+    )
+    .unwrap_or(1);
+    let initialize_allocations = cfg!(feature = "debug_initialize_allocations");
+    let heavy_assert = cfg!(feature = "heavy_assert");
+    let stl_containers = cfg!(feature = "stl_containers");
+
+    if debug_margin != 0 {
+        build.define("VMA_DEBUG_MARGIN", debug_margin.to_string().as_str());
+    }
+    if detect_corruption {
+        build.define("VMA_DEBUG_DETECT_CORRUPTION", "1");
+    }
+    if initialize_allocations {
+        build.define("VMA_DEBUG_INITIALIZE_ALLOCATIONS", "1");
+    }
+    if min_buffer_image_granularity != 1 {
+        build.define(
+            "VMA_DEBUG_MIN_BUFFER_IMAGE_GRANULARITY",
+            min_buffer_image_granularity.to_string().as_str(),
+        );
+    }
+    if heavy_assert {
+        build.define("VK_MEM_HEAVY_ASSERT", "1");
+    }
+    if stl_containers {
+        build.define("VMA_USE_STL_CONTAINERS", "1");
+    }
+
+    println!("cargo:rustc-env=VK_MEM_DEBUG_MARGIN={}", debug_margin);
+    println!(
+        "cargo:rustc-env=VK_MEM_DEBUG_DETECT_CORRUPTION={}",
+        detect_corruption
+    );
+    println!(
+        "cargo:rustc-env=VK_MEM_DEBUG_INITIALIZE_ALLOCATIONS={}",
+        initialize_allocations
+    );
+    println!(
+        "cargo:rustc-env=VK_MEM_DEBUG_MIN_BUFFER_IMAGE_GRANULARITY={}",
+        min_buffer_image_granularity
+    );
+    println!("cargo:rustc-env=VK_MEM_HEAVY_ASSERT={}", heavy_assert);
+    println!(
+        "cargo:rustc-env=VK_MEM_USE_STL_CONTAINERS={}",
+        stl_containers
+    );
+
+    // Declaring the environment variables below disables the default of rerunning on any
+    // change in the package, so the sources are listed as well
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=wrapper");
+    println!("cargo:rerun-if-changed=vendor/VulkanMemoryAllocator/include");
+    println!("cargo:rerun-if-changed=vendor/Vulkan-Headers/include");
+    println!("cargo:rerun-if-env-changed=VMA_DEBUG_MARGIN");
+    println!("cargo:rerun-if-env-changed=VMA_DEBUG_MIN_BUFFER_IMAGE_GRANULARITY");
 
     // Add the files we build
     let source_files = ["wrapper/vma_lib.cpp"];
@@ -104,7 +169,22 @@ fn main() {
 
     build.compile("vma");
 
-    generate_bindings("src/ffi.rs");
+    generate_bindings("src/api/ffi.rs");
+}
+
+/// Value of a numeric VMA configuration macro: the environment variable `name` if set,
+/// otherwise `default` if the corresponding feature is enabled.
+fn debug_value(name: &str, enabled: bool, default: u64) -> Option<u64> {
+    match env::var(name) {
+        Ok(value) => Some(
+            value
+                .trim()
+                .parse()
+                .unwrap_or_else(|_| panic!("{} must be a number, got {:?}", name, value)),
+        ),
+        Err(_) if enabled => Some(default),
+        Err(_) => None,
+    }
 }
 
 #[cfg(feature = "generate_bindings")]
@@ -125,7 +205,8 @@ fn generate_bindings(output_file: &str) {
         .raw_line("#![allow(non_camel_case_types)]")
         .raw_line("#![allow(non_snake_case)]")
         .raw_line("#![allow(dead_code)]")
-        .raw_line("use spark::vk::*;")
+        .raw_line("#![allow(clashing_extern_declarations)]")
+        .raw_line("use super::ffi_types::*;")
         .trust_clang_mangling(false)
         .layout_tests(false)
         .rustified_enum("Vma.*")
@@ -148,12 +229,17 @@ struct FixAshTypes;
 impl bindgen::callbacks::ParseCallbacks for FixAshTypes {
     fn item_name(&self, original_item_name: &str) -> Option<String> {
         if original_item_name.starts_with("Vk") {
-            // Strip `Vk` prefix, will use `spark::vk::*` instead
+            // Strip `Vk` prefix, will use `super::ffi_types::*` instead
             Some(original_item_name.trim_start_matches("Vk").to_string())
         } else if original_item_name.starts_with("PFN_vk") {
             // VMA uses a few extensions like `PFN_vkGetBufferMemoryRequirements2KHR`,
             // ash keeps these as `FnGetBufferMemoryRequirements2`
-            Some(original_item_name.replace("PFN_vk", "Fn").trim_end_matches("KHR").to_string())
+            Some(
+                original_item_name
+                    .replace("PFN_vk", "Fn")
+                    .trim_end_matches("KHR")
+                    .to_string(),
+            )
         } else {
             None
         }
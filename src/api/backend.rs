@@ -0,0 +1,54 @@
+use std::os::raw::c_char;
+
+use super::ffi;
+use super::vk;
+use super::{FnVoidFunction, Nullable};
+
+/// Vulkan loader an `Allocator` takes its handles and function pointers from.
+///
+/// Implemented for `spark::Device` in `vk_mem::spark` and for `ash::Device` in `vk_mem::ash`,
+/// their instance being `spark::Instance` and `ash::Instance` respectively.
+///
+/// # Safety
+///
+/// The handles and every function pointer of `vulkan_functions` have to belong to the same
+/// instance and device, and have the signatures VMA expects.
+pub unsafe trait Backend {
+    /// Instance the device was created from.
+    type Instance;
+
+    fn instance_handle(instance: &Self::Instance) -> vk::Instance;
+
+    fn device_handle(&self) -> vk::Device;
+
+    /// Memory layout of `physical_device`, used to check the arrays passed to
+    /// `AllocatorCreateInfo`.
+    ///
+    /// # Safety
+    ///
+    /// `physical_device` has to be enumerated from `instance`.
+    unsafe fn memory_properties(
+        instance: &Self::Instance,
+        physical_device: vk::PhysicalDevice,
+    ) -> vk::PhysicalDeviceMemoryProperties;
+
+    /// Table passed to VMA as `VmaAllocatorCreateInfo::pVulkanFunctions`.
+    ///
+    /// VMA is built without `VMA_DYNAMIC_VULKAN_FUNCTIONS`, so `vkGetInstanceProcAddr` and
+    /// `vkGetDeviceProcAddr` are never called and may be stubs.
+    fn vulkan_functions(&self, instance: &Self::Instance) -> ffi::VmaVulkanFunctions;
+}
+
+pub(crate) unsafe extern "system" fn get_instance_proc_addr_stub(
+    _instance: Nullable<vk::Instance>,
+    _p_name: *const c_char,
+) -> Option<FnVoidFunction> {
+    panic!("VMA_DYNAMIC_VULKAN_FUNCTIONS is unsupported")
+}
+
+pub(crate) unsafe extern "system" fn get_device_proc_stub(
+    _device: Nullable<vk::Device>,
+    _p_name: *const c_char,
+) -> Option<FnVoidFunction> {
+    panic!("VMA_DYNAMIC_VULKAN_FUNCTIONS is unsupported")
+}
@@ -0,0 +1,167 @@
+use std::sync::Arc;
+
+use super::Allocator;
+use super::Budget;
+use super::Result;
+
+/// Source of per-heap budgets polled by `BudgetMonitor`.
+///
+/// Implemented by `Allocator`; implement it for a fake to test reactions to memory pressure
+/// without a GPU.
+pub trait BudgetSource {
+    /// Current budget of every memory heap, indexed by heap index.
+    fn heap_budgets(&self) -> Result<Vec<Budget>>;
+}
+
+impl BudgetSource for Allocator {
+    fn heap_budgets(&self) -> Result<Vec<Budget>> {
+        self.get_heap_budgets()
+    }
+}
+
+impl<T: BudgetSource + ?Sized> BudgetSource for Arc<T> {
+    fn heap_budgets(&self) -> Result<Vec<Budget>> {
+        (**self).heap_budgets()
+    }
+}
+
+impl<T: BudgetSource + ?Sized> BudgetSource for &T {
+    fn heap_budgets(&self) -> Result<Vec<Budget>> {
+        (**self).heap_budgets()
+    }
+}
+
+/// Direction in which a watermark was crossed.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum WatermarkCrossing {
+    /// Usage reached the watermark.
+    Above,
+    /// Usage dropped below the watermark minus the hysteresis.
+    Below,
+}
+
+/// Reported by `BudgetMonitor::poll` whenever usage of a heap crosses a watermark.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BudgetEvent {
+    /// Index of the memory heap.
+    pub heap_index: u32,
+    /// Watermark that was crossed, as a fraction of `Budget::budget`.
+    pub watermark: f32,
+    /// Direction of the crossing.
+    pub crossing: WatermarkCrossing,
+    /// Budget of the heap at the time of the crossing.
+    pub budget: Budget,
+}
+
+/// Callback registered with `BudgetMonitor::on_crossing`.
+type BudgetCallback = Box<dyn FnMut(&BudgetEvent) + Send>;
+
+/// Watches `Budget::usage` against `Budget::budget` of every heap and reports when it crosses
+/// configurable watermarks.
+///
+/// Call `BudgetMonitor::poll` regularly, e.g. once per frame. A watermark is crossed upwards as
+/// soon as usage reaches it, but only crossed back downwards once usage drops below the
+/// watermark minus the hysteresis, so usage hovering around a watermark doesn't report it
+/// every frame.
+pub struct BudgetMonitor<S: BudgetSource = Arc<Allocator>> {
+    source: S,
+    watermarks: Vec<f32>,
+    hysteresis: f32,
+    /// Number of watermarks every heap is currently above.
+    levels: Vec<usize>,
+    callbacks: Vec<BudgetCallback>,
+}
+
+impl<S: BudgetSource> BudgetMonitor<S> {
+    /// Creates a monitor with watermarks at 80% and 95% of the budget and 5% hysteresis.
+    pub fn new(source: S) -> Self {
+        BudgetMonitor {
+            source,
+            watermarks: vec![0.8, 0.95],
+            hysteresis: 0.05,
+            levels: Vec::new(),
+            callbacks: Vec::new(),
+        }
+    }
+
+    /// Replaces the watermarks, as fractions of the budget, e.g. `0.8` for 80%.
+    ///
+    /// Resets the state of all heaps, so the next poll reports every watermark that is
+    /// already exceeded.
+    pub fn watermarks(mut self, watermarks: &[f32]) -> Self {
+        let mut watermarks = watermarks.to_vec();
+        watermarks.sort_by(|a, b| a.partial_cmp(b).expect("watermarks must not be NaN"));
+        watermarks.dedup();
+        self.watermarks = watermarks;
+        self.levels.clear();
+        self
+    }
+
+    /// Sets how far below a watermark, as a fraction of the budget, usage has to drop before
+    /// the watermark is reported as crossed downwards.
+    pub fn hysteresis(mut self, hysteresis: f32) -> Self {
+        self.hysteresis = hysteresis.max(0.0);
+        self
+    }
+
+    /// Registers a callback invoked for every event reported by `BudgetMonitor::poll`.
+    pub fn on_crossing<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(&BudgetEvent) + Send + 'static,
+    {
+        self.callbacks.push(Box::new(callback));
+        self
+    }
+
+    /// Source of the budgets, e.g. the allocator.
+    pub fn source(&self) -> &S {
+        &self.source
+    }
+
+    /// Highest watermark given heap is currently above, as of the last poll.
+    pub fn current_watermark(&self, heap_index: u32) -> Option<f32> {
+        let level = self.levels.get(heap_index as usize).copied().unwrap_or(0);
+        level.checked_sub(1).map(|index| self.watermarks[index])
+    }
+
+    /// Fetches the budgets from the source, invokes the callbacks for every watermark crossed
+    /// since the previous poll and returns the same events.
+    ///
+    /// Heaps with a budget of 0 are ignored.
+    pub fn poll(&mut self) -> Result<Vec<BudgetEvent>> {
+        let budgets = self.source.heap_budgets()?;
+        self.levels.resize(budgets.len(), 0);
+
+        let mut events = Vec::new();
+        for (heap_index, budget) in budgets.iter().enumerate() {
+            if budget.budget == 0 {
+                continue;
+            }
+            // Compared in f32 so that e.g. 800 of 1000 bytes reaches a watermark of 0.8
+            let usage = (budget.usage as f64 / budget.budget as f64) as f32;
+            let level = &mut self.levels[heap_index];
+            let event = |watermark: f32, crossing| BudgetEvent {
+                heap_index: heap_index as u32,
+                watermark,
+                crossing,
+                budget: *budget,
+            };
+
+            while *level < self.watermarks.len() && usage >= self.watermarks[*level] {
+                events.push(event(self.watermarks[*level], WatermarkCrossing::Above));
+                *level += 1;
+            }
+            while *level > 0 && usage < self.watermarks[*level - 1] - self.hysteresis {
+                *level -= 1;
+                events.push(event(self.watermarks[*level], WatermarkCrossing::Below));
+            }
+        }
+
+        for event in &events {
+            for callback in &mut self.callbacks {
+                callback(event);
+            }
+        }
+        Ok(events)
+    }
+}
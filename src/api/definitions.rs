@@ -1,10 +1,20 @@
-use crate::ffi;
-use spark::vk;
-use spark::vk::PhysicalDevice;
+use super::device_memory::DeviceMemoryCallbacks;
+use super::ffi;
+use super::vk;
+use super::vk::PhysicalDevice;
+use super::Backend;
+use super::HostAllocationCallbacks;
+use super::LeakHandler;
+#[cfg(feature = "recording")]
+use super::Recorder;
 use bitflags::bitflags;
+use std::alloc::GlobalAlloc;
+use std::any::Any;
+use std::ffi::CStr;
 use std::marker::PhantomData;
 use std::ops::Deref;
 use std::ptr;
+use std::sync::Arc;
 
 /// Intended usage of memory.
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, PartialOrd, Ord)]
@@ -25,7 +35,7 @@ pub enum MemoryUsage {
     ///   device multiple times, e.g. textures to be sampled, vertex buffers, uniform
     ///   (constant) buffers, and majority of other types of resources used on GPU.
     ///
-    /// Allocation may still end up in `spark::vk::MemoryPropertyFlags::HOST_VISIBLE` memory on some implementations.
+    /// Allocation may still end up in `vk::MemoryPropertyFlags::HOST_VISIBLE` memory on some implementations.
     /// In such case, you are free to map it.
     /// You can use `AllocationCreateFlags::MAPPED` with this usage type.
     #[deprecated(since = "0.3")]
@@ -33,7 +43,7 @@ pub enum MemoryUsage {
 
     /// Memory will be mappable on host.
     /// It usually means CPU (system) memory.
-    /// Guarantees to be `spark::vk::MemoryPropertyFlags::HOST_VISIBLE` and `spark::vk::MemoryPropertyFlags::HOST_COHERENT`.
+    /// Guarantees to be `vk::MemoryPropertyFlags::HOST_VISIBLE` and `vk::MemoryPropertyFlags::HOST_COHERENT`.
     /// CPU access is typically uncached. Writes may be write-combined.
     /// Resources created in this pool may still be accessible to the device, but access to them can be slow.
     /// It is roughly equivalent of `D3D12_HEAP_TYPE_UPLOAD`.
@@ -42,7 +52,7 @@ pub enum MemoryUsage {
     #[deprecated(since = "0.3")]
     CpuOnly,
 
-    /// Memory that is both mappable on host (guarantees to be `spark::vk::MemoryPropertyFlags::HOST_VISIBLE`) and preferably fast to access by GPU.
+    /// Memory that is both mappable on host (guarantees to be `vk::MemoryPropertyFlags::HOST_VISIBLE`) and preferably fast to access by GPU.
     /// CPU access is typically uncached. Writes may be write-combined.
     ///
     /// Usage: Resources written frequently by host (dynamic), read by device. E.g. textures, vertex buffers,
@@ -50,7 +60,7 @@ pub enum MemoryUsage {
     #[deprecated(since = "0.3")]
     CpuToGpu,
 
-    /// Memory mappable on host (guarantees to be `spark::vk::MemoryPropertFlags::HOST_VISIBLE`) and cached.
+    /// Memory mappable on host (guarantees to be `vk::MemoryPropertFlags::HOST_VISIBLE`) and cached.
     /// It is roughly equivalent of `D3D12_HEAP_TYPE_READBACK`.
     ///
     /// Usage:
@@ -64,7 +74,7 @@ pub enum MemoryUsage {
     #[deprecated(since = "0.3")]
     CpuCopy,
 
-    /// Lazily allocated GPU memory having (guarantees to be `spark::vk::MemoryPropertFlags::LAZILY_ALLOCATED`).
+    /// Lazily allocated GPU memory having (guarantees to be `vk::MemoryPropertFlags::LAZILY_ALLOCATED`).
     /// Exists mostly on mobile platforms. Using it on desktop PC or other GPUs with no such memory type present will fail the allocation.
     ///
     /// Usage:
@@ -223,10 +233,10 @@ bitflags! {
         /// Use it for special, big resources, like fullscreen images used as attachments.
         const DEDICATED_MEMORY = ffi::VmaAllocationCreateFlagBits::VMA_ALLOCATION_CREATE_DEDICATED_MEMORY_BIT as u32;
 
-        /// Set this flag to only try to allocate from existing `spark::vk::DeviceMemory` blocks and never create new such block.
+        /// Set this flag to only try to allocate from existing `vk::DeviceMemory` blocks and never create new such block.
         ///
         /// If new allocation cannot be placed in any of the existing blocks, allocation
-        /// fails with `spark::vk::Result::ERROR_OUT_OF_DEVICE_MEMORY` error.
+        /// fails with `vk::Result::ERROR_OUT_OF_DEVICE_MEMORY` error.
         ///
         /// You should not use `AllocationCreateFlags::DEDICATED_MEMORY` and `AllocationCreateFlags::NEVER_ALLOCATE` at the same time. It makes no sense.
         const NEVER_ALLOCATE = ffi::VmaAllocationCreateFlagBits::VMA_ALLOCATION_CREATE_NEVER_ALLOCATE_BIT as u32;
@@ -236,9 +246,9 @@ bitflags! {
         /// Pointer to mapped memory will be returned through `Allocation::get_mapped_data()`.
         ///
         /// Is it valid to use this flag for allocation made from memory type that is not
-        /// `spark::vk::MemoryPropertyFlags::HOST_VISIBLE`. This flag is then ignored and memory is not mapped. This is
+        /// `vk::MemoryPropertyFlags::HOST_VISIBLE`. This flag is then ignored and memory is not mapped. This is
         /// useful if you need an allocation that is efficient to use on GPU
-        /// (`spark::vk::MemoryPropertyFlags::DEVICE_LOCAL`) and still want to map it directly if possible on platforms that
+        /// (`vk::MemoryPropertyFlags::DEVICE_LOCAL`) and still want to map it directly if possible on platforms that
         /// support it (e.g. Intel GPU).
         ///
         /// You should not use this flag together with `AllocationCreateFlags::CAN_BECOME_LOST`.
@@ -380,21 +390,29 @@ pub struct AllocatorCreateInfo<'a, I, D> {
     pub(crate) physical_device: PhysicalDevice,
     pub(crate) instance: I,
     pub(crate) device: D,
+    pub(crate) device_memory_callbacks: Option<Box<DeviceMemoryCallbacks>>,
+    pub(crate) host_allocation_callbacks: Option<Arc<dyn Any + Send + Sync>>,
+    pub(crate) leak_handler: Option<LeakHandler>,
+    #[cfg(feature = "recording")]
+    pub(crate) recorder: Option<Arc<Recorder>>,
     pub(crate) _phantom_data: PhantomData<&'a u8>,
 }
 
 impl<'a, I, D> AllocatorCreateInfo<'a, I, D>
 where
-    I: Deref<Target = spark::Instance>,
-    D: Deref<Target = spark::Device>,
+    I: Deref<Target = <D::Target as Backend>::Instance>,
+    D: Deref,
+    D::Target: Backend,
 {
-    pub fn new(instance: I, device: D, physical_device: spark::vk::PhysicalDevice) -> Self {
+    /// Takes the `spark` or `ash` instance and device the allocator fetches its
+    /// function pointers from, see `Backend`.
+    pub fn new(instance: I, device: D, physical_device: vk::PhysicalDevice) -> Self {
         Self {
             inner: ffi::VmaAllocatorCreateInfo {
                 flags: 0,
                 physicalDevice: physical_device,
-                instance: instance.handle,
-                device: device.handle,
+                instance: <D::Target as Backend>::instance_handle(&instance),
+                device: device.device_handle(),
                 preferredLargeHeapBlockSize: 0,
                 pAllocationCallbacks: ptr::null(),
                 pDeviceMemoryCallbacks: ptr::null(),
@@ -406,10 +424,44 @@ where
             physical_device,
             device,
             instance,
+            device_memory_callbacks: None,
+            host_allocation_callbacks: None,
+            leak_handler: None,
+            #[cfg(feature = "recording")]
+            recorder: None,
             _phantom_data: Default::default(),
         }
     }
 
+    pub fn heap_size_limit(mut self, device_sizes: &'a [vk::DeviceSize]) -> Self {
+        unsafe {
+            debug_assert!(
+                <D::Target as Backend>::memory_properties(&self.instance, self.physical_device)
+                    .memory_heap_count
+                    == device_sizes.len() as u32
+            );
+        }
+        self.inner.pHeapSizeLimit = device_sizes.as_ptr();
+        self
+    }
+
+    pub fn external_memory_handles(
+        mut self,
+        external_memory_handles: &'a [vk::ExternalMemoryHandleTypeFlagsKHR],
+    ) -> Self {
+        unsafe {
+            debug_assert!(
+                <D::Target as Backend>::memory_properties(&self.instance, self.physical_device)
+                    .memory_type_count
+                    == external_memory_handles.len() as u32
+            );
+        }
+        self.inner.pTypeExternalMemoryHandleTypes = external_memory_handles.as_ptr();
+        self
+    }
+}
+
+impl<'a, I, D> AllocatorCreateInfo<'a, I, D> {
     pub fn preferred_large_heap_block_size(mut self, size: u64) -> Self {
         self.inner.preferredLargeHeapBlockSize = size;
         self
@@ -420,21 +472,40 @@ where
         self
     }
 
-    pub fn heap_size_limit(mut self, device_sizes: &'a [spark::vk::DeviceSize]) -> Self {
-        unsafe {
-            debug_assert!(
-                self.instance
-                    .get_physical_device_memory_properties(self.physical_device)
-                    .memory_heap_count
-                    == device_sizes.len() as u32
-            );
-        }
-        self.inner.pHeapSizeLimit = device_sizes.as_ptr();
+    pub fn allocation_callback(mut self, allocation: &'a vk::AllocationCallbacks) -> Self {
+        self.inner.pAllocationCallbacks = allocation as *const _;
         self
     }
 
-    pub fn allocation_callback(mut self, allocation: &'a spark::vk::AllocationCallbacks) -> Self {
-        self.inner.pAllocationCallbacks = allocation as *const _;
+    /// Routes host memory allocations of the allocator through `callbacks`, which are kept
+    /// alive until the allocator is dropped. Replaces any `allocation_callback` set before.
+    pub fn host_allocation_callbacks<A>(
+        mut self,
+        callbacks: Arc<HostAllocationCallbacks<A>>,
+    ) -> Self
+    where
+        A: GlobalAlloc + Send + Sync + 'static,
+    {
+        self.inner.pAllocationCallbacks = callbacks.as_raw();
+        self.host_allocation_callbacks = Some(callbacks);
+        self
+    }
+
+    /// Tracks live allocations and passes the ones still alive when the allocator, or their
+    /// `AllocatorPool`, is dropped to `handler`, before freeing them.
+    ///
+    /// Without a handler, leaks are left to VMA: its assertion aborts the process when the
+    /// allocator is dropped with live allocations in debug builds, and they go unreported in
+    /// release builds.
+    pub fn leak_handler(mut self, handler: LeakHandler) -> Self {
+        self.leak_handler = Some(handler);
+        self
+    }
+
+    /// Writes every call made on the allocator and its pools to `recorder`, see `replay`.
+    #[cfg(feature = "recording")]
+    pub fn recorder(mut self, recorder: Arc<Recorder>) -> Self {
+        self.recorder = Some(recorder);
         self
     }
 
@@ -443,19 +514,17 @@ where
         self
     }
 
-    pub fn external_memory_handles(
-        mut self,
-        external_memory_handles: &'a [spark::vk::ExternalMemoryHandleTypeFlagsKHR],
-    ) -> Self {
-        unsafe {
-            debug_assert!(
-                self.instance
-                    .get_physical_device_memory_properties(self.physical_device)
-                    .memory_type_count
-                    == external_memory_handles.len() as u32
-            );
-        }
-        self.inner.pTypeExternalMemoryHandleTypes = external_memory_handles.as_ptr();
+    /// Sets closures called after every `vkAllocateMemory` and before every `vkFreeMemory`
+    /// issued by the allocator, with the memory type index, the memory handle and its size.
+    ///
+    /// The closures are owned by the resulting `Allocator` and may be called from any thread
+    /// that uses it. Panicking inside them aborts the process.
+    pub fn device_memory_callbacks<A, F>(mut self, allocate: A, free: F) -> Self
+    where
+        A: Fn(u32, vk::DeviceMemory, vk::DeviceSize) + Send + Sync + 'static,
+        F: Fn(u32, vk::DeviceMemory, vk::DeviceSize) + Send + Sync + 'static,
+    {
+        self.device_memory_callbacks = Some(DeviceMemoryCallbacks::new(allocate, free));
         self
     }
 }
@@ -517,13 +586,39 @@ impl<'a> PoolCreateInfo<'a> {
         self
     }
 
-    pub fn memory_allocate(mut self, next: &'a mut spark::vk::MemoryAllocateInfo) -> Self {
-        self.inner.pMemoryAllocateNext = next as *mut spark::vk::MemoryAllocateInfo as *mut _;
+    pub fn memory_allocate(mut self, next: &'a mut vk::MemoryAllocateInfo) -> Self {
+        self.inner.pMemoryAllocateNext = next as *mut vk::MemoryAllocateInfo as *mut _;
         self
     }
 }
 
-#[derive(Clone)]
+/// Vulkan handles an `Allocator` was created with, see `Allocator::info`.
+#[derive(Debug, Clone, Copy)]
+pub struct AllocatorInfo {
+    /// Handle to Vulkan instance object.
+    pub instance: vk::Instance,
+    /// Handle to Vulkan physical device object.
+    pub physical_device: vk::PhysicalDevice,
+    /// Handle to Vulkan device object.
+    pub device: vk::Device,
+}
+
+impl From<&ffi::VmaAllocatorInfo> for AllocatorInfo {
+    fn from(info: &ffi::VmaAllocatorInfo) -> Self {
+        Self {
+            instance: info.instance,
+            physical_device: info.physicalDevice,
+            device: info.device,
+        }
+    }
+}
+impl From<ffi::VmaAllocatorInfo> for AllocatorInfo {
+    fn from(info: ffi::VmaAllocatorInfo) -> Self {
+        (&info).into()
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct AllocationCreateInfo {
     pub flags: AllocationCreateFlags,
     /// Intended usage of memory.
@@ -654,10 +749,19 @@ pub struct AllocationInfo {
     ///
     /// It can change after call to vmaSetAllocationUserData() for this allocation.
     pub user_data: usize,
+    /// Custom allocation name that was set with `Allocator::set_allocation_name`.
+    ///
+    /// It can change after call to `Allocator::set_allocation_name` for this allocation.
+    pub name: Option<String>,
 }
 
 impl From<&ffi::VmaAllocationInfo> for AllocationInfo {
     fn from(info: &ffi::VmaAllocationInfo) -> Self {
+        let name = if info.pName.is_null() {
+            None
+        } else {
+            unsafe { Some(CStr::from_ptr(info.pName).to_string_lossy().into_owned()) }
+        };
         Self {
             memory_type: info.memoryType,
             device_memory: info.deviceMemory,
@@ -665,6 +769,7 @@ impl From<&ffi::VmaAllocationInfo> for AllocationInfo {
             size: info.size,
             mapped_data: info.pMappedData,
             user_data: info.pUserData as _,
+            name,
         }
     }
 }
@@ -674,8 +779,6 @@ impl From<ffi::VmaAllocationInfo> for AllocationInfo {
     }
 }
 
-
-
 bitflags! {
     /// Flags for configuring `VirtualBlock` construction
     pub struct VirtualBlockCreateFlags: u32 {
@@ -734,6 +837,10 @@ pub struct VirtualAllocationCreateInfo {
 /// Parameters of created VirtualBlock, to be passed to VirtualBlock::new()
 pub struct VirtualBlockCreateInfo<'a> {
     pub(crate) inner: ffi::VmaVirtualBlockCreateInfo,
+    pub(crate) host_allocation_callbacks: Option<Arc<dyn Any + Send + Sync>>,
+    pub(crate) leak_handler: Option<LeakHandler>,
+    #[cfg(feature = "recording")]
+    pub(crate) recorder: Option<Arc<Recorder>>,
     pub(crate) _phantom_data: PhantomData<&'a u8>,
 }
 
@@ -762,15 +869,47 @@ impl<'a> VirtualBlockCreateInfo<'a> {
                 size: 0,
                 pAllocationCallbacks: ptr::null(),
             },
+            host_allocation_callbacks: None,
+            leak_handler: None,
+            #[cfg(feature = "recording")]
+            recorder: None,
             _phantom_data: Default::default(),
         }
     }
 
-    pub fn allocation_callback(mut self, allocation: &'a spark::vk::AllocationCallbacks) -> Self {
+    pub fn allocation_callback(mut self, allocation: &'a vk::AllocationCallbacks) -> Self {
         self.inner.pAllocationCallbacks = allocation as *const _;
         self
     }
 
+    /// Routes host memory allocations of the virtual block through `callbacks`, which are kept
+    /// alive until the block is dropped. Replaces any `allocation_callback` set before.
+    pub fn host_allocation_callbacks<A>(
+        mut self,
+        callbacks: Arc<HostAllocationCallbacks<A>>,
+    ) -> Self
+    where
+        A: GlobalAlloc + Send + Sync + 'static,
+    {
+        self.inner.pAllocationCallbacks = callbacks.as_raw();
+        self.host_allocation_callbacks = Some(callbacks);
+        self
+    }
+
+    /// Tracks live virtual allocations and passes the ones still alive when the block is
+    /// dropped to `handler`, before clearing the block.
+    pub fn leak_handler(mut self, handler: LeakHandler) -> Self {
+        self.leak_handler = Some(handler);
+        self
+    }
+
+    /// Writes every call made on the virtual block to `recorder`, see `replay`.
+    #[cfg(feature = "recording")]
+    pub fn recorder(mut self, recorder: Arc<Recorder>) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
     pub fn size(mut self, size: u64) -> Self {
         self.inner.size = size;
         self
@@ -808,4 +947,3 @@ impl From<VirtualAllocationCreateInfo> for ffi::VmaVirtualAllocationCreateInfo {
         (&info).into()
     }
 }
-
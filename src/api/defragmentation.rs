@@ -0,0 +1,158 @@
+use super::error::ResultExt;
+use super::ffi;
+#[cfg(feature = "recording")]
+use super::recording::{handle, Call, CallResult, RecordedMove};
+use super::vk;
+use super::Allocator;
+use super::Result;
+
+pub use ffi::VmaDefragmentationInfo as DefragmentationInfo;
+pub use ffi::VmaDefragmentationMove as DefragmentationMove;
+pub use ffi::VmaDefragmentationMoveOperation as DefragmentationMoveOperation;
+pub use ffi::VmaDefragmentationStats as DefragmentationStats;
+pub struct DefragmentationContext<'a> {
+    allocator: &'a Allocator,
+    raw: ffi::VmaDefragmentationContext,
+}
+
+impl<'a> Drop for DefragmentationContext<'a> {
+    fn drop(&mut self) {
+        unsafe {
+            ffi::vmaEndDefragmentation(self.allocator.internal, self.raw, std::ptr::null_mut());
+        }
+        #[cfg(feature = "recording")]
+        self.allocator.record(|| Call::EndDefragmentation {
+            context: handle(self.raw),
+        });
+    }
+}
+
+impl<'a> DefragmentationContext<'a> {
+    /// Ends defragmentation process.
+    pub fn end(self) -> DefragmentationStats {
+        let mut stats = DefragmentationStats {
+            bytesMoved: 0,
+            bytesFreed: 0,
+            allocationsMoved: 0,
+            deviceMemoryBlocksFreed: 0,
+        };
+        unsafe {
+            ffi::vmaEndDefragmentation(self.allocator.internal, self.raw, &mut stats);
+        }
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            target: "vk_mem",
+            operation = "vmaEndDefragmentation",
+            bytes_moved = stats.bytesMoved,
+            bytes_freed = stats.bytesFreed,
+            allocations_moved = stats.allocationsMoved,
+            device_memory_blocks_freed = stats.deviceMemoryBlocksFreed,
+        );
+        #[cfg(feature = "recording")]
+        self.allocator.record(|| Call::EndDefragmentation {
+            context: handle(self.raw),
+        });
+        std::mem::forget(self);
+        stats
+    }
+
+    /// Returns `false` if no more moves are possible or `true` if more defragmentations are possible.
+    pub fn begin_pass(&self, mover: impl FnOnce(&mut [DefragmentationMove])) -> bool {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(target: "vk_mem", "vmaBeginDefragmentationPass").entered();
+        let mut pass_info = ffi::VmaDefragmentationPassMoveInfo {
+            moveCount: 0,
+            pMoves: std::ptr::null_mut(),
+        };
+        let result = unsafe {
+            ffi::vmaBeginDefragmentationPass(self.allocator.internal, self.raw, &mut pass_info)
+        };
+        if result == vk::Result::SUCCESS {
+            #[cfg(feature = "recording")]
+            self.allocator.record(|| Call::DefragmentationPass {
+                context: handle(self.raw),
+                moves: Vec::new(),
+            });
+            return false;
+        }
+        debug_assert_eq!(result, vk::Result::INCOMPLETE);
+        let moves = unsafe {
+            std::slice::from_raw_parts_mut(pass_info.pMoves, pass_info.moveCount as usize)
+        };
+        #[cfg(feature = "tracing")]
+        let move_count = moves.len();
+        mover(moves);
+        #[cfg(feature = "recording")]
+        self.allocator.record(|| Call::DefragmentationPass {
+            context: handle(self.raw),
+            moves: moves
+                .iter()
+                .map(|move_| RecordedMove {
+                    src_allocation: handle(move_.srcAllocation),
+                    dst_tmp_allocation: handle(move_.dstTmpAllocation),
+                    operation: move_.operation,
+                })
+                .collect(),
+        });
+        // VMA frees these at the end of the pass
+        let destroyed: Vec<ffi::VmaAllocation> = moves
+            .iter()
+            .filter(|move_| {
+                move_.operation
+                    == DefragmentationMoveOperation::VMA_DEFRAGMENTATION_MOVE_OPERATION_DESTROY
+            })
+            .map(|move_| move_.srcAllocation)
+            .collect();
+
+        let result = unsafe {
+            ffi::vmaEndDefragmentationPass(self.allocator.internal, self.raw, &mut pass_info)
+        };
+        for allocation in destroyed {
+            self.allocator.forget_allocation(allocation);
+        }
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            target: "vk_mem",
+            operation = "vmaEndDefragmentationPass",
+            move_count,
+            result = ?result,
+        );
+
+        return result == vk::Result::INCOMPLETE;
+    }
+}
+
+impl Allocator {
+    /// Begins defragmentation process.
+    ///
+    /// ## Returns
+    /// `VK_SUCCESS` if defragmentation can begin.
+    /// `VK_ERROR_FEATURE_NOT_PRESENT` if defragmentation is not supported.
+    pub unsafe fn begin_defragmentation(
+        &self,
+        info: &ffi::VmaDefragmentationInfo,
+    ) -> Result<DefragmentationContext> {
+        let mut context: ffi::VmaDefragmentationContext = std::ptr::null_mut();
+
+        let result = ffi::vmaBeginDefragmentation(self.internal, info, &mut context)
+            .result()
+            .context("vmaBeginDefragmentation");
+        #[cfg(feature = "tracing")]
+        super::instrumentation::trace_result("vmaBeginDefragmentation", &result);
+        #[cfg(feature = "recording")]
+        self.record(|| Call::BeginDefragmentation {
+            context: handle(context),
+            pool: handle(info.pool),
+            flags: info.flags,
+            max_bytes_per_pass: info.maxBytesPerPass,
+            max_allocations_per_pass: info.maxAllocationsPerPass,
+            result: CallResult::from(&result),
+        });
+        result?;
+
+        Ok(DefragmentationContext {
+            allocator: self,
+            raw: context,
+        })
+    }
+}
@@ -0,0 +1,73 @@
+use std::os::raw::c_void;
+use std::panic::{self, AssertUnwindSafe};
+use std::process;
+
+use super::ffi;
+use super::vk;
+
+type DeviceMemoryCallback = Box<dyn Fn(u32, vk::DeviceMemory, vk::DeviceSize) + Send + Sync>;
+
+/// Closures notified whenever VMA calls `vkAllocateMemory` or `vkFreeMemory`.
+///
+/// Boxed so that the address passed to VMA as `pUserData` stays stable while the owning
+/// `AllocatorCreateInfo` and later `Allocator` are moved around.
+pub(crate) struct DeviceMemoryCallbacks {
+    allocate: DeviceMemoryCallback,
+    free: DeviceMemoryCallback,
+}
+
+impl DeviceMemoryCallbacks {
+    pub(crate) fn new<A, F>(allocate: A, free: F) -> Box<Self>
+    where
+        A: Fn(u32, vk::DeviceMemory, vk::DeviceSize) + Send + Sync + 'static,
+        F: Fn(u32, vk::DeviceMemory, vk::DeviceSize) + Send + Sync + 'static,
+    {
+        Box::new(DeviceMemoryCallbacks {
+            allocate: Box::new(allocate),
+            free: Box::new(free),
+        })
+    }
+
+    /// Callbacks to pass to VMA, only valid while `self` stays alive and in place.
+    pub(crate) fn to_ffi(&self) -> ffi::VmaDeviceMemoryCallbacks {
+        ffi::VmaDeviceMemoryCallbacks {
+            pfnAllocate: Some(allocate_device_memory),
+            pfnFree: Some(free_device_memory),
+            pUserData: self as *const Self as *mut c_void,
+        }
+    }
+}
+
+unsafe extern "C" fn allocate_device_memory(
+    _allocator: ffi::VmaAllocator,
+    memory_type: u32,
+    memory: vk::DeviceMemory,
+    size: vk::DeviceSize,
+    user_data: *mut c_void,
+) {
+    let callbacks = &*(user_data as *const DeviceMemoryCallbacks);
+    call(&callbacks.allocate, memory_type, memory, size);
+}
+
+unsafe extern "C" fn free_device_memory(
+    _allocator: ffi::VmaAllocator,
+    memory_type: u32,
+    memory: vk::DeviceMemory,
+    size: vk::DeviceSize,
+    user_data: *mut c_void,
+) {
+    let callbacks = &*(user_data as *const DeviceMemoryCallbacks);
+    call(&callbacks.free, memory_type, memory, size);
+}
+
+/// Unwinding into VMA would leave it in an inconsistent state, so a panicking callback aborts.
+fn call(
+    callback: &DeviceMemoryCallback,
+    memory_type: u32,
+    memory: vk::DeviceMemory,
+    size: vk::DeviceSize,
+) {
+    if panic::catch_unwind(AssertUnwindSafe(|| callback(memory_type, memory, size))).is_err() {
+        process::abort();
+    }
+}
@@ -0,0 +1,185 @@
+use std::error;
+use std::fmt;
+
+use super::ffi;
+use super::vk;
+
+/// Result of fallible operations of this crate.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Error returned by the allocator, together with the VMA operation that failed.
+///
+/// `Error::vk_result` and the `From<Error> for vk::Result` conversion give back the
+/// underlying Vulkan result code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// Not enough device memory, or the heap size limit or budget was exceeded.
+    OutOfDeviceMemory {
+        operation: &'static str,
+        /// Requested size in bytes, if known.
+        size: Option<vk::DeviceSize>,
+    },
+    /// None of the memory types allowed by `memory_type_bits` has all of `required_flags`,
+    /// or matches the intended `MemoryUsage` of the allocation.
+    NoSuitableMemoryType {
+        operation: &'static str,
+        /// Requested size in bytes, if known.
+        size: Option<vk::DeviceSize>,
+        /// Memory types allowed by the resource and `AllocationCreateInfo::memory_type_bits`.
+        memory_type_bits: u32,
+        required_flags: vk::MemoryPropertyFlags,
+        preferred_flags: vk::MemoryPropertyFlags,
+    },
+    /// Corruption detection found a corrupted margin around one of the allocations.
+    CorruptionDetected { operation: &'static str },
+    /// The operation needs a feature that is not enabled, e.g. corruption detection without
+    /// `VMA_DEBUG_DETECT_CORRUPTION`.
+    FeatureNotEnabled { operation: &'static str },
+    /// Any other error returned by Vulkan.
+    Vulkan {
+        operation: &'static str,
+        /// Requested size in bytes, if known.
+        size: Option<vk::DeviceSize>,
+        result: vk::Result,
+    },
+}
+
+impl Error {
+    /// Wraps an error returned by Vulkan or VMA in `operation`.
+    pub(crate) fn vulkan(operation: &'static str, result: vk::Result) -> Self {
+        Self::sized(operation, None, result)
+    }
+
+    /// Wraps an error returned by `operation` for a request of `size` bytes.
+    pub(crate) fn sized(
+        operation: &'static str,
+        size: Option<vk::DeviceSize>,
+        result: vk::Result,
+    ) -> Self {
+        match result {
+            vk::Result::ERROR_OUT_OF_DEVICE_MEMORY => Error::OutOfDeviceMemory { operation, size },
+            _ => Error::Vulkan {
+                operation,
+                size,
+                result,
+            },
+        }
+    }
+
+    /// Wraps an error returned by an allocating `operation`, where
+    /// `ERROR_FEATURE_NOT_PRESENT` means that no memory type satisfies the request.
+    ///
+    /// `memory_type_bits` are the bits allowed by the resource, if known.
+    pub(crate) fn allocation(
+        operation: &'static str,
+        size: Option<vk::DeviceSize>,
+        memory_type_bits: Option<u32>,
+        create_info: &ffi::VmaAllocationCreateInfo,
+        result: vk::Result,
+    ) -> Self {
+        match result {
+            vk::Result::ERROR_FEATURE_NOT_PRESENT => {
+                let mut memory_type_bits = memory_type_bits.unwrap_or(u32::MAX);
+                if create_info.memoryTypeBits != 0 {
+                    memory_type_bits &= create_info.memoryTypeBits;
+                }
+                Error::NoSuitableMemoryType {
+                    operation,
+                    size,
+                    memory_type_bits,
+                    required_flags: create_info.requiredFlags,
+                    preferred_flags: create_info.preferredFlags,
+                }
+            }
+            _ => Self::sized(operation, size, result),
+        }
+    }
+
+    /// Wraps an error returned by a corruption check `operation`.
+    pub(crate) fn corruption_check(operation: &'static str, result: vk::Result) -> Self {
+        match result {
+            vk::Result::ERROR_FEATURE_NOT_PRESENT => Error::FeatureNotEnabled { operation },
+            vk::Result::ERROR_VALIDATION_FAILED_EXT => Error::CorruptionDetected { operation },
+            _ => Self::vulkan(operation, result),
+        }
+    }
+
+    /// Name of the VMA function that failed, e.g. `vmaCreateBuffer`.
+    pub fn operation(&self) -> &'static str {
+        match *self {
+            Error::OutOfDeviceMemory { operation, .. }
+            | Error::NoSuitableMemoryType { operation, .. }
+            | Error::CorruptionDetected { operation }
+            | Error::FeatureNotEnabled { operation }
+            | Error::Vulkan { operation, .. } => operation,
+        }
+    }
+
+    /// Size in bytes of the failed request, if known.
+    pub fn size(&self) -> Option<vk::DeviceSize> {
+        match *self {
+            Error::OutOfDeviceMemory { size, .. }
+            | Error::NoSuitableMemoryType { size, .. }
+            | Error::Vulkan { size, .. } => size,
+            Error::CorruptionDetected { .. } | Error::FeatureNotEnabled { .. } => None,
+        }
+    }
+
+    /// Vulkan result code VMA returned for this error.
+    pub fn vk_result(&self) -> vk::Result {
+        match *self {
+            Error::OutOfDeviceMemory { .. } => vk::Result::ERROR_OUT_OF_DEVICE_MEMORY,
+            Error::NoSuitableMemoryType { .. } | Error::FeatureNotEnabled { .. } => {
+                vk::Result::ERROR_FEATURE_NOT_PRESENT
+            }
+            Error::CorruptionDetected { .. } => vk::Result::ERROR_VALIDATION_FAILED_EXT,
+            Error::Vulkan { result, .. } => result,
+        }
+    }
+}
+
+impl From<Error> for vk::Result {
+    fn from(error: Error) -> Self {
+        error.vk_result()
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} failed: ", self.operation())?;
+        match self {
+            Error::OutOfDeviceMemory { .. } => write!(f, "out of device memory")?,
+            Error::NoSuitableMemoryType {
+                memory_type_bits,
+                required_flags,
+                preferred_flags,
+                ..
+            } => write!(
+                f,
+                "no suitable memory type among bits {:#x} with required flags {:?} and preferred flags {:?}",
+                memory_type_bits, required_flags, preferred_flags
+            )?,
+            Error::CorruptionDetected { .. } => write!(f, "memory corruption detected")?,
+            Error::FeatureNotEnabled { .. } => write!(f, "feature not enabled")?,
+            Error::Vulkan { result, .. } => write!(f, "{:?}", result)?,
+        }
+        if let Some(size) = self.size() {
+            write!(f, " (requested {} bytes)", size)?;
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for Error {}
+
+/// Attaches the failed operation to results of VMA calls.
+pub(crate) trait ResultExt<T> {
+    fn context(self, operation: &'static str) -> Result<T>;
+}
+
+impl<T> ResultExt<T> for std::result::Result<T, vk::Result> {
+    fn context(self, operation: &'static str) -> Result<T> {
+        self.map_err(|result| Error::vulkan(operation, result))
+    }
+}
@@ -3,7 +3,8 @@
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
 #![allow(dead_code)]
-use spark::vk::*;
+#![allow(clashing_extern_declarations)]
+use super::ffi_types::*;
 
 #[repr(i32)]
 #[doc = " Flags for created #VmaAllocator."]
@@ -0,0 +1,215 @@
+use std::alloc::{self, GlobalAlloc, Layout};
+use std::os::raw::c_void;
+use std::ptr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use super::vk;
+
+/// `GlobalAlloc` forwarding to the `#[global_allocator]` of the program.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct GlobalAllocator;
+
+unsafe impl GlobalAlloc for GlobalAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        alloc::alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        alloc::dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        alloc::realloc(ptr, layout, new_size)
+    }
+}
+
+/// `vk::AllocationCallbacks` routing VMA's host memory allocations to a Rust allocator.
+///
+/// Pass it to `AllocatorCreateInfo::host_allocation_callbacks` or
+/// `VirtualBlockCreateInfo::host_allocation_callbacks`, which keep it alive for as long as the
+/// allocator or virtual block exists. The same instance can be shared by several of them to
+/// account for their host memory together.
+///
+/// Vulkan frees memory without passing its size or alignment back, so every block is prefixed
+/// with a small header remembering them. Only the sizes requested by VMA are counted.
+pub struct HostAllocationCallbacks<A: GlobalAlloc = GlobalAllocator> {
+    allocator: A,
+    callbacks: vk::AllocationCallbacks,
+    allocated_bytes: AtomicUsize,
+    peak_allocated_bytes: AtomicUsize,
+    allocation_count: AtomicUsize,
+}
+unsafe impl<A: GlobalAlloc + Send> Send for HostAllocationCallbacks<A> {}
+unsafe impl<A: GlobalAlloc + Sync> Sync for HostAllocationCallbacks<A> {}
+
+/// Size and alignment stored right before every block handed out to VMA.
+#[derive(Clone, Copy)]
+struct Header {
+    size: usize,
+    alignment: usize,
+}
+
+impl HostAllocationCallbacks<GlobalAllocator> {
+    /// Creates callbacks allocating from the `#[global_allocator]` of the program.
+    pub fn new() -> Arc<Self> {
+        Self::with_allocator(GlobalAllocator)
+    }
+}
+
+impl<A: GlobalAlloc> HostAllocationCallbacks<A> {
+    /// Creates callbacks allocating from given allocator.
+    pub fn with_allocator(allocator: A) -> Arc<Self> {
+        Arc::new_cyclic(|this| HostAllocationCallbacks {
+            allocator,
+            callbacks: vk::AllocationCallbacks {
+                p_user_data: this.as_ptr() as *mut c_void,
+                pfn_allocation: Some(allocation::<A>),
+                pfn_reallocation: Some(reallocation::<A>),
+                pfn_free: Some(free::<A>),
+                pfn_internal_allocation: None,
+                pfn_internal_free: None,
+            },
+            allocated_bytes: AtomicUsize::new(0),
+            peak_allocated_bytes: AtomicUsize::new(0),
+            allocation_count: AtomicUsize::new(0),
+        })
+    }
+
+    /// Raw callbacks, valid for as long as `self` is alive.
+    pub fn as_raw(&self) -> &vk::AllocationCallbacks {
+        &self.callbacks
+    }
+
+    /// Allocator the memory is taken from.
+    pub fn allocator(&self) -> &A {
+        &self.allocator
+    }
+
+    /// Number of bytes currently allocated through these callbacks.
+    pub fn allocated_bytes(&self) -> usize {
+        self.allocated_bytes.load(Ordering::Relaxed)
+    }
+
+    /// Highest value `allocated_bytes` has reached so far.
+    pub fn peak_allocated_bytes(&self) -> usize {
+        self.peak_allocated_bytes.load(Ordering::Relaxed)
+    }
+
+    /// Number of live blocks allocated through these callbacks.
+    pub fn allocation_count(&self) -> usize {
+        self.allocation_count.load(Ordering::Relaxed)
+    }
+
+    /// Layout of a whole block, and the offset of the user memory within it.
+    fn layout(size: usize, alignment: usize) -> Option<(Layout, usize)> {
+        let alignment = alignment.max(std::mem::align_of::<Header>());
+        let offset = (std::mem::size_of::<Header>() + alignment - 1) & !(alignment - 1);
+        let layout = Layout::from_size_align(offset.checked_add(size)?, alignment).ok()?;
+        Some((layout, offset))
+    }
+
+    unsafe fn header(memory: *mut c_void) -> *mut Header {
+        (memory as *mut Header).sub(1)
+    }
+
+    unsafe fn allocate(&self, size: usize, alignment: usize) -> *mut c_void {
+        let (layout, offset) = match Self::layout(size, alignment) {
+            Some(layout) => layout,
+            None => return ptr::null_mut(),
+        };
+        let block = self.allocator.alloc(layout);
+        if block.is_null() {
+            return ptr::null_mut();
+        }
+        let memory = block.add(offset) as *mut c_void;
+        Self::header(memory).write(Header { size, alignment });
+        self.allocation_count.fetch_add(1, Ordering::Relaxed);
+        self.add_bytes(size);
+        memory
+    }
+
+    unsafe fn reallocate(
+        &self,
+        original: *mut c_void,
+        size: usize,
+        alignment: usize,
+    ) -> *mut c_void {
+        if original.is_null() {
+            return self.allocate(size, alignment);
+        }
+        if size == 0 {
+            self.free(original);
+            return ptr::null_mut();
+        }
+
+        // Vulkan requires the alignment to match the original allocation, so the offset of the
+        // user memory within the block stays the same and the block can be resized in place.
+        let header = Self::header(original).read();
+        let (old_layout, offset) = Self::layout(header.size, header.alignment).unwrap();
+        let new_size = match offset.checked_add(size) {
+            Some(new_size) => new_size,
+            None => return ptr::null_mut(),
+        };
+        let block = self
+            .allocator
+            .realloc((original as *mut u8).sub(offset), old_layout, new_size);
+        if block.is_null() {
+            return ptr::null_mut();
+        }
+        let memory = block.add(offset) as *mut c_void;
+        Self::header(memory).write(Header {
+            size,
+            alignment: header.alignment,
+        });
+        self.allocated_bytes
+            .fetch_sub(header.size, Ordering::Relaxed);
+        self.add_bytes(size);
+        memory
+    }
+
+    unsafe fn free(&self, memory: *mut c_void) {
+        if memory.is_null() {
+            return;
+        }
+        let header = Self::header(memory).read();
+        let (layout, offset) = Self::layout(header.size, header.alignment).unwrap();
+        self.allocator
+            .dealloc((memory as *mut u8).sub(offset), layout);
+        self.allocation_count.fetch_sub(1, Ordering::Relaxed);
+        self.allocated_bytes
+            .fetch_sub(header.size, Ordering::Relaxed);
+    }
+
+    fn add_bytes(&self, size: usize) {
+        let allocated = self.allocated_bytes.fetch_add(size, Ordering::Relaxed) + size;
+        self.peak_allocated_bytes
+            .fetch_max(allocated, Ordering::Relaxed);
+    }
+}
+
+unsafe extern "system" fn allocation<A: GlobalAlloc>(
+    p_user_data: *mut c_void,
+    size: usize,
+    alignment: usize,
+    _allocation_scope: vk::SystemAllocationScope,
+) -> *mut c_void {
+    let callbacks = &*(p_user_data as *const HostAllocationCallbacks<A>);
+    callbacks.allocate(size, alignment)
+}
+
+unsafe extern "system" fn reallocation<A: GlobalAlloc>(
+    p_user_data: *mut c_void,
+    p_original: *mut c_void,
+    size: usize,
+    alignment: usize,
+    _allocation_scope: vk::SystemAllocationScope,
+) -> *mut c_void {
+    let callbacks = &*(p_user_data as *const HostAllocationCallbacks<A>);
+    callbacks.reallocate(p_original, size, alignment)
+}
+
+unsafe extern "system" fn free<A: GlobalAlloc>(p_user_data: *mut c_void, p_memory: *mut c_void) {
+    let callbacks = &*(p_user_data as *const HostAllocationCallbacks<A>);
+    callbacks.free(p_memory)
+}
@@ -0,0 +1,108 @@
+//! Spans and events for the `tracing` crate, enabled by the `tracing` feature.
+//!
+//! Allocating, freeing and mapping functions, pool creation and defragmentation passes run in
+//! a `DEBUG` span named after the VMA function they call. Their outcome is reported as a
+//! `DEBUG` event, or a `WARN` event if they fail, with the target `vk_mem` and the fields
+//! `operation`, `size`, `memory_type`, `pool` and `result` where they apply.
+
+use std::ffi::CStr;
+
+use super::ffi;
+use super::AllocationInfo;
+use super::Allocator;
+use super::PoolHandle;
+use super::Result;
+
+/// Reports the outcome of an operation that doesn't allocate, e.g. `vmaMapMemory`.
+pub(crate) fn trace_result<T>(operation: &'static str, result: &Result<T>) {
+    match result {
+        Ok(_) => tracing::debug!(target: "vk_mem", operation, result = "SUCCESS"),
+        Err(error) => tracing::warn!(
+            target: "vk_mem",
+            operation,
+            result = ?error.vk_result(),
+            "{}",
+            error
+        ),
+    }
+}
+
+impl Allocator {
+    /// Name of `pool` set with `AllocatorPool::set_name`, `None` for the default pools.
+    pub(crate) fn pool_name(&self, pool: PoolHandle) -> Option<String> {
+        if pool.is_default() {
+            return None;
+        }
+        let mut name = std::ptr::null();
+        unsafe {
+            ffi::vmaGetPoolName(self.internal, pool.0, &mut name);
+            if name.is_null() {
+                return None;
+            }
+            Some(CStr::from_ptr(name).to_string_lossy().into_owned())
+        }
+    }
+
+    fn raw_allocation_info(&self, allocation: ffi::VmaAllocation) -> AllocationInfo {
+        unsafe {
+            let mut info = std::mem::zeroed();
+            ffi::vmaGetAllocationInfo(self.internal, allocation, &mut info);
+            AllocationInfo::from(&info)
+        }
+    }
+
+    /// Reports the outcome of an allocating operation, with one event per allocation if it
+    /// succeeded.
+    pub(crate) fn trace_allocations(
+        &self,
+        operation: &'static str,
+        pool: PoolHandle,
+        allocations: &[ffi::VmaAllocation],
+        result: &Result<()>,
+    ) {
+        match result {
+            Ok(()) => {
+                if !tracing::enabled!(target: "vk_mem", tracing::Level::DEBUG) {
+                    return;
+                }
+                let pool_name = self.pool_name(pool);
+                for &allocation in allocations {
+                    let info = self.raw_allocation_info(allocation);
+                    tracing::debug!(
+                        target: "vk_mem",
+                        operation,
+                        size = info.size,
+                        memory_type = info.memory_type,
+                        pool = pool_name.as_deref(),
+                        result = "SUCCESS",
+                    );
+                }
+            }
+            Err(error) => tracing::warn!(
+                target: "vk_mem",
+                operation,
+                size = error.size(),
+                pool = self.pool_name(pool).as_deref(),
+                result = ?error.vk_result(),
+                "{}",
+                error
+            ),
+        }
+    }
+
+    /// Reports allocations about to be freed, while their information is still available.
+    pub(crate) fn trace_free(&self, operation: &'static str, allocations: &[ffi::VmaAllocation]) {
+        if !tracing::enabled!(target: "vk_mem", tracing::Level::DEBUG) {
+            return;
+        }
+        for &allocation in allocations {
+            let info = self.raw_allocation_info(allocation);
+            tracing::debug!(
+                target: "vk_mem",
+                operation,
+                size = info.size,
+                memory_type = info.memory_type,
+            );
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::panic::Location;
+use std::sync::Mutex;
+
+use super::statistics::Bytes;
+use super::vk;
+
+/// What to do with allocations that are still alive when an `Allocator`, `AllocatorPool` or
+/// `VirtualBlock` is dropped, see `AllocatorCreateInfo::leak_handler`.
+///
+/// In every case the leaked allocations are freed after the report, so that destroying the
+/// allocator doesn't fire `VMA_ASSERT`.
+pub enum LeakHandler {
+    /// Frees the leaked allocations without reporting them.
+    Ignore,
+    /// Prints the report to stderr.
+    Log,
+    /// Panics with the report once the allocator, pool or block has been destroyed.
+    ///
+    /// Falls back to `LeakHandler::Log` if the thread is already panicking.
+    Panic,
+    /// Passes the report to a callback.
+    Custom(Box<dyn Fn(&LeakReport) + Send + Sync>),
+}
+
+/// Allocation that was still alive when its `Allocator`, `AllocatorPool` or `VirtualBlock` was
+/// dropped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LeakedAllocation {
+    /// Offset within its `vk::DeviceMemory` block, or within the virtual block.
+    pub offset: vk::DeviceSize,
+    /// Size in bytes.
+    pub size: vk::DeviceSize,
+    /// Memory type index, `None` for virtual allocations.
+    pub memory_type: Option<u32>,
+    /// Name set with `Allocator::set_allocation_name`.
+    pub name: Option<String>,
+    /// Raw user data, see `AllocationInfo::user_data` and `VirtualAllocationInfo::user_data`.
+    pub user_data: usize,
+    /// Type name of the value attached with `Allocator::set_user_data`, if any.
+    pub user_data_type: Option<&'static str>,
+    /// Code that made the allocation, only known with the `track_allocations` feature.
+    pub location: Option<&'static Location<'static>>,
+}
+
+/// Allocations leaked by a dropped `Allocator`, `AllocatorPool` or `VirtualBlock`, passed to
+/// the `LeakHandler`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LeakReport {
+    /// `"Allocator"`, `"AllocatorPool"` or `"VirtualBlock"`.
+    pub owner: &'static str,
+    /// Leaked allocations, ordered by memory type and offset.
+    pub allocations: Vec<LeakedAllocation>,
+}
+
+impl LeakReport {
+    /// Sum of the sizes of all leaked allocations.
+    pub fn total_bytes(&self) -> vk::DeviceSize {
+        self.allocations
+            .iter()
+            .map(|allocation| allocation.size)
+            .sum()
+    }
+}
+
+impl fmt::Display for LeakReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} dropped with {} live allocations ({})",
+            self.owner,
+            self.allocations.len(),
+            Bytes(self.total_bytes())
+        )?;
+        for allocation in &self.allocations {
+            write!(f, "\n  {}", Bytes(allocation.size))?;
+            if let Some(memory_type) = allocation.memory_type {
+                write!(f, " in memory type {}", memory_type)?;
+            }
+            write!(f, " at offset {}", allocation.offset)?;
+            if let Some(name) = &allocation.name {
+                write!(f, ", name {:?}", name)?;
+            }
+            if allocation.user_data != 0 {
+                write!(f, ", user data {:#x}", allocation.user_data)?;
+            }
+            if let Some(user_data_type) = allocation.user_data_type {
+                write!(f, ", user data of type {}", user_data_type)?;
+            }
+            if let Some(location) = allocation.location {
+                write!(f, ", allocated at {}", location)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Live allocations of an allocator or virtual block with a `LeakHandler`, mapping their raw
+/// handle to the raw handle of their pool, 0 for the default pools and virtual blocks.
+pub(crate) struct LeakDetection {
+    handler: LeakHandler,
+    live: Mutex<HashMap<usize, usize>>,
+}
+
+impl LeakDetection {
+    pub(crate) fn new(handler: LeakHandler) -> Self {
+        LeakDetection {
+            handler,
+            live: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub(crate) fn insert(&self, handle: usize, pool: usize) {
+        self.live.lock().unwrap().insert(handle, pool);
+    }
+
+    pub(crate) fn remove(&self, handle: usize) {
+        self.live.lock().unwrap().remove(&handle);
+    }
+
+    /// Forgets the live allocations of `pool`, or all of them, returning their handles.
+    pub(crate) fn take(&self, pool: Option<usize>) -> Vec<usize> {
+        let mut live = self.live.lock().unwrap();
+        let taken: Vec<usize> = live
+            .iter()
+            .filter(|&(_, &allocation_pool)| pool.map_or(true, |pool| pool == allocation_pool))
+            .map(|(&handle, _)| handle)
+            .collect();
+        for handle in &taken {
+            live.remove(handle);
+        }
+        taken
+    }
+
+    /// Reports `allocations` unless there are none, returning the report if it has to be
+    /// raised as a panic once the owner is destroyed.
+    pub(crate) fn report(
+        &self,
+        owner: &'static str,
+        mut allocations: Vec<LeakedAllocation>,
+    ) -> Option<LeakReport> {
+        if allocations.is_empty() {
+            return None;
+        }
+        allocations.sort_by_key(|allocation| (allocation.memory_type, allocation.offset));
+        let report = LeakReport { owner, allocations };
+        match &self.handler {
+            LeakHandler::Ignore => None,
+            LeakHandler::Panic if !std::thread::panicking() => Some(report),
+            LeakHandler::Log | LeakHandler::Panic => {
+                eprintln!("{}", report);
+                None
+            }
+            LeakHandler::Custom(callback) => {
+                callback(&report);
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,244 @@
+use std::ops::{Deref, DerefMut, Range};
+use std::{mem, ptr, slice};
+
+use super::vk;
+use super::Allocation;
+use super::Allocator;
+use super::Result;
+
+/// Host mapping of an allocation, unmapped automatically when dropped.
+///
+/// Dereferences to the bytes of the whole allocation. If the allocation lives in memory that is not
+/// `vk::MemoryPropertyFlags::HOST_COHERENT`, the ranges written through this guard are flushed
+/// with `Allocator::flush_allocation` before unmapping. Errors of that last flush are ignored,
+/// call `MappedMemory::flush` before dropping the guard to handle them.
+pub struct MappedMemory<'a> {
+    allocator: &'a Allocator,
+    allocation: &'a mut Allocation,
+    data: *mut u8,
+    size: usize,
+    coherent: bool,
+    dirty: Option<Range<usize>>,
+}
+
+impl Allocator {
+    /// Maps given allocation and returns a guard giving access to its memory.
+    ///
+    /// See `Allocator::map_memory` for the requirements on the allocation.
+    pub fn map<'a>(&'a self, allocation: &'a mut Allocation) -> Result<MappedMemory<'a>> {
+        unsafe {
+            let info = self.get_allocation_info(allocation)?;
+            let data = self.map_memory(allocation)?;
+            Ok(MappedMemory {
+                allocator: self,
+                allocation,
+                data,
+                size: info.size as usize,
+                coherent: self.is_host_coherent(info.memory_type),
+                dirty: None,
+            })
+        }
+    }
+
+    /// Copies `data` into given allocation at `offset`.
+    ///
+    /// The allocation is mapped for the duration of the copy. If its memory type is not
+    /// `vk::MemoryPropertyFlags::HOST_COHERENT`, the written range, extended to multiples
+    /// of `nonCoherentAtomSize`, is flushed afterwards.
+    ///
+    /// Panics if the range doesn't fit into the allocation.
+    pub fn copy_to_allocation(
+        &self,
+        allocation: &Allocation,
+        offset: usize,
+        data: &[u8],
+    ) -> Result<()> {
+        unsafe {
+            let info = self.get_allocation_info(allocation)?;
+            assert!(
+                offset
+                    .checked_add(data.len())
+                    .map_or(false, |end| end <= info.size as usize),
+                "range out of bounds of the allocation"
+            );
+            let mapped = self.map_raw(self.raw_allocation(allocation))?;
+            ptr::copy_nonoverlapping(data.as_ptr(), mapped.add(offset), data.len());
+            let result = if self.is_host_coherent(info.memory_type) {
+                Ok(())
+            } else {
+                let range = self.non_coherent_atom_range(offset..offset + data.len(), info.size);
+                self.flush_allocation(allocation, range.start, range.end - range.start)
+            };
+            self.unmap_raw(self.raw_allocation(allocation));
+            result
+        }
+    }
+
+    /// Copies bytes of given allocation starting at `offset` into `data`.
+    ///
+    /// The allocation is mapped for the duration of the copy. If its memory type is not
+    /// `vk::MemoryPropertyFlags::HOST_COHERENT`, the read range, extended to multiples
+    /// of `nonCoherentAtomSize`, is invalidated first.
+    ///
+    /// Panics if the range doesn't fit into the allocation.
+    pub fn copy_from_allocation(
+        &self,
+        allocation: &Allocation,
+        offset: usize,
+        data: &mut [u8],
+    ) -> Result<()> {
+        unsafe {
+            let info = self.get_allocation_info(allocation)?;
+            assert!(
+                offset
+                    .checked_add(data.len())
+                    .map_or(false, |end| end <= info.size as usize),
+                "range out of bounds of the allocation"
+            );
+            let mapped = self.map_raw(self.raw_allocation(allocation))?;
+            let result = if self.is_host_coherent(info.memory_type) {
+                Ok(())
+            } else {
+                let range = self.non_coherent_atom_range(offset..offset + data.len(), info.size);
+                self.invalidate_allocation(allocation, range.start, range.end - range.start)
+            };
+            if result.is_ok() {
+                ptr::copy_nonoverlapping(mapped.add(offset), data.as_mut_ptr(), data.len());
+            }
+            self.unmap_raw(self.raw_allocation(allocation));
+            result
+        }
+    }
+
+    fn is_host_coherent(&self, memory_type: u32) -> bool {
+        unsafe {
+            self.get_memory_properties().memory_types[memory_type as usize]
+                .property_flags
+                .contains(vk::MemoryPropertyFlags::HOST_COHERENT)
+        }
+    }
+
+    /// Extends `range` to multiples of `nonCoherentAtomSize`, clamped to the allocation size.
+    fn non_coherent_atom_range(
+        &self,
+        range: Range<usize>,
+        allocation_size: vk::DeviceSize,
+    ) -> Range<usize> {
+        let atom_size = unsafe {
+            self.get_physical_device_properties()
+                .map_or(1, |properties| properties.limits.non_coherent_atom_size)
+                .max(1) as usize
+        };
+        let start = range.start / atom_size * atom_size;
+        let end = (range.end + atom_size - 1) / atom_size * atom_size;
+        start..end.min(allocation_size as usize)
+    }
+}
+
+impl<'a> MappedMemory<'a> {
+    /// Size of the mapping in bytes, equal to `AllocationInfo::size`.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns `true` if the mapped memory type is `HOST_COHERENT` and never needs flushing.
+    pub fn is_coherent(&self) -> bool {
+        self.coherent
+    }
+
+    /// Copies `data` into the mapping at `offset`, marking only that range as dirty.
+    ///
+    /// Panics if the range doesn't fit into the mapping.
+    pub fn write(&mut self, offset: usize, data: &[u8]) {
+        let end = offset
+            .checked_add(data.len())
+            .expect("range out of bounds of the mapping");
+        let range = offset..end;
+        self.mark_dirty(range.clone());
+        unsafe { slice::from_raw_parts_mut(self.data, self.size)[range].copy_from_slice(data) };
+    }
+
+    /// Marks given byte range as written, so it is flushed when the guard is dropped.
+    ///
+    /// Panics if the range is reversed or doesn't fit into the mapping.
+    pub fn mark_dirty(&mut self, range: Range<usize>) {
+        assert!(range.start <= range.end, "reversed range");
+        assert!(range.end <= self.size, "range out of bounds of the mapping");
+        self.dirty = match self.dirty.take() {
+            Some(dirty) => Some(dirty.start.min(range.start)..dirty.end.max(range.end)),
+            None => Some(range),
+        };
+    }
+
+    /// Flushes the ranges written so far. Does nothing for `HOST_COHERENT` memory.
+    pub fn flush(&mut self) -> Result<()> {
+        if let Some(dirty) = self.dirty.take() {
+            if !self.coherent {
+                self.allocator.flush_allocation(
+                    &*self.allocation,
+                    dirty.start,
+                    dirty.end - dirty.start,
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Views the mapping as a slice of `T`, trailing bytes that don't form a whole `T` are ignored.
+    ///
+    /// Panics if the mapping is not aligned for `T` or `T` is zero-sized.
+    ///
+    /// # Safety
+    ///
+    /// Any bit pattern in the mapped memory must be a valid `T`.
+    pub unsafe fn as_slice<T: Copy>(&self) -> &[T] {
+        slice::from_raw_parts(self.typed_ptr::<T>(), self.size / mem::size_of::<T>())
+    }
+
+    /// Views the mapping as a mutable slice of `T` and marks the whole mapping as dirty.
+    ///
+    /// Panics if the mapping is not aligned for `T` or `T` is zero-sized.
+    ///
+    /// # Safety
+    ///
+    /// Any bit pattern in the mapped memory must be a valid `T`.
+    pub unsafe fn as_mut_slice<T: Copy>(&mut self) -> &mut [T] {
+        self.mark_dirty(0..self.size);
+        slice::from_raw_parts_mut(self.typed_ptr::<T>(), self.size / mem::size_of::<T>())
+    }
+
+    fn typed_ptr<T>(&self) -> *mut T {
+        assert_ne!(mem::size_of::<T>(), 0, "zero-sized types can't be mapped");
+        assert_eq!(
+            self.data as usize % mem::align_of::<T>(),
+            0,
+            "mapping is not sufficiently aligned for the requested type"
+        );
+        self.data as *mut T
+    }
+}
+
+impl<'a> Deref for MappedMemory<'a> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.data, self.size) }
+    }
+}
+
+impl<'a> DerefMut for MappedMemory<'a> {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        self.mark_dirty(0..self.size);
+        unsafe { slice::from_raw_parts_mut(self.data, self.size) }
+    }
+}
+
+impl<'a> Drop for MappedMemory<'a> {
+    fn drop(&mut self) {
+        // Nothing to report the error to, see the type documentation
+        let _ = self.flush();
+        unsafe {
+            self.allocator.unmap_memory(&mut *self.allocation);
+        }
+    }
+}
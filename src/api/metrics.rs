@@ -0,0 +1,334 @@
+//! OpenMetrics text exposition of heap budgets and statistics, e.g. for Prometheus.
+//!
+//! ```ignore
+//! let exporter = Arc::new(vk_mem::MetricsExporter::new(allocator.clone()));
+//! exporter.add_pool(&pool);
+//! let handler = exporter.handler();
+//! // In the HTTP server, for GET /metrics:
+//! let response = handler();
+//! ```
+
+use std::fmt::Write;
+use std::sync::{Arc, Mutex, Weak};
+
+use super::Allocator;
+use super::AllocatorPool;
+use super::Budget;
+use super::DetailedStatistics;
+use super::Result;
+use super::TotalStatistics;
+
+/// Content type of the text rendered by `MetricsSnapshot::render`.
+pub const OPENMETRICS_CONTENT_TYPE: &str =
+    "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// Statistics of a custom pool, see `MetricsSnapshot::pools`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolMetrics {
+    /// Name set with `AllocatorPool::set_name`, used as the `pool` label. Names may repeat,
+    /// samples are told apart by the `pool_index` label.
+    pub name: String,
+    pub statistics: DetailedStatistics,
+}
+
+/// Statistics of an allocator and its pools at one point in time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetricsSnapshot {
+    /// Budget of every memory heap, indexed by heap index.
+    pub budgets: Vec<Budget>,
+    /// Statistics of every memory type and heap.
+    pub statistics: TotalStatistics,
+    /// Heap index of every memory type, indexed by memory type index.
+    pub memory_type_heaps: Vec<u32>,
+    /// Custom pools, their index is used as the `pool_index` label.
+    pub pools: Vec<PoolMetrics>,
+}
+
+impl MetricsSnapshot {
+    /// Captures the statistics of `allocator` and `pools`.
+    ///
+    /// Pools without a name are labeled `pool<N>`, `N` being their index in `pools`.
+    pub fn capture(allocator: &Allocator, pools: &[&AllocatorPool]) -> Result<Self> {
+        let memory_properties = unsafe { allocator.get_memory_properties() };
+        let memory_type_heaps = memory_properties.memory_types
+            [..memory_properties.memory_type_count as usize]
+            .iter()
+            .map(|memory_type| memory_type.heap_index)
+            .collect();
+        let mut pool_metrics = Vec::with_capacity(pools.len());
+        for (index, pool) in pools.iter().enumerate() {
+            pool_metrics.push(PoolMetrics {
+                name: pool.name().map_or_else(
+                    || format!("pool{}", index),
+                    |name| name.to_string_lossy().into_owned(),
+                ),
+                statistics: pool.calculate_statistics()?,
+            });
+        }
+        Ok(MetricsSnapshot {
+            budgets: allocator.get_heap_budgets()?,
+            statistics: allocator.calculate_statistics()?,
+            memory_type_heaps,
+            pools: pool_metrics,
+        })
+    }
+
+    /// Renders the snapshot in the OpenMetrics text format, terminated by `# EOF`.
+    ///
+    /// Heap metrics are labeled with `heap`, memory type metrics with `memory_type` and
+    /// `heap`, and pool metrics with `pool` and `pool_index`.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+
+        let heaps: Vec<(String, &Budget)> = self
+            .budgets
+            .iter()
+            .enumerate()
+            .map(|(heap, budget)| (format!("heap=\"{}\"", heap), budget))
+            .collect();
+        let heap_family = |out: &mut String,
+                           name: &str,
+                           unit: Option<&str>,
+                           help: &str,
+                           value: fn(&Budget) -> u64| {
+            family(
+                out,
+                name,
+                unit,
+                help,
+                heaps
+                    .iter()
+                    .map(|(labels, budget)| (&labels[..], value(budget))),
+            )
+        };
+        heap_family(
+            &mut out,
+            "vk_mem_heap_budget_bytes",
+            Some("bytes"),
+            "Estimated memory available to the program in the heap.",
+            |budget| budget.budget,
+        );
+        heap_family(
+            &mut out,
+            "vk_mem_heap_usage_bytes",
+            Some("bytes"),
+            "Estimated memory used by the program in the heap.",
+            |budget| budget.usage,
+        );
+        heap_family(
+            &mut out,
+            "vk_mem_heap_block_bytes",
+            Some("bytes"),
+            "Memory allocated in device memory blocks of the heap.",
+            |budget| budget.statistics.block_bytes,
+        );
+        heap_family(
+            &mut out,
+            "vk_mem_heap_allocation_bytes",
+            Some("bytes"),
+            "Memory occupied by allocations in the heap.",
+            |budget| budget.statistics.allocation_bytes,
+        );
+        heap_family(
+            &mut out,
+            "vk_mem_heap_blocks",
+            None,
+            "Device memory blocks allocated in the heap.",
+            |budget| budget.statistics.block_count.into(),
+        );
+        heap_family(
+            &mut out,
+            "vk_mem_heap_allocations",
+            None,
+            "Allocations in the heap.",
+            |budget| budget.statistics.allocation_count.into(),
+        );
+
+        let memory_types: Vec<(String, &DetailedStatistics)> = self
+            .statistics
+            .per_memory_type
+            .iter()
+            .zip(&self.memory_type_heaps)
+            .enumerate()
+            .map(|(memory_type, (statistics, heap))| {
+                let labels = format!("memory_type=\"{}\",heap=\"{}\"", memory_type, heap);
+                (labels, statistics)
+            })
+            .collect();
+        detailed_families(&mut out, "vk_mem_memory_type", "memory type", &memory_types);
+
+        let pools: Vec<(String, &DetailedStatistics)> = self
+            .pools
+            .iter()
+            .enumerate()
+            .map(|(index, pool)| {
+                let labels = format!(
+                    "pool=\"{}\",pool_index=\"{}\"",
+                    escape_label(&pool.name),
+                    index
+                );
+                (labels, &pool.statistics)
+            })
+            .collect();
+        detailed_families(&mut out, "vk_mem_pool", "pool", &pools);
+
+        out.push_str("# EOF\n");
+        out
+    }
+}
+
+/// Writes the families shared by memory types and pools, named `<prefix>_block_bytes` etc.
+fn detailed_families(
+    out: &mut String,
+    prefix: &str,
+    subject: &str,
+    samples: &[(String, &DetailedStatistics)],
+) {
+    let detailed_family = |out: &mut String,
+                           suffix: &str,
+                           unit: Option<&str>,
+                           help: &str,
+                           value: fn(&DetailedStatistics) -> u64| {
+        family(
+            out,
+            &format!("{}_{}", prefix, suffix),
+            unit,
+            &format!("{} {}.", help, subject),
+            samples
+                .iter()
+                .map(|(labels, statistics)| (&labels[..], value(statistics))),
+        )
+    };
+    detailed_family(
+        out,
+        "block_bytes",
+        Some("bytes"),
+        "Memory allocated in device memory blocks of the",
+        |statistics| statistics.statistics.block_bytes,
+    );
+    detailed_family(
+        out,
+        "allocation_bytes",
+        Some("bytes"),
+        "Memory occupied by allocations in the",
+        |statistics| statistics.statistics.allocation_bytes,
+    );
+    detailed_family(
+        out,
+        "blocks",
+        None,
+        "Device memory blocks allocated in the",
+        |statistics| statistics.statistics.block_count.into(),
+    );
+    detailed_family(
+        out,
+        "allocations",
+        None,
+        "Allocations in the",
+        |statistics| statistics.statistics.allocation_count.into(),
+    );
+    detailed_family(
+        out,
+        "unused_ranges",
+        None,
+        "Free ranges between allocations in the",
+        |statistics| statistics.unused_range_count.into(),
+    );
+}
+
+/// Writes a gauge metric family with one sample per label set.
+fn family<'a>(
+    out: &mut String,
+    name: &str,
+    unit: Option<&str>,
+    help: &str,
+    samples: impl Iterator<Item = (&'a str, u64)>,
+) {
+    // Writing to a String can't fail
+    let _ = writeln!(out, "# TYPE {} gauge", name);
+    if let Some(unit) = unit {
+        let _ = writeln!(out, "# UNIT {} {}", name, unit);
+    }
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    for (labels, value) in samples {
+        let _ = writeln!(out, "{}{{{}}} {}", name, labels, value);
+    }
+}
+
+/// Escapes a label value, see the OpenMetrics ABNF.
+fn escape_label(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Response of the handler returned by `MetricsExporter::handler`, to be translated into the
+/// HTTP server's own response type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetricsResponse {
+    /// HTTP status code, 200, or 500 if the statistics couldn't be retrieved.
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+/// Renders the metrics of an allocator and the pools added to it on demand.
+///
+/// Pools are referenced weakly and disappear from the output once they are dropped.
+pub struct MetricsExporter {
+    allocator: Arc<Allocator>,
+    pools: Mutex<Vec<Weak<AllocatorPool>>>,
+}
+
+impl MetricsExporter {
+    pub fn new(allocator: Arc<Allocator>) -> Self {
+        MetricsExporter {
+            allocator,
+            pools: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Adds the statistics of `pool` to the output, labeled with its name.
+    pub fn add_pool(&self, pool: &Arc<AllocatorPool>) {
+        self.pools.lock().unwrap().push(Arc::downgrade(pool));
+    }
+
+    /// Captures a snapshot of the allocator and the live pools.
+    pub fn snapshot(&self) -> Result<MetricsSnapshot> {
+        let pools: Vec<Arc<AllocatorPool>> = {
+            let mut pools = self.pools.lock().unwrap();
+            pools.retain(|pool| pool.strong_count() > 0);
+            pools.iter().filter_map(Weak::upgrade).collect()
+        };
+        let pools: Vec<&AllocatorPool> = pools.iter().map(|pool| &**pool).collect();
+        MetricsSnapshot::capture(&self.allocator, &pools)
+    }
+
+    /// Captures a snapshot and renders it in the OpenMetrics text format.
+    pub fn render(&self) -> Result<String> {
+        self.snapshot().map(|snapshot| snapshot.render())
+    }
+
+    /// Returns a function answering scrape requests, independent of any HTTP server.
+    pub fn handler(self: Arc<Self>) -> impl Fn() -> MetricsResponse + Send + Sync + 'static {
+        move || match self.render() {
+            Ok(body) => MetricsResponse {
+                status: 200,
+                content_type: OPENMETRICS_CONTENT_TYPE,
+                body,
+            },
+            Err(error) => MetricsResponse {
+                status: 500,
+                content_type: "text/plain; charset=utf-8",
+                body: error.to_string(),
+            },
+        }
+    }
+}
@@ -0,0 +1,754 @@
+//! Software stand-in for a Vulkan device, backed by host memory.
+//!
+//! `MockDevice` supplies the full `VmaVulkanFunctions` table, so an `Allocator` can be created
+//! and exercised (buffers, images, pools, mapping, defragmentation) on machines without a GPU.
+//! Enabled by the `mock` feature.
+//!
+//! ```ignore
+//! let device = vk_mem::mock::MockDevice::new(vk_mem::mock::MockDevice::default_memory_properties());
+//! let allocator = vk_mem::Allocator::new_mock(device.create_info())?;
+//! ```
+
+use std::alloc::{self, Layout};
+use std::marker::PhantomData;
+use std::os::raw::{c_char, c_void};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex};
+use std::{mem, process, ptr};
+
+use super::ffi;
+use super::vk;
+use super::Result;
+use super::{Allocator, AllocatorCreateInfo};
+use super::{FnVoidFunction, Nullable};
+
+/// Alignment of every host block backing a `vk::DeviceMemory` object.
+const MEMORY_BLOCK_ALIGNMENT: usize = 4096;
+
+/// Alignment reported in memory requirements of mock buffers.
+const BUFFER_ALIGNMENT: vk::DeviceSize = 256;
+
+/// Alignment reported in memory requirements of mock images.
+const IMAGE_ALIGNMENT: vk::DeviceSize = 4096;
+
+/// Size of a single texel assumed for every image format.
+const IMAGE_TEXEL_SIZE: vk::DeviceSize = 4;
+
+/// Software implementation of the subset of Vulkan used by VMA.
+///
+/// Device memory is backed by zero-initialized host allocations, so mapping works for every
+/// memory type, including the ones that are not `HOST_VISIBLE` on real hardware.
+/// Allocations fail with `Error::OutOfDeviceMemory` once the size of
+/// the corresponding heap in `PhysicalDeviceMemoryProperties` is exhausted.
+pub struct MockDevice {
+    properties: vk::PhysicalDeviceProperties,
+    memory_properties: vk::PhysicalDeviceMemoryProperties,
+    state: Mutex<MockDeviceState>,
+}
+
+struct MockDeviceState {
+    heap_usage: Vec<vk::DeviceSize>,
+    memory_count: u32,
+    buffer_count: u32,
+    image_count: u32,
+    flush_count: u32,
+    invalidate_count: u32,
+}
+
+struct MockMemory {
+    data: *mut u8,
+    size: vk::DeviceSize,
+    heap_index: u32,
+}
+
+struct MockBuffer {
+    size: vk::DeviceSize,
+    binding: MockBinding,
+}
+
+struct MockImage {
+    size: vk::DeviceSize,
+    binding: MockBinding,
+}
+
+#[derive(Default)]
+struct MockBinding {
+    memory: Nullable<vk::DeviceMemory>,
+    offset: vk::DeviceSize,
+}
+
+impl MockDevice {
+    /// Creates a new mock device with the given memory layout and default device properties.
+    pub fn new(memory_properties: vk::PhysicalDeviceMemoryProperties) -> Arc<Self> {
+        let mut properties = vk::PhysicalDeviceProperties::default();
+        properties.limits.max_memory_allocation_count = 4096;
+        properties.limits.buffer_image_granularity = 1;
+        properties.limits.non_coherent_atom_size = 64;
+        Self::with_properties(properties, memory_properties)
+    }
+
+    /// Creates a new mock device with the given device properties and memory layout.
+    ///
+    /// `properties.limits` is used by VMA, most notably `buffer_image_granularity`,
+    /// `non_coherent_atom_size` and `max_memory_allocation_count`.
+    pub fn with_properties(
+        properties: vk::PhysicalDeviceProperties,
+        memory_properties: vk::PhysicalDeviceMemoryProperties,
+    ) -> Arc<Self> {
+        let heap_count = memory_properties.memory_heap_count as usize;
+        Arc::new(MockDevice {
+            properties,
+            memory_properties,
+            state: Mutex::new(MockDeviceState {
+                heap_usage: vec![0; heap_count],
+                memory_count: 0,
+                buffer_count: 0,
+                image_count: 0,
+                flush_count: 0,
+                invalidate_count: 0,
+            }),
+        })
+    }
+
+    /// Memory layout of a typical discrete GPU, with 256 MiB per heap.
+    ///
+    /// - Heap 0: `DEVICE_LOCAL`
+    /// - Heap 1: host memory
+    /// - Type 0: `DEVICE_LOCAL` in heap 0
+    /// - Type 1: `HOST_VISIBLE | HOST_COHERENT` in heap 1
+    /// - Type 2: `HOST_VISIBLE | HOST_CACHED` in heap 1 (not coherent)
+    /// - Type 3: `DEVICE_LOCAL | HOST_VISIBLE | HOST_COHERENT` in heap 0
+    pub fn default_memory_properties() -> vk::PhysicalDeviceMemoryProperties {
+        let mut memory_properties = vk::PhysicalDeviceMemoryProperties {
+            memory_heap_count: 2,
+            memory_type_count: 4,
+            ..Default::default()
+        };
+
+        memory_properties.memory_heaps[0] = vk::MemoryHeap {
+            size: 256 * 1024 * 1024,
+            flags: vk::MemoryHeapFlags::DEVICE_LOCAL,
+        };
+        memory_properties.memory_heaps[1] = vk::MemoryHeap {
+            size: 256 * 1024 * 1024,
+            flags: vk::MemoryHeapFlags::empty(),
+        };
+
+        memory_properties.memory_types[0] = vk::MemoryType {
+            property_flags: vk::MemoryPropertyFlags::DEVICE_LOCAL,
+            heap_index: 0,
+        };
+        memory_properties.memory_types[1] = vk::MemoryType {
+            property_flags: vk::MemoryPropertyFlags::HOST_VISIBLE
+                | vk::MemoryPropertyFlags::HOST_COHERENT,
+            heap_index: 1,
+        };
+        memory_properties.memory_types[2] = vk::MemoryType {
+            property_flags: vk::MemoryPropertyFlags::HOST_VISIBLE
+                | vk::MemoryPropertyFlags::HOST_CACHED,
+            heap_index: 1,
+        };
+        memory_properties.memory_types[3] = vk::MemoryType {
+            property_flags: vk::MemoryPropertyFlags::DEVICE_LOCAL
+                | vk::MemoryPropertyFlags::HOST_VISIBLE
+                | vk::MemoryPropertyFlags::HOST_COHERENT,
+            heap_index: 0,
+        };
+
+        memory_properties
+    }
+
+    /// Returns the create info used to construct an `Allocator` on top of this device
+    /// with `Allocator::new_mock`.
+    pub fn create_info<'a>(self: &Arc<Self>) -> AllocatorCreateInfo<'a, Arc<Self>, Arc<Self>> {
+        unsafe {
+            AllocatorCreateInfo {
+                inner: ffi::VmaAllocatorCreateInfo {
+                    flags: 0,
+                    physicalDevice: dispatchable_handle(self),
+                    instance: dispatchable_handle(self),
+                    device: dispatchable_handle(self),
+                    preferredLargeHeapBlockSize: 0,
+                    pAllocationCallbacks: ptr::null(),
+                    pDeviceMemoryCallbacks: ptr::null(),
+                    pHeapSizeLimit: ptr::null(),
+                    pVulkanFunctions: ptr::null(),
+                    vulkanApiVersion: 0,
+                    pTypeExternalMemoryHandleTypes: ptr::null(),
+                },
+                physical_device: dispatchable_handle(self),
+                instance: self.clone(),
+                device: self.clone(),
+                device_memory_callbacks: None,
+                host_allocation_callbacks: None,
+                leak_handler: None,
+                #[cfg(feature = "recording")]
+                recorder: None,
+                _phantom_data: PhantomData,
+            }
+        }
+    }
+
+    /// Memory layout reported by this device.
+    pub fn memory_properties(&self) -> &vk::PhysicalDeviceMemoryProperties {
+        &self.memory_properties
+    }
+
+    /// Number of bytes currently allocated from given heap.
+    pub fn heap_usage(&self, heap_index: u32) -> vk::DeviceSize {
+        self.state.lock().unwrap().heap_usage[heap_index as usize]
+    }
+
+    /// Number of live `vk::DeviceMemory` objects.
+    pub fn memory_count(&self) -> u32 {
+        self.state.lock().unwrap().memory_count
+    }
+
+    /// Number of live `vk::Buffer` objects.
+    pub fn buffer_count(&self) -> u32 {
+        self.state.lock().unwrap().buffer_count
+    }
+
+    /// Number of live `vk::Image` objects.
+    pub fn image_count(&self) -> u32 {
+        self.state.lock().unwrap().image_count
+    }
+
+    /// Number of memory ranges passed to `vkFlushMappedMemoryRanges` so far.
+    pub fn flush_count(&self) -> u32 {
+        self.state.lock().unwrap().flush_count
+    }
+
+    /// Number of memory ranges passed to `vkInvalidateMappedMemoryRanges` so far.
+    pub fn invalidate_count(&self) -> u32 {
+        self.state.lock().unwrap().invalidate_count
+    }
+
+    /// Destroys a buffer the allocator doesn't own, e.g. one created with
+    /// `Allocator::create_aliasing_buffer`, like `vkDestroyBuffer`.
+    ///
+    /// # Safety
+    ///
+    /// `buffer` has to be created on this device and not destroyed yet.
+    pub unsafe fn destroy_buffer(&self, buffer: vk::Buffer) {
+        if from_handle::<MockBuffer, _>(buffer).is_some() {
+            self.state.lock().unwrap().buffer_count -= 1;
+        }
+    }
+
+    /// Destroys an image the allocator doesn't own, e.g. one created with
+    /// `Allocator::create_aliasing_image`, like `vkDestroyImage`.
+    ///
+    /// # Safety
+    ///
+    /// `image` has to be created on this device and not destroyed yet.
+    pub unsafe fn destroy_image(&self, image: vk::Image) {
+        if from_handle::<MockImage, _>(image).is_some() {
+            self.state.lock().unwrap().image_count -= 1;
+        }
+    }
+
+    fn memory_type_bits(&self) -> u32 {
+        match self.memory_properties.memory_type_count {
+            32 => !0,
+            count => (1u32 << count) - 1,
+        }
+    }
+
+    fn buffer_requirements(&self, create_info: &vk::BufferCreateInfo) -> vk::MemoryRequirements {
+        vk::MemoryRequirements {
+            size: align_up(create_info.size, BUFFER_ALIGNMENT),
+            alignment: BUFFER_ALIGNMENT,
+            memory_type_bits: self.memory_type_bits(),
+        }
+    }
+
+    fn image_requirements(&self, create_info: &vk::ImageCreateInfo) -> vk::MemoryRequirements {
+        let extent = create_info.extent;
+        let texels: vk::DeviceSize = (0..create_info.mip_levels.max(1))
+            .map(|level| {
+                let width = (extent.width >> level).max(1) as vk::DeviceSize;
+                let height = (extent.height >> level).max(1) as vk::DeviceSize;
+                let depth = (extent.depth >> level).max(1) as vk::DeviceSize;
+                width * height * depth
+            })
+            .sum();
+        let size = texels * create_info.array_layers.max(1) as vk::DeviceSize * IMAGE_TEXEL_SIZE;
+        vk::MemoryRequirements {
+            size: align_up(size, IMAGE_ALIGNMENT),
+            alignment: IMAGE_ALIGNMENT,
+            memory_type_bits: self.memory_type_bits(),
+        }
+    }
+}
+
+impl Allocator {
+    /// Constructs a new `Allocator` on top of a `MockDevice`.
+    ///
+    /// The allocator keeps the device alive until it is dropped.
+    pub fn new_mock(
+        mut create_info: AllocatorCreateInfo<Arc<MockDevice>, Arc<MockDevice>>,
+    ) -> Result<Self> {
+        let functions = functions();
+        create_info.inner.pVulkanFunctions = &functions;
+        let device = create_info.device.clone();
+        unsafe { Self::from_create_info(create_info, Some(device)) }
+    }
+}
+
+fn functions() -> ffi::VmaVulkanFunctions {
+    ffi::VmaVulkanFunctions {
+        vkGetInstanceProcAddr: get_instance_proc_addr,
+        vkGetDeviceProcAddr: get_device_proc_addr,
+        vkGetPhysicalDeviceProperties: get_physical_device_properties,
+        vkGetPhysicalDeviceMemoryProperties: get_physical_device_memory_properties,
+        vkAllocateMemory: allocate_memory,
+        vkFreeMemory: free_memory,
+        vkMapMemory: map_memory,
+        vkUnmapMemory: unmap_memory,
+        vkFlushMappedMemoryRanges: flush_mapped_memory_ranges,
+        vkInvalidateMappedMemoryRanges: invalidate_mapped_memory_ranges,
+        vkBindBufferMemory: bind_buffer_memory,
+        vkBindImageMemory: bind_image_memory,
+        vkGetBufferMemoryRequirements: get_buffer_memory_requirements,
+        vkGetImageMemoryRequirements: get_image_memory_requirements,
+        vkCreateBuffer: create_buffer,
+        vkDestroyBuffer: destroy_buffer,
+        vkCreateImage: create_image,
+        vkDestroyImage: destroy_image,
+        vkCmdCopyBuffer: cmd_copy_buffer,
+        vkGetBufferMemoryRequirements2KHR: get_buffer_memory_requirements2,
+        vkGetImageMemoryRequirements2KHR: get_image_memory_requirements2,
+        vkBindBufferMemory2KHR: bind_buffer_memory2,
+        vkBindImageMemory2KHR: bind_image_memory2,
+        vkGetPhysicalDeviceMemoryProperties2KHR: get_physical_device_memory_properties2,
+        vkGetDeviceBufferMemoryRequirements: get_device_buffer_memory_requirements,
+        vkGetDeviceImageMemoryRequirements: get_device_image_memory_requirements,
+    }
+}
+
+fn align_up(value: vk::DeviceSize, alignment: vk::DeviceSize) -> vk::DeviceSize {
+    (value + alignment - 1) / alignment * alignment
+}
+
+/// Instance, physical device and device handles all point to the `MockDevice` itself.
+unsafe fn dispatchable_handle<H>(device: &Arc<MockDevice>) -> H {
+    debug_assert_eq!(mem::size_of::<H>(), mem::size_of::<usize>());
+    mem::transmute_copy(&(Arc::as_ptr(device) as usize))
+}
+
+unsafe fn device<'a, H>(handle: Nullable<H>) -> &'a MockDevice {
+    debug_assert_eq!(mem::size_of::<Nullable<H>>(), mem::size_of::<usize>());
+    let raw: usize = mem::transmute_copy(&handle);
+    &*(raw as *const MockDevice)
+}
+
+/// Non-dispatchable handles are boxed host objects.
+unsafe fn into_handle<T, H>(object: Box<T>) -> H {
+    debug_assert_eq!(mem::size_of::<H>(), mem::size_of::<u64>());
+    mem::transmute_copy(&(Box::into_raw(object) as usize as u64))
+}
+
+/// `H` is either a handle or a nullable handle, which have the same representation.
+unsafe fn object<'a, T, H>(handle: H) -> Option<&'a mut T> {
+    debug_assert_eq!(mem::size_of::<H>(), mem::size_of::<u64>());
+    let raw: u64 = mem::transmute_copy(&handle);
+    (raw as usize as *mut T).as_mut()
+}
+
+unsafe fn from_handle<T, H>(handle: H) -> Option<Box<T>> {
+    object::<T, H>(handle).map(|object| Box::from_raw(object))
+}
+
+/// Unwinding out of a Vulkan function into VMA is undefined behavior, so the functions that can
+/// panic, e.g. on an invalid handle, abort instead.
+fn abort_on_panic<R>(f: impl FnOnce() -> R) -> R {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(result) => result,
+        Err(_) => process::abort(),
+    }
+}
+
+unsafe extern "system" fn get_instance_proc_addr(
+    _instance: Nullable<vk::Instance>,
+    _p_name: *const c_char,
+) -> Option<FnVoidFunction> {
+    None
+}
+
+unsafe extern "system" fn get_device_proc_addr(
+    _device: Nullable<vk::Device>,
+    _p_name: *const c_char,
+) -> Option<FnVoidFunction> {
+    None
+}
+
+unsafe extern "system" fn get_physical_device_properties(
+    physical_device: Nullable<vk::PhysicalDevice>,
+    p_properties: *mut vk::PhysicalDeviceProperties,
+) {
+    *p_properties = device(physical_device).properties;
+}
+
+unsafe extern "system" fn get_physical_device_memory_properties(
+    physical_device: Nullable<vk::PhysicalDevice>,
+    p_memory_properties: *mut vk::PhysicalDeviceMemoryProperties,
+) {
+    *p_memory_properties = device(physical_device).memory_properties;
+}
+
+unsafe extern "system" fn get_physical_device_memory_properties2(
+    physical_device: Nullable<vk::PhysicalDevice>,
+    p_memory_properties: *mut vk::PhysicalDeviceMemoryProperties2,
+) {
+    (*p_memory_properties).memory_properties = device(physical_device).memory_properties;
+}
+
+unsafe extern "system" fn allocate_memory(
+    device_handle: Nullable<vk::Device>,
+    p_allocate_info: *const vk::MemoryAllocateInfo,
+    _p_allocator: *const vk::AllocationCallbacks,
+    p_memory: *mut vk::DeviceMemory,
+) -> vk::Result {
+    abort_on_panic(|| {
+        let device = device(device_handle);
+        let info = &*p_allocate_info;
+        if info.memory_type_index >= device.memory_properties.memory_type_count {
+            return vk::Result::ERROR_INITIALIZATION_FAILED;
+        }
+        let heap_index =
+            device.memory_properties.memory_types[info.memory_type_index as usize].heap_index;
+        let heap_size = device.memory_properties.memory_heaps[heap_index as usize].size;
+
+        let mut state = device.state.lock().unwrap();
+        if state.memory_count >= device.properties.limits.max_memory_allocation_count
+            || state.heap_usage[heap_index as usize] + info.allocation_size > heap_size
+        {
+            return vk::Result::ERROR_OUT_OF_DEVICE_MEMORY;
+        }
+
+        let layout = match Layout::from_size_align(
+            info.allocation_size.max(1) as usize,
+            MEMORY_BLOCK_ALIGNMENT,
+        ) {
+            Ok(layout) => layout,
+            Err(_) => return vk::Result::ERROR_OUT_OF_HOST_MEMORY,
+        };
+        let data = alloc::alloc_zeroed(layout);
+        if data.is_null() {
+            return vk::Result::ERROR_OUT_OF_HOST_MEMORY;
+        }
+
+        state.heap_usage[heap_index as usize] += info.allocation_size;
+        state.memory_count += 1;
+        *p_memory = into_handle(Box::new(MockMemory {
+            data,
+            size: info.allocation_size,
+            heap_index,
+        }));
+        vk::Result::SUCCESS
+    })
+}
+
+unsafe extern "system" fn free_memory(
+    device_handle: Nullable<vk::Device>,
+    memory: Nullable<vk::DeviceMemory>,
+    _p_allocator: *const vk::AllocationCallbacks,
+) {
+    abort_on_panic(|| {
+        if let Some(memory) = from_handle::<MockMemory, _>(memory) {
+            let device = device(device_handle);
+            let mut state = device.state.lock().unwrap();
+            state.heap_usage[memory.heap_index as usize] -= memory.size;
+            state.memory_count -= 1;
+            alloc::dealloc(
+                memory.data,
+                Layout::from_size_align_unchecked(
+                    memory.size.max(1) as usize,
+                    MEMORY_BLOCK_ALIGNMENT,
+                ),
+            );
+        }
+    })
+}
+
+unsafe extern "system" fn map_memory(
+    _device: Nullable<vk::Device>,
+    memory: Nullable<vk::DeviceMemory>,
+    offset: vk::DeviceSize,
+    _size: vk::DeviceSize,
+    _flags: vk::MemoryMapFlags,
+    pp_data: *mut *mut c_void,
+) -> vk::Result {
+    match object::<MockMemory, _>(memory) {
+        Some(memory) if offset < memory.size => {
+            *pp_data = memory.data.add(offset as usize) as *mut c_void;
+            vk::Result::SUCCESS
+        }
+        _ => vk::Result::ERROR_MEMORY_MAP_FAILED,
+    }
+}
+
+unsafe extern "system" fn unmap_memory(
+    _device: Nullable<vk::Device>,
+    _memory: Nullable<vk::DeviceMemory>,
+) {
+}
+
+/// Host memory is always coherent, so flushes and invalidations are only counted.
+unsafe extern "system" fn flush_mapped_memory_ranges(
+    device_handle: Nullable<vk::Device>,
+    memory_range_count: u32,
+    _p_memory_ranges: *const vk::MappedMemoryRange,
+) -> vk::Result {
+    abort_on_panic(|| {
+        device(device_handle).state.lock().unwrap().flush_count += memory_range_count;
+        vk::Result::SUCCESS
+    })
+}
+
+unsafe extern "system" fn invalidate_mapped_memory_ranges(
+    device_handle: Nullable<vk::Device>,
+    memory_range_count: u32,
+    _p_memory_ranges: *const vk::MappedMemoryRange,
+) -> vk::Result {
+    abort_on_panic(|| {
+        device(device_handle).state.lock().unwrap().invalidate_count += memory_range_count;
+        vk::Result::SUCCESS
+    })
+}
+
+unsafe extern "system" fn create_buffer(
+    device_handle: Nullable<vk::Device>,
+    p_create_info: *const vk::BufferCreateInfo,
+    _p_allocator: *const vk::AllocationCallbacks,
+    p_buffer: *mut vk::Buffer,
+) -> vk::Result {
+    abort_on_panic(|| {
+        let device = device(device_handle);
+        device.state.lock().unwrap().buffer_count += 1;
+        *p_buffer = into_handle(Box::new(MockBuffer {
+            size: (*p_create_info).size,
+            binding: MockBinding::default(),
+        }));
+        vk::Result::SUCCESS
+    })
+}
+
+unsafe extern "system" fn destroy_buffer(
+    device_handle: Nullable<vk::Device>,
+    buffer: Nullable<vk::Buffer>,
+    _p_allocator: *const vk::AllocationCallbacks,
+) {
+    abort_on_panic(|| {
+        if from_handle::<MockBuffer, _>(buffer).is_some() {
+            device(device_handle).state.lock().unwrap().buffer_count -= 1;
+        }
+    })
+}
+
+unsafe extern "system" fn create_image(
+    device_handle: Nullable<vk::Device>,
+    p_create_info: *const vk::ImageCreateInfo,
+    _p_allocator: *const vk::AllocationCallbacks,
+    p_image: *mut vk::Image,
+) -> vk::Result {
+    abort_on_panic(|| {
+        let device = device(device_handle);
+        let size = device.image_requirements(&*p_create_info).size;
+        device.state.lock().unwrap().image_count += 1;
+        *p_image = into_handle(Box::new(MockImage {
+            size,
+            binding: MockBinding::default(),
+        }));
+        vk::Result::SUCCESS
+    })
+}
+
+unsafe extern "system" fn destroy_image(
+    device_handle: Nullable<vk::Device>,
+    image: Nullable<vk::Image>,
+    _p_allocator: *const vk::AllocationCallbacks,
+) {
+    abort_on_panic(|| {
+        if from_handle::<MockImage, _>(image).is_some() {
+            device(device_handle).state.lock().unwrap().image_count -= 1;
+        }
+    })
+}
+
+unsafe extern "system" fn get_buffer_memory_requirements(
+    device_handle: Nullable<vk::Device>,
+    buffer: Nullable<vk::Buffer>,
+    p_memory_requirements: *mut vk::MemoryRequirements,
+) {
+    abort_on_panic(|| {
+        let device = device(device_handle);
+        let buffer = object::<MockBuffer, _>(buffer).expect("invalid mock buffer");
+        *p_memory_requirements = vk::MemoryRequirements {
+            size: align_up(buffer.size, BUFFER_ALIGNMENT),
+            alignment: BUFFER_ALIGNMENT,
+            memory_type_bits: device.memory_type_bits(),
+        };
+    })
+}
+
+unsafe extern "system" fn get_image_memory_requirements(
+    device_handle: Nullable<vk::Device>,
+    image: Nullable<vk::Image>,
+    p_memory_requirements: *mut vk::MemoryRequirements,
+) {
+    abort_on_panic(|| {
+        let device = device(device_handle);
+        let image = object::<MockImage, _>(image).expect("invalid mock image");
+        *p_memory_requirements = vk::MemoryRequirements {
+            size: image.size,
+            alignment: IMAGE_ALIGNMENT,
+            memory_type_bits: device.memory_type_bits(),
+        };
+    })
+}
+
+unsafe extern "system" fn get_buffer_memory_requirements2(
+    device_handle: Nullable<vk::Device>,
+    p_info: *const vk::BufferMemoryRequirementsInfo2,
+    p_memory_requirements: *mut vk::MemoryRequirements2,
+) {
+    get_buffer_memory_requirements(
+        device_handle,
+        (*p_info).buffer,
+        &mut (*p_memory_requirements).memory_requirements,
+    );
+}
+
+unsafe extern "system" fn get_image_memory_requirements2(
+    device_handle: Nullable<vk::Device>,
+    p_info: *const vk::ImageMemoryRequirementsInfo2,
+    p_memory_requirements: *mut vk::MemoryRequirements2,
+) {
+    get_image_memory_requirements(
+        device_handle,
+        (*p_info).image,
+        &mut (*p_memory_requirements).memory_requirements,
+    );
+}
+
+unsafe extern "system" fn get_device_buffer_memory_requirements(
+    device_handle: Nullable<vk::Device>,
+    p_info: *const vk::DeviceBufferMemoryRequirements,
+    p_memory_requirements: *mut vk::MemoryRequirements2,
+) {
+    abort_on_panic(|| {
+        (*p_memory_requirements).memory_requirements =
+            device(device_handle).buffer_requirements(&*(*p_info).p_create_info);
+    })
+}
+
+unsafe extern "system" fn get_device_image_memory_requirements(
+    device_handle: Nullable<vk::Device>,
+    p_info: *const vk::DeviceImageMemoryRequirements,
+    p_memory_requirements: *mut vk::MemoryRequirements2,
+) {
+    abort_on_panic(|| {
+        (*p_memory_requirements).memory_requirements =
+            device(device_handle).image_requirements(&*(*p_info).p_create_info);
+    })
+}
+
+unsafe fn bind_memory(
+    binding: &mut MockBinding,
+    required_size: vk::DeviceSize,
+    memory: Nullable<vk::DeviceMemory>,
+    memory_offset: vk::DeviceSize,
+) -> vk::Result {
+    match object::<MockMemory, _>(memory) {
+        Some(mock_memory) if memory_offset + required_size <= mock_memory.size => {
+            binding.memory = memory;
+            binding.offset = memory_offset;
+            vk::Result::SUCCESS
+        }
+        _ => vk::Result::ERROR_OUT_OF_DEVICE_MEMORY,
+    }
+}
+
+unsafe extern "system" fn bind_buffer_memory(
+    _device: Nullable<vk::Device>,
+    buffer: Nullable<vk::Buffer>,
+    memory: Nullable<vk::DeviceMemory>,
+    memory_offset: vk::DeviceSize,
+) -> vk::Result {
+    abort_on_panic(|| {
+        let buffer = object::<MockBuffer, _>(buffer).expect("invalid mock buffer");
+        bind_memory(&mut buffer.binding, buffer.size, memory, memory_offset)
+    })
+}
+
+unsafe extern "system" fn bind_image_memory(
+    _device: Nullable<vk::Device>,
+    image: Nullable<vk::Image>,
+    memory: Nullable<vk::DeviceMemory>,
+    memory_offset: vk::DeviceSize,
+) -> vk::Result {
+    abort_on_panic(|| {
+        let image = object::<MockImage, _>(image).expect("invalid mock image");
+        bind_memory(&mut image.binding, image.size, memory, memory_offset)
+    })
+}
+
+unsafe extern "system" fn bind_buffer_memory2(
+    device_handle: Nullable<vk::Device>,
+    bind_info_count: u32,
+    p_bind_infos: *const vk::BindBufferMemoryInfo,
+) -> vk::Result {
+    for info in std::slice::from_raw_parts(p_bind_infos, bind_info_count as usize) {
+        let result =
+            bind_buffer_memory(device_handle, info.buffer, info.memory, info.memory_offset);
+        if result != vk::Result::SUCCESS {
+            return result;
+        }
+    }
+    vk::Result::SUCCESS
+}
+
+unsafe extern "system" fn bind_image_memory2(
+    device_handle: Nullable<vk::Device>,
+    bind_info_count: u32,
+    p_bind_infos: *const vk::BindImageMemoryInfo,
+) -> vk::Result {
+    for info in std::slice::from_raw_parts(p_bind_infos, bind_info_count as usize) {
+        let result = bind_image_memory(device_handle, info.image, info.memory, info.memory_offset);
+        if result != vk::Result::SUCCESS {
+            return result;
+        }
+    }
+    vk::Result::SUCCESS
+}
+
+/// Copies between the host blocks bound to both buffers immediately, there is no queue to submit to.
+unsafe extern "system" fn cmd_copy_buffer(
+    _command_buffer: Nullable<vk::CommandBuffer>,
+    src_buffer: Nullable<vk::Buffer>,
+    dst_buffer: Nullable<vk::Buffer>,
+    region_count: u32,
+    p_regions: *const vk::BufferCopy,
+) {
+    abort_on_panic(|| {
+        let src = object::<MockBuffer, _>(src_buffer).map(|buffer| &buffer.binding);
+        let dst = object::<MockBuffer, _>(dst_buffer).map(|buffer| &buffer.binding);
+        if let (Some(src), Some(dst)) = (src, dst) {
+            let src_memory = object::<MockMemory, _>(src.memory).expect("unbound mock buffer");
+            let dst_memory = object::<MockMemory, _>(dst.memory).expect("unbound mock buffer");
+            for region in std::slice::from_raw_parts(p_regions, region_count as usize) {
+                ptr::copy(
+                    src_memory
+                        .data
+                        .add((src.offset + region.src_offset) as usize),
+                    dst_memory
+                        .data
+                        .add((dst.offset + region.dst_offset) as usize),
+                    region.size as usize,
+                );
+            }
+        }
+    })
+}
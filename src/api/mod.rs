@@ -0,0 +1,1057 @@
+//! Allocator API shared by the backends, compiled once by `vk_mem::spark` and once by
+//! `vk_mem::ash` against their own `vk` types.
+
+mod backend;
+mod budget_monitor;
+mod definitions;
+mod defragmentation;
+mod device_memory;
+mod error;
+pub(crate) mod ffi;
+mod host_allocator;
+#[cfg(feature = "tracing")]
+mod instrumentation;
+mod leak;
+mod mapped;
+mod metrics;
+#[cfg(feature = "mock")]
+pub mod mock;
+mod pool;
+#[cfg(feature = "recording")]
+mod recording;
+mod resource;
+mod statistics;
+#[cfg(feature = "track_allocations")]
+mod tracking;
+mod user_data;
+mod virtual_block;
+pub use backend::*;
+pub use budget_monitor::*;
+pub use definitions::*;
+pub use defragmentation::*;
+pub use error::*;
+pub use host_allocator::*;
+pub use leak::*;
+pub use mapped::*;
+pub use metrics::*;
+pub use pool::*;
+#[cfg(feature = "recording")]
+pub use recording::*;
+pub use resource::*;
+pub use statistics::*;
+#[cfg(feature = "track_allocations")]
+pub use tracking::*;
+pub use virtual_block::*;
+
+use super::{ffi_types, vk, FnVoidFunction, Nullable};
+
+use device_memory::DeviceMemoryCallbacks;
+use error::ResultExt;
+use leak::LeakDetection;
+#[cfg(feature = "recording")]
+use recording::handle;
+use std::any::Any;
+use std::ffi::{CStr, CString};
+use std::mem::MaybeUninit;
+use std::mem::{self, transmute};
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// Main allocator object
+pub struct Allocator {
+    /// Pointer to internal VmaAllocator instance
+    internal: ffi::VmaAllocator,
+    /// Identity used to reject allocations created by other allocators
+    id: AllocatorId,
+    /// Closures referenced by VMA through `pDeviceMemoryCallbacks`, kept alive until destruction
+    _device_memory_callbacks: Option<Box<DeviceMemoryCallbacks>>,
+    /// Adapter referenced by VMA through `pAllocationCallbacks`, kept alive until destruction
+    _host_allocation_callbacks: Option<Arc<dyn Any + Send + Sync>>,
+    /// Software device backing this allocator, if it was created with `Allocator::new_mock`
+    _mock_device: Option<Arc<dyn Any + Send + Sync>>,
+    /// Values attached with `Allocator::set_user_data`, dropped when their allocation is freed
+    user_data: user_data::UserDataMap,
+    /// Live allocations, tracked if a `LeakHandler` was set
+    leak_detection: Option<LeakDetection>,
+    /// Call sites of live allocations, see `Allocator::live_allocations`
+    #[cfg(feature = "track_allocations")]
+    allocation_registry: tracking::AllocationRegistry,
+    /// Receives every call made on this allocator, see `AllocatorCreateInfo::recorder`
+    #[cfg(feature = "recording")]
+    recorder: Option<Arc<Recorder>>,
+}
+
+// Allocator is internally thread safe unless AllocatorCreateFlags::EXTERNALLY_SYNCHRONIZED is used (then you need to add synchronization!)
+unsafe impl Send for Allocator {}
+unsafe impl Sync for Allocator {}
+
+/// Represents single memory allocation.
+///
+/// It may be either dedicated block of `vk::DeviceMemory` or a specific region of a
+/// bigger block of this type plus unique offset.
+///
+/// Although the library provides convenience functions that create a Vulkan buffer or image,
+/// allocate memory for it and bind them together, binding of the allocation to a buffer or an
+/// image is out of scope of the allocation itself.
+///
+/// Allocation object can exist without buffer/image bound, binding can be done manually by
+/// the user, and destruction of it can be done independently of destruction of the allocation.
+///
+/// The object also remembers its size and some other information. To retrieve this information,
+/// use `Allocator::get_allocation_info`.
+///
+/// Some kinds allocations can be in lost state.
+///
+/// Every allocation remembers the `AllocatorId` of the allocator that created it. Passing it
+/// to any other allocator panics instead of corrupting that allocator's internal state.
+#[derive(Debug)]
+pub struct Allocation(ffi::VmaAllocation, AllocatorId);
+unsafe impl Send for Allocation {}
+unsafe impl Sync for Allocation {}
+
+/// Process-wide unique identity of an `Allocator`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub struct AllocatorId(u64);
+
+impl AllocatorId {
+    fn next() -> Self {
+        AllocatorId(crate::next_allocator_id())
+    }
+}
+
+impl Allocation {
+    #[track_caller]
+    pub(crate) fn new(raw: ffi::VmaAllocation, allocator: &Allocator, pool: PoolHandle) -> Self {
+        if let Some(leak_detection) = &allocator.leak_detection {
+            leak_detection.insert(raw as usize, pool.0 as usize);
+        }
+        #[cfg(feature = "track_allocations")]
+        allocator.track_allocation(raw, pool, std::panic::Location::caller());
+        Allocation(raw, allocator.id)
+    }
+
+    /// Identity of the allocator this allocation was created by.
+    pub fn allocator_id(&self) -> AllocatorId {
+        self.1
+    }
+}
+
+impl Allocator {
+    /// Constructor a new `Allocator` using the provided options.
+    pub fn new<I, D>(mut create_info: AllocatorCreateInfo<I, D>) -> Result<Self>
+    where
+        I: Deref<Target = <D::Target as Backend>::Instance>,
+        D: Deref,
+        D::Target: Backend,
+    {
+        #[cfg(feature = "loaded")]
+        let routed_functions = create_info.device.vulkan_functions(&create_info.instance);
+        #[cfg(feature = "loaded")]
+        {
+            create_info.inner.pVulkanFunctions = &routed_functions;
+        }
+        unsafe { Self::from_create_info(create_info, None) }
+    }
+
+    /// Creates the internal VmaAllocator instance from a fully populated create info.
+    ///
+    /// `create_info.inner.pVulkanFunctions` only has to stay valid for the duration of this call,
+    /// VMA copies the function table internally. The same goes for the callback table built
+    /// from `create_info.device_memory_callbacks`, but the closures themselves, as well as
+    /// the host allocation callbacks, are stored in the allocator.
+    pub(crate) unsafe fn from_create_info<I, D>(
+        create_info: AllocatorCreateInfo<I, D>,
+        mock_device: Option<Arc<dyn Any + Send + Sync>>,
+    ) -> Result<Self> {
+        let mut inner = create_info.inner;
+        let device_memory_callbacks = create_info.device_memory_callbacks;
+        let ffi_callbacks = device_memory_callbacks
+            .as_ref()
+            .map(|callbacks| callbacks.to_ffi());
+        if let Some(ffi_callbacks) = &ffi_callbacks {
+            inner.pDeviceMemoryCallbacks = ffi_callbacks;
+        }
+
+        let mut internal: ffi::VmaAllocator = mem::zeroed();
+        ffi::vmaCreateAllocator(&inner, &mut internal)
+            .result()
+            .context("vmaCreateAllocator")?;
+
+        let allocator = Allocator {
+            internal,
+            id: AllocatorId::next(),
+            _device_memory_callbacks: device_memory_callbacks,
+            _host_allocation_callbacks: create_info.host_allocation_callbacks,
+            _mock_device: mock_device,
+            user_data: Default::default(),
+            leak_detection: create_info.leak_handler.map(LeakDetection::new),
+            #[cfg(feature = "track_allocations")]
+            allocation_registry: Default::default(),
+            #[cfg(feature = "recording")]
+            recorder: create_info.recorder,
+        };
+        #[cfg(feature = "recording")]
+        allocator.record(|| Call::CreateAllocator {
+            flags: AllocatorCreateFlags::from_bits_truncate(inner.flags),
+            preferred_large_heap_block_size: inner.preferredLargeHeapBlockSize,
+            memory_properties: Box::new(*allocator.get_memory_properties()),
+        });
+        Ok(allocator)
+    }
+
+    /// Identity of this allocator, matching `Allocation::allocator_id` of allocations created by it.
+    pub fn id(&self) -> AllocatorId {
+        self.id
+    }
+
+    /// Returns the internal handle of `allocation`.
+    ///
+    /// Panics if the allocation was created by a different allocator.
+    pub(crate) fn raw_allocation(&self, allocation: &Allocation) -> ffi::VmaAllocation {
+        assert_eq!(
+            allocation.1, self.id,
+            "allocation was created by a different allocator"
+        );
+        allocation.0
+    }
+
+    /// The allocator fetches `vk::PhysicalDeviceProperties` from the physical device.
+    /// You can get it here, without fetching it again on your own.
+    pub unsafe fn get_physical_device_properties(&self) -> Result<vk::PhysicalDeviceProperties> {
+        let mut properties: *const vk::PhysicalDeviceProperties = std::ptr::null();
+        ffi::vmaGetPhysicalDeviceProperties(self.internal, &mut properties);
+
+        Ok(*properties)
+    }
+
+    /// The allocator fetches `vk::PhysicalDeviceMemoryProperties` from the physical device.
+    /// You can get it here, without fetching it again on your own.
+    pub unsafe fn get_memory_properties(&self) -> &vk::PhysicalDeviceMemoryProperties {
+        let mut properties: *const vk::PhysicalDeviceMemoryProperties = std::ptr::null();
+        ffi::vmaGetMemoryProperties(self.internal, &mut properties);
+
+        &*properties
+    }
+
+    /// Returns the Vulkan handles this allocator was created with.
+    pub fn info(&self) -> AllocatorInfo {
+        unsafe {
+            let mut info = MaybeUninit::uninit();
+            ffi::vmaGetAllocatorInfo(self.internal, info.as_mut_ptr());
+            info.assume_init().into()
+        }
+    }
+
+    /// Given memory type index, returns property flags of this memory type.
+    ///
+    /// This is just a convenience function, same information can be obtained using
+    /// `Allocator::get_memory_properties`.
+    ///
+    /// Panics if `memory_type_index` is not less than `memory_type_count`.
+    pub fn memory_type_properties(&self, memory_type_index: u32) -> vk::MemoryPropertyFlags {
+        let memory_type_count = unsafe { self.get_memory_properties().memory_type_count };
+        assert!(
+            memory_type_index < memory_type_count,
+            "memory type index {} out of range, the device has {} memory types",
+            memory_type_index,
+            memory_type_count
+        );
+        let mut flags = vk::MemoryPropertyFlags::empty();
+        unsafe {
+            ffi::vmaGetMemoryTypeProperties(self.internal, memory_type_index, &mut flags);
+        }
+        flags
+    }
+
+    /// Sets index of the current frame.
+    ///
+    /// This function must be used if you make allocations with `AllocationCreateFlags::CAN_BECOME_LOST` and
+    /// `AllocationCreateFlags::CAN_MAKE_OTHER_LOST` flags to inform the allocator when a new frame begins.
+    /// Allocations queried using `Allocator::get_allocation_info` cannot become lost
+    /// in the current frame.
+    pub unsafe fn set_current_frame_index(&self, frame_index: u32) {
+        ffi::vmaSetCurrentFrameIndex(self.internal, frame_index);
+        #[cfg(feature = "recording")]
+        self.record(|| Call::SetCurrentFrameIndex { frame_index });
+    }
+
+    /// Retrieves statistics from current state of the `Allocator`.
+    ///
+    /// Per memory type and per heap statistics only cover the types and heaps that exist
+    /// on the physical device.
+    pub fn calculate_statistics(&self) -> Result<TotalStatistics> {
+        unsafe {
+            let mut vma_stats: ffi::VmaTotalStatistics = mem::zeroed();
+            ffi::vmaCalculateStatistics(self.internal, &mut vma_stats);
+            let memory_properties = self.get_memory_properties();
+            Ok(TotalStatistics::from_ffi(
+                &vma_stats,
+                memory_properties.memory_type_count,
+                memory_properties.memory_heap_count,
+            ))
+        }
+    }
+
+    /// Retrieves information about current memory usage and budget for all memory heaps.
+    ///
+    /// This function is called "get" not "calculate" because it is very fast, suitable to be called
+    /// every frame or every allocation. For more detailed statistics use vmaCalculateStatistics().
+    ///
+    /// Note that when using allocator from multiple threads, returned information may immediately
+    /// become outdated.
+    pub fn get_heap_budgets(&self) -> Result<Vec<Budget>> {
+        unsafe {
+            let len = self.get_memory_properties().memory_heap_count as usize;
+            let mut vma_budgets: Vec<ffi::VmaBudget> = Vec::with_capacity(len);
+            ffi::vmaGetHeapBudgets(self.internal, vma_budgets.as_mut_ptr());
+            vma_budgets.set_len(len);
+            Ok(vma_budgets.iter().map(Budget::from).collect())
+        }
+    }
+
+    /// Builds a string in JSON format with the current state of the allocator.
+    ///
+    /// With `detailed_map` the dump also lists every block together with the allocations and
+    /// free ranges inside it. See `StatsDump` (with the `stats_dump` feature) for a parsed form.
+    pub fn build_stats_string(&self, detailed_map: bool) -> String {
+        unsafe {
+            let mut stats_string: *mut std::os::raw::c_char = std::ptr::null_mut();
+            ffi::vmaBuildStatsString(self.internal, &mut stats_string, detailed_map as vk::Bool32);
+            let result = CStr::from_ptr(stats_string).to_string_lossy().into_owned();
+            ffi::vmaFreeStatsString(self.internal, stats_string);
+            result
+        }
+    }
+
+    /// Drops the state kept for `allocation` once VMA has freed it.
+    fn forget_allocation(&self, allocation: ffi::VmaAllocation) {
+        self.user_data.remove(allocation);
+        #[cfg(feature = "track_allocations")]
+        self.allocation_registry.remove(allocation);
+        if let Some(leak_detection) = &self.leak_detection {
+            leak_detection.remove(allocation as usize);
+        }
+    }
+
+    /// Reports the live allocations of `pool`, or all of them, to the `LeakHandler` and frees
+    /// them. Returns the report if it has to be raised as a panic by the caller.
+    pub(crate) fn free_leaked_allocations(
+        &self,
+        owner: &'static str,
+        pool: Option<PoolHandle>,
+    ) -> Option<LeakReport> {
+        let leak_detection = self.leak_detection.as_ref()?;
+        let leaked = leak_detection.take(pool.map(|pool| pool.0 as usize));
+        let allocations = leaked
+            .iter()
+            .map(|&allocation| unsafe {
+                let allocation = allocation as ffi::VmaAllocation;
+                let mut info = mem::zeroed();
+                ffi::vmaGetAllocationInfo(self.internal, allocation, &mut info);
+                let info = AllocationInfo::from(&info);
+                LeakedAllocation {
+                    offset: info.offset,
+                    size: info.size,
+                    memory_type: Some(info.memory_type),
+                    name: info.name,
+                    user_data: info.user_data,
+                    user_data_type: self.user_data.type_name(allocation),
+                    #[cfg(feature = "track_allocations")]
+                    location: self.allocation_registry.location(allocation),
+                    #[cfg(not(feature = "track_allocations"))]
+                    location: None,
+                }
+            })
+            .collect();
+        let leak_report = leak_detection.report(owner, allocations);
+        for allocation in leaked {
+            let allocation = allocation as ffi::VmaAllocation;
+            unsafe { ffi::vmaFreeMemory(self.internal, allocation) };
+            self.forget_allocation(allocation);
+        }
+        leak_report
+    }
+
+    /// Frees memory previously allocated using `Allocator::allocate_memory`,
+    /// `Allocator::allocate_memory_for_buffer`, or `Allocator::allocate_memory_for_image`.
+    pub unsafe fn free_memory(&self, allocation: Allocation) {
+        let allocation = self.raw_allocation(&allocation);
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(target: "vk_mem", "vmaFreeMemory").entered();
+        #[cfg(feature = "tracing")]
+        self.trace_free("vmaFreeMemory", &[allocation]);
+        ffi::vmaFreeMemory(self.internal, allocation);
+        self.forget_allocation(allocation);
+        #[cfg(feature = "recording")]
+        self.record(|| Call::FreeMemory {
+            allocations: vec![handle(allocation)],
+        });
+    }
+
+    /// Frees memory and destroys multiple allocations.
+    ///
+    /// Word "pages" is just a suggestion to use this function to free pieces of memory used for sparse binding.
+    /// It is just a general purpose function to free memory and destroy allocations made using e.g. `Allocator::allocate_memory',
+    /// 'Allocator::allocate_memory_pages` and other functions.
+    ///
+    /// It may be internally optimized to be more efficient than calling 'Allocator::free_memory` `allocations.len()` times.
+    ///
+    /// Allocations in 'allocations' slice can come from any memory pools and types.
+    pub unsafe fn free_memory_pages(&self, allocations: &[Allocation]) {
+        let allocations: Vec<ffi::VmaAllocation> =
+            allocations.iter().map(|a| self.raw_allocation(a)).collect();
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(
+            target: "vk_mem",
+            "vmaFreeMemoryPages",
+            allocation_count = allocations.len()
+        )
+        .entered();
+        #[cfg(feature = "tracing")]
+        self.trace_free("vmaFreeMemoryPages", &allocations);
+        ffi::vmaFreeMemoryPages(
+            self.internal,
+            allocations.len(),
+            allocations.as_ptr() as *mut _,
+        );
+        for &allocation in &allocations {
+            self.forget_allocation(allocation);
+        }
+        #[cfg(feature = "recording")]
+        self.record(|| Call::FreeMemory {
+            allocations: allocations
+                .iter()
+                .map(|&allocation| handle(allocation))
+                .collect(),
+        });
+    }
+
+    /// Returns current information about specified allocation and atomically marks it as used in current frame.
+    ///
+    /// Current parameters of given allocation are returned in the result object, available through accessors.
+    ///
+    /// This function also atomically "touches" allocation - marks it as used in current frame,
+    /// just like `Allocator::touch_allocation`.
+    ///
+    /// If the allocation is in lost state, `allocation.get_device_memory` returns `vk::DeviceMemory::null()`.
+    ///
+    /// Although this function uses atomics and doesn't lock any mutex, so it should be quite efficient,
+    /// you can avoid calling it too often.
+    ///
+    /// If you just want to check if allocation is not lost, `Allocator::touch_allocation` will work faster.
+    pub unsafe fn get_allocation_info(&self, allocation: &Allocation) -> Result<AllocationInfo> {
+        let mut allocation_info = MaybeUninit::zeroed();
+        ffi::vmaGetAllocationInfo(
+            self.internal,
+            self.raw_allocation(allocation),
+            allocation_info.as_mut_ptr(),
+        );
+        Ok(allocation_info.assume_init().into())
+    }
+
+    /// Given an allocation, returns property flags of its memory type.
+    ///
+    /// This is just a convenience function, same information can be obtained using
+    /// `Allocator::get_allocation_info` and `Allocator::get_memory_properties`. It is useful
+    /// with `MemoryUsage::Auto`, where the memory type is chosen dynamically, e.g. to decide
+    /// whether the allocation can be written directly or needs a staging copy.
+    pub fn get_allocation_memory_properties(
+        &self,
+        allocation: &Allocation,
+    ) -> vk::MemoryPropertyFlags {
+        let mut flags = vk::MemoryPropertyFlags::empty();
+        unsafe {
+            ffi::vmaGetAllocationMemoryProperties(
+                self.internal,
+                self.raw_allocation(allocation),
+                &mut flags,
+            );
+        }
+        flags
+    }
+
+    /// Sets user data in given allocation to new value.
+    ///
+    /// If the allocation was created with `AllocationCreateFlags::USER_DATA_COPY_STRING`,
+    /// `user_data` must be either null, or pointer to a null-terminated string. The function
+    /// makes local copy of the string and sets it as allocation's user data. String
+    /// passed as user data doesn't need to be valid for whole lifetime of the allocation -
+    /// you can free it after this call. String previously pointed by allocation's
+    /// user data is freed from memory.
+    ///
+    /// If the flag was not used, the value of pointer `user_data` is just copied to
+    /// allocation's user data. It is opaque, so you can use it however you want - e.g.
+    /// as a pointer, ordinal number or some handle to you own data.
+    pub unsafe fn set_allocation_user_data(
+        &self,
+        allocation: &mut Allocation,
+        user_data: *mut ::std::os::raw::c_void,
+    ) {
+        ffi::vmaSetAllocationUserData(self.internal, self.raw_allocation(allocation), user_data);
+    }
+
+    /// Sets name of given allocation to new value.
+    ///
+    /// The name is copied, so the string doesn't need to outlive this call. It is returned in
+    /// `AllocationInfo::name` and printed next to the allocation in detailed stats dumps made with
+    /// `vmaBuildStatsString`, which helps to identify which resource owns a memory block.
+    ///
+    /// Panics if `name` contains a nul byte.
+    pub fn set_allocation_name(&self, allocation: &Allocation, name: &str) {
+        let name = CString::new(name).expect("allocation name contains a nul byte");
+        let allocation = self.raw_allocation(allocation);
+        unsafe {
+            ffi::vmaSetAllocationName(self.internal, allocation, name.as_ptr());
+        }
+        #[cfg(feature = "recording")]
+        self.record(|| Call::SetAllocationName {
+            allocation: handle(allocation),
+            name: name.to_string_lossy().into_owned(),
+        });
+    }
+
+    /// Maps memory represented by given allocation and returns pointer to it.
+    ///
+    /// Maps memory represented by given allocation to make it accessible to CPU code.
+    /// When succeeded, result is a pointer to first byte of this memory.
+    ///
+    /// If the allocation is part of bigger `vk::DeviceMemory` block, the pointer is
+    /// correctly offseted to the beginning of region assigned to this particular
+    /// allocation.
+    ///
+    /// Mapping is internally reference-counted and synchronized, so despite raw Vulkan
+    /// function `vk::Device::MapMemory` cannot be used to map same block of
+    /// `vk::DeviceMemory` multiple times simultaneously, it is safe to call this
+    /// function on allocations assigned to the same memory block. Actual Vulkan memory
+    /// will be mapped on first mapping and unmapped on last unmapping.
+    ///
+    /// If the function succeeded, you must call `Allocator::unmap_memory` to unmap the
+    /// allocation when mapping is no longer needed or before freeing the allocation, at
+    /// the latest.
+    ///
+    /// It also safe to call this function multiple times on the same allocation. You
+    /// must call `Allocator::unmap_memory` same number of times as you called
+    /// `Allocator::map_memory`.
+    ///
+    /// It is also safe to call this function on allocation created with
+    /// `AllocationCreateFlags::MAPPED` flag. Its memory stays mapped all the time.
+    /// You must still call `Allocator::unmap_memory` same number of times as you called
+    /// `Allocator::map_memory`. You must not call `Allocator::unmap_memory` additional
+    /// time to free the "0-th" mapping made automatically due to `AllocationCreateFlags::MAPPED` flag.
+    ///
+    /// This function fails when used on allocation made in memory type that is not
+    /// `vk::MemoryPropertyFlags::HOST_VISIBLE`.
+    ///
+    /// This function always fails when called for allocation that was created with
+    /// `AllocationCreateFlags::CAN_BECOME_LOST` flag. Such allocations cannot be mapped.
+    pub unsafe fn map_memory(&self, allocation: &mut Allocation) -> Result<*mut u8> {
+        self.map_raw(self.raw_allocation(allocation))
+    }
+
+    /// `Allocator::map_memory` for a raw allocation, also used by the mapping helpers that
+    /// only borrow the allocation.
+    unsafe fn map_raw(&self, allocation: ffi::VmaAllocation) -> Result<*mut u8> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(target: "vk_mem", "vmaMapMemory").entered();
+        let mut mapped_data: *mut ::std::os::raw::c_void = ::std::ptr::null_mut();
+        let result = ffi::vmaMapMemory(self.internal, allocation, &mut mapped_data)
+            .result()
+            .context("vmaMapMemory");
+        #[cfg(feature = "tracing")]
+        instrumentation::trace_result("vmaMapMemory", &result);
+        #[cfg(feature = "recording")]
+        self.record(|| Call::MapMemory {
+            allocation: handle(allocation),
+            result: CallResult::from(&result),
+        });
+        result?;
+
+        Ok(mapped_data as *mut u8)
+    }
+
+    /// Unmaps memory represented by given allocation, mapped previously using `Allocator::map_memory`.
+    pub unsafe fn unmap_memory(&self, allocation: &mut Allocation) {
+        self.unmap_raw(self.raw_allocation(allocation));
+    }
+
+    /// `Allocator::unmap_memory` for a raw allocation.
+    unsafe fn unmap_raw(&self, allocation: ffi::VmaAllocation) {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(target: "vk_mem", "vmaUnmapMemory").entered();
+        ffi::vmaUnmapMemory(self.internal, allocation);
+        #[cfg(feature = "tracing")]
+        instrumentation::trace_result("vmaUnmapMemory", &Ok(()));
+        #[cfg(feature = "recording")]
+        self.record(|| Call::UnmapMemory {
+            allocation: handle(allocation),
+        });
+    }
+
+    /// Flushes memory of given allocation.
+    ///
+    /// Calls `vk::Device::FlushMappedMemoryRanges` for memory associated with given range of given allocation.
+    ///
+    /// - `offset` must be relative to the beginning of allocation.
+    /// - `size` can be `vk::WHOLE_SIZE`. It means all memory from `offset` the the end of given allocation.
+    /// - `offset` and `size` don't have to be aligned; hey are internally rounded down/up to multiple of `nonCoherentAtomSize`.
+    /// - If `size` is 0, this call is ignored.
+    /// - If memory type that the `allocation` belongs to is not `vk::MemoryPropertyFlags::HOST_VISIBLE` or it is `vk::MemoryPropertyFlags::HOST_COHERENT`, this call is ignored.
+    pub fn flush_allocation(
+        &self,
+        allocation: &Allocation,
+        offset: usize,
+        size: usize,
+    ) -> Result<()> {
+        let allocation = self.raw_allocation(allocation);
+        let result = unsafe {
+            ffi::vmaFlushAllocation(
+                self.internal,
+                allocation,
+                offset as vk::DeviceSize,
+                size as vk::DeviceSize,
+            )
+            .result()
+            .context("vmaFlushAllocation")
+        };
+        #[cfg(feature = "recording")]
+        self.record(|| Call::FlushAllocation {
+            allocation: handle(allocation),
+            offset: offset as vk::DeviceSize,
+            size: size as vk::DeviceSize,
+            result: CallResult::from(&result),
+        });
+        result
+    }
+
+    /// Invalidates memory of given allocation.
+    ///
+    /// Calls `vk::Device::invalidate_mapped_memory_ranges` for memory associated with given range of given allocation.
+    ///
+    /// - `offset` must be relative to the beginning of allocation.
+    /// - `size` can be `vk::WHOLE_SIZE`. It means all memory from `offset` the the end of given allocation.
+    /// - `offset` and `size` don't have to be aligned. They are internally rounded down/up to multiple of `nonCoherentAtomSize`.
+    /// - If `size` is 0, this call is ignored.
+    /// - If memory type that the `allocation` belongs to is not `vk::MemoryPropertyFlags::HOST_VISIBLE` or it is `vk::MemoryPropertyFlags::HOST_COHERENT`, this call is ignored.
+    pub fn invalidate_allocation(
+        &self,
+        allocation: &Allocation,
+        offset: usize,
+        size: usize,
+    ) -> Result<()> {
+        let allocation = self.raw_allocation(allocation);
+        let result = unsafe {
+            ffi::vmaInvalidateAllocation(
+                self.internal,
+                allocation,
+                offset as vk::DeviceSize,
+                size as vk::DeviceSize,
+            )
+            .result()
+            .context("vmaInvalidateAllocation")
+        };
+        #[cfg(feature = "recording")]
+        self.record(|| Call::InvalidateAllocation {
+            allocation: handle(allocation),
+            offset: offset as vk::DeviceSize,
+            size: size as vk::DeviceSize,
+            result: CallResult::from(&result),
+        });
+        result
+    }
+
+    /// Checks magic number in margins around all allocations in given memory types (in both default and custom pools) in search for corruptions.
+    ///
+    /// `memory_type_bits` bit mask, where each bit set means that a memory type with that index should be checked.
+    ///
+    /// Corruption detection is enabled only with the `detect_corruption` feature, which defines
+    /// `VMA_DEBUG_DETECT_CORRUPTION` and a nonzero `VMA_DEBUG_MARGIN`, and only for memory types that are `HOST_VISIBLE` and `HOST_COHERENT`.
+    ///
+    /// Possible error values:
+    ///
+    /// - `Error::FeatureNotEnabled` - corruption detection is not enabled for any of specified memory types.
+    /// - `Error::CorruptionDetected` - corruption detection has been performed and found memory corruptions around one of the allocations.
+    ///   `VMA_ASSERT` is also fired in that case.
+    /// - Other value: Error returned by Vulkan, e.g. memory mapping failure.
+    pub unsafe fn check_corruption(&self, memory_types: vk::MemoryPropertyFlags) -> Result<()> {
+        let memory_types = transmute::<vk::MemoryPropertyFlags, u32>(memory_types);
+        let result = ffi::vmaCheckCorruption(self.internal, memory_types)
+            .result()
+            .map_err(|result| Error::corruption_check("vmaCheckCorruption", result));
+        #[cfg(feature = "recording")]
+        self.record(|| Call::CheckCorruption {
+            memory_types,
+            result: CallResult::from(&result),
+        });
+        result
+    }
+
+    /// Binds buffer to allocation.
+    ///
+    /// Binds specified buffer to region of memory represented by specified allocation.
+    /// Gets `vk::DeviceMemory` handle and offset from the allocation.
+    ///
+    /// If you want to create a buffer, allocate memory for it and bind them together separately,
+    /// you should use this function for binding instead of `vk::Device::bind_buffer_memory`,
+    /// because it ensures proper synchronization so that when a `vk::DeviceMemory` object is
+    /// used by multiple allocations, calls to `vk::Device::bind_buffer_memory()` or
+    /// `vk::Device::map_memory()` won't happen from multiple threads simultaneously
+    /// (which is illegal in Vulkan).
+    ///
+    /// It is recommended to use function `Allocator::create_buffer` instead of this one.
+    pub unsafe fn bind_buffer_memory(
+        &self,
+        allocation: &Allocation,
+        buffer: vk::Buffer,
+    ) -> Result<()> {
+        let allocation = self.raw_allocation(allocation);
+        let result = ffi::vmaBindBufferMemory(self.internal, allocation, buffer)
+            .result()
+            .context("vmaBindBufferMemory");
+        #[cfg(feature = "recording")]
+        self.record(|| Call::BindBufferMemory {
+            allocation: handle(allocation),
+            allocation_local_offset: 0,
+            result: CallResult::from(&result),
+        });
+        result
+    }
+
+    /// Binds buffer to allocation with additional parameters.
+    ///
+    /// * `allocation`
+    /// * `allocation_local_offset` - Additional offset to be added while binding, relative to the beginning of the `allocation`. Normally it should be 0.
+    /// * `buffer`
+    /// * `next` - A chain of structures to be attached to `VkBindImageMemoryInfoKHR` structure used internally. Normally it should be null.
+    ///
+    /// This function is similar to vmaBindImageMemory(), but it provides additional parameters.
+    ///
+    /// If `pNext` is not null, #VmaAllocator object must have been created with #VMA_ALLOCATOR_CREATE_KHR_BIND_MEMORY2_BIT flag
+    /// or with VmaAllocatorCreateInfo::vulkanApiVersion `>= VK_API_VERSION_1_1`. Otherwise the call fails.
+    pub unsafe fn bind_buffer_memory2(
+        &self,
+        allocation: &Allocation,
+        allocation_local_offset: vk::DeviceSize,
+        buffer: vk::Buffer,
+        next: *const ::std::os::raw::c_void,
+    ) -> Result<()> {
+        let allocation = self.raw_allocation(allocation);
+        let result = ffi::vmaBindBufferMemory2(
+            self.internal,
+            allocation,
+            allocation_local_offset,
+            buffer,
+            next,
+        )
+        .result()
+        .context("vmaBindBufferMemory2");
+        #[cfg(feature = "recording")]
+        self.record(|| Call::BindBufferMemory {
+            allocation: handle(allocation),
+            allocation_local_offset,
+            result: CallResult::from(&result),
+        });
+        result
+    }
+
+    /// Binds image to allocation.
+    ///
+    /// Binds specified image to region of memory represented by specified allocation.
+    /// Gets `vk::DeviceMemory` handle and offset from the allocation.
+    ///
+    /// If you want to create a image, allocate memory for it and bind them together separately,
+    /// you should use this function for binding instead of `vk::Device::bind_image_memory`,
+    /// because it ensures proper synchronization so that when a `vk::DeviceMemory` object is
+    /// used by multiple allocations, calls to `vk::Device::bind_image_memory()` or
+    /// `vk::Device::map_memory()` won't happen from multiple threads simultaneously
+    /// (which is illegal in Vulkan).
+    ///
+    /// It is recommended to use function `Allocator::create_image` instead of this one.
+    pub unsafe fn bind_image_memory(
+        &self,
+        allocation: &Allocation,
+        image: vk::Image,
+    ) -> Result<()> {
+        let allocation = self.raw_allocation(allocation);
+        let result = ffi::vmaBindImageMemory(self.internal, allocation, image)
+            .result()
+            .context("vmaBindImageMemory");
+        #[cfg(feature = "recording")]
+        self.record(|| Call::BindImageMemory {
+            allocation: handle(allocation),
+            allocation_local_offset: 0,
+            result: CallResult::from(&result),
+        });
+        result
+    }
+
+    /// Binds image to allocation with additional parameters.
+    ///
+    /// * `allocation`
+    /// * `allocation_local_offset` - Additional offset to be added while binding, relative to the beginning of the `allocation`. Normally it should be 0.
+    /// * `image`
+    /// * `next` - A chain of structures to be attached to `VkBindImageMemoryInfoKHR` structure used internally. Normally it should be null.
+    ///
+    /// This function is similar to vmaBindImageMemory(), but it provides additional parameters.
+    ///
+    /// If `pNext` is not null, #VmaAllocator object must have been created with #VMA_ALLOCATOR_CREATE_KHR_BIND_MEMORY2_BIT flag
+    /// or with VmaAllocatorCreateInfo::vulkanApiVersion `>= VK_API_VERSION_1_1`. Otherwise the call fails.
+    pub unsafe fn bind_image_memory2(
+        &self,
+        allocation: &Allocation,
+        allocation_local_offset: vk::DeviceSize,
+        image: vk::Image,
+        next: *const ::std::os::raw::c_void,
+    ) -> Result<()> {
+        let allocation = self.raw_allocation(allocation);
+        let result = ffi::vmaBindImageMemory2(
+            self.internal,
+            allocation,
+            allocation_local_offset,
+            image,
+            next,
+        )
+        .result()
+        .context("vmaBindImageMemory2");
+        #[cfg(feature = "recording")]
+        self.record(|| Call::BindImageMemory {
+            allocation: handle(allocation),
+            allocation_local_offset,
+            result: CallResult::from(&result),
+        });
+        result
+    }
+
+    /// Creates a new buffer and binds it to already allocated memory.
+    ///
+    /// The new buffer aliases the memory of `allocation`, e.g. to overlap transient resources
+    /// that are never used at the same time. The allocation should have been created with
+    /// `AllocationCreateFlags::CAN_ALIAS` and must be big enough for the new buffer.
+    ///
+    /// The allocation is not owned by the returned buffer. Destroy the buffer with
+    /// `vkDestroyBuffer` before the allocation is freed, and never pass it
+    /// to `Allocator::destroy_buffer`.
+    pub unsafe fn create_aliasing_buffer(
+        &self,
+        allocation: &Allocation,
+        buffer_info: &vk::BufferCreateInfo,
+    ) -> Result<vk::Buffer> {
+        let allocation = self.raw_allocation(allocation);
+        let mut buffer = MaybeUninit::zeroed();
+        let result = ffi::vmaCreateAliasingBuffer(
+            self.internal,
+            allocation,
+            buffer_info,
+            buffer.as_mut_ptr(),
+        )
+        .result()
+        .context("vmaCreateAliasingBuffer");
+        #[cfg(feature = "recording")]
+        self.record(|| Call::CreateAliasingBuffer {
+            allocation: handle(allocation),
+            size: buffer_info.size,
+            usage: buffer_info.usage,
+            result: CallResult::from(&result),
+        });
+        result?;
+
+        Ok(buffer.assume_init())
+    }
+
+    /// Creates a new image and binds it to already allocated memory.
+    ///
+    /// The new image aliases the memory of `allocation`, e.g. to overlap transient attachments
+    /// that are never used at the same time. The allocation should have been created with
+    /// `AllocationCreateFlags::CAN_ALIAS` and must be big enough for the new image.
+    ///
+    /// The allocation is not owned by the returned image. Destroy the image with
+    /// `vkDestroyImage` before the allocation is freed, and never pass it
+    /// to `Allocator::destroy_image`.
+    pub unsafe fn create_aliasing_image(
+        &self,
+        allocation: &Allocation,
+        image_info: &vk::ImageCreateInfo,
+    ) -> Result<vk::Image> {
+        let allocation = self.raw_allocation(allocation);
+        let mut image = MaybeUninit::zeroed();
+        let result =
+            ffi::vmaCreateAliasingImage(self.internal, allocation, image_info, image.as_mut_ptr())
+                .result()
+                .context("vmaCreateAliasingImage");
+        #[cfg(feature = "recording")]
+        self.record(|| Call::CreateAliasingImage {
+            allocation: handle(allocation),
+            image: RecordedImage::from(image_info),
+            result: CallResult::from(&result),
+        });
+        result?;
+
+        Ok(image.assume_init())
+    }
+
+    /// Destroys Vulkan buffer and frees allocated memory.
+    ///
+    /// This is just a convenience function equivalent to:
+    ///
+    /// ```ignore
+    /// vk::Device::destroy_buffer(buffer, None);
+    /// Allocator::free_memory(allocator, allocation);
+    /// ```
+    ///
+    /// It it safe to pass null as `buffer` and/or `allocation`.
+    pub unsafe fn destroy_buffer(&self, buffer: vk::Buffer, allocation: Allocation) {
+        let allocation = self.raw_allocation(&allocation);
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(target: "vk_mem", "vmaDestroyBuffer").entered();
+        #[cfg(feature = "tracing")]
+        self.trace_free("vmaDestroyBuffer", &[allocation]);
+        ffi::vmaDestroyBuffer(self.internal, buffer, allocation);
+        self.forget_allocation(allocation);
+        #[cfg(feature = "recording")]
+        self.record(|| Call::FreeMemory {
+            allocations: vec![handle(allocation)],
+        });
+    }
+
+    /// Destroys Vulkan image and frees allocated memory.
+    ///
+    /// This is just a convenience function equivalent to:
+    ///
+    /// ```ignore
+    /// vk::Device::destroy_image(image, None);
+    /// Allocator::free_memory(allocator, allocation);
+    /// ```
+    ///
+    /// It it safe to pass null as `image` and/or `allocation`.
+    pub unsafe fn destroy_image(&self, image: vk::Image, allocation: Allocation) {
+        let allocation = self.raw_allocation(&allocation);
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(target: "vk_mem", "vmaDestroyImage").entered();
+        #[cfg(feature = "tracing")]
+        self.trace_free("vmaDestroyImage", &[allocation]);
+        ffi::vmaDestroyImage(self.internal, image, allocation);
+        self.forget_allocation(allocation);
+        #[cfg(feature = "recording")]
+        self.record(|| Call::FreeMemory {
+            allocations: vec![handle(allocation)],
+        });
+    }
+    /// Flushes memory of given set of allocations."]
+    ///
+    /// Calls `vkFlushMappedMemoryRanges()` for memory associated with given ranges of given allocations."]
+    /// For more information, see documentation of vmaFlushAllocation()."]
+    ///
+    /// * `allocations`
+    /// * `offsets` - If not None, it must be a slice of offsets of regions to flush, relative to the beginning of respective allocations. None means all ofsets are zero.
+    /// * `sizes` - If not None, it must be a slice of sizes of regions to flush in respective allocations. None means `VK_WHOLE_SIZE` for all allocations.
+    pub unsafe fn flush_allocations<'a>(
+        &self,
+        allocations: impl IntoIterator<Item = &'a Allocation>,
+        offsets: Option<&[vk::DeviceSize]>,
+        sizes: Option<&[vk::DeviceSize]>,
+    ) -> Result<()> {
+        let allocations: Vec<ffi::VmaAllocation> = allocations
+            .into_iter()
+            .map(|a| self.raw_allocation(a))
+            .collect();
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(
+            target: "vk_mem",
+            "vmaFlushAllocations",
+            count = allocations.len()
+        )
+        .entered();
+        let result = ffi::vmaFlushAllocations(
+            self.internal,
+            allocations.len() as u32,
+            allocations.as_ptr() as *mut _,
+            offsets.map_or(std::ptr::null(), |offsets| offsets.as_ptr()),
+            sizes.map_or(std::ptr::null(), |sizes| sizes.as_ptr()),
+        )
+        .result()
+        .context("vmaFlushAllocations");
+        #[cfg(feature = "tracing")]
+        instrumentation::trace_result("vmaFlushAllocations", &result);
+        #[cfg(feature = "recording")]
+        self.record(|| Call::FlushAllocations {
+            allocations: allocations
+                .iter()
+                .map(|&allocation| handle(allocation))
+                .collect(),
+            offsets: offsets.map_or_else(Vec::new, <[_]>::to_vec),
+            sizes: sizes.map_or_else(Vec::new, <[_]>::to_vec),
+            result: CallResult::from(&result),
+        });
+        result
+    }
+
+    /// Invalidates memory of given set of allocations."]
+    ///
+    /// Calls `vkInvalidateMappedMemoryRanges()` for memory associated with given ranges of given allocations."]
+    /// For more information, see documentation of vmaInvalidateAllocation()."]
+    ///
+    /// * `allocations`
+    /// * `offsets` - If not None, it must be a slice of offsets of regions to flush, relative to the beginning of respective allocations. None means all ofsets are zero.
+    /// * `sizes` - If not None, it must be a slice of sizes of regions to flush in respective allocations. None means `VK_WHOLE_SIZE` for all allocations.
+    pub unsafe fn invalidate_allocations<'a>(
+        &self,
+        allocations: impl IntoIterator<Item = &'a Allocation>,
+        offsets: Option<&[vk::DeviceSize]>,
+        sizes: Option<&[vk::DeviceSize]>,
+    ) -> Result<()> {
+        let allocations: Vec<ffi::VmaAllocation> = allocations
+            .into_iter()
+            .map(|a| self.raw_allocation(a))
+            .collect();
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(
+            target: "vk_mem",
+            "vmaInvalidateAllocations",
+            count = allocations.len()
+        )
+        .entered();
+        let result = ffi::vmaInvalidateAllocations(
+            self.internal,
+            allocations.len() as u32,
+            allocations.as_ptr() as *mut _,
+            offsets.map_or(std::ptr::null(), |offsets| offsets.as_ptr()),
+            sizes.map_or(std::ptr::null(), |sizes| sizes.as_ptr()),
+        )
+        .result()
+        .context("vmaInvalidateAllocations");
+        #[cfg(feature = "tracing")]
+        instrumentation::trace_result("vmaInvalidateAllocations", &result);
+        #[cfg(feature = "recording")]
+        self.record(|| Call::InvalidateAllocations {
+            allocations: allocations
+                .iter()
+                .map(|&allocation| handle(allocation))
+                .collect(),
+            offsets: offsets.map_or_else(Vec::new, <[_]>::to_vec),
+            sizes: sizes.map_or_else(Vec::new, <[_]>::to_vec),
+            result: CallResult::from(&result),
+        });
+        result
+    }
+}
+
+/// Custom `Drop` implementation to clean up internal allocation instance
+impl Drop for Allocator {
+    fn drop(&mut self) {
+        #[cfg(feature = "recording")]
+        if let Some(recorder) = &self.recorder {
+            recorder.record(Call::DestroyAllocator);
+            let _ = recorder.flush();
+        }
+        let mut leak_report = None;
+        if self.leak_detection.is_some() {
+            leak_report = self.free_leaked_allocations("Allocator", None);
+        }
+        unsafe {
+            ffi::vmaDestroyAllocator(self.internal);
+            self.internal = std::ptr::null_mut();
+        }
+        if let Some(leak_report) = leak_report {
+            panic!("{}", leak_report);
+        }
+    }
+}
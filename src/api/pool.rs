@@ -0,0 +1,823 @@
+use std::ffi::CStr;
+use std::mem::MaybeUninit;
+use std::sync::Arc;
+
+use super::error::ResultExt;
+use super::ffi;
+use super::vk;
+use super::Allocation;
+use super::AllocationCreateInfo;
+use super::Allocator;
+use super::DetailedStatistics;
+use super::Error;
+use super::PoolCreateInfo;
+use super::Result;
+use super::Statistics;
+#[cfg(feature = "recording")]
+use super::{
+    recording::{handle, Call, CallResult, RecordedImage},
+    AllocatorPoolCreateFlags,
+};
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct PoolHandle(pub(crate) ffi::VmaPool);
+unsafe impl Send for PoolHandle {}
+unsafe impl Sync for PoolHandle {}
+
+impl PoolHandle {
+    /// Whether this is the handle of the default pools, i.e. not of a custom pool.
+    pub fn is_default(&self) -> bool {
+        self.0.is_null()
+    }
+}
+
+/// Represents custom memory pool handle.
+pub struct AllocatorPool {
+    allocator: Arc<Allocator>,
+    pub(crate) pool: PoolHandle,
+}
+unsafe impl Send for AllocatorPool {}
+unsafe impl Sync for AllocatorPool {}
+
+impl Allocator {
+    /// Allocates Vulkan device memory and creates `AllocatorPool` object.
+    pub fn create_pool(self: &Arc<Self>, create_info: &PoolCreateInfo) -> Result<AllocatorPool> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(
+            target: "vk_mem",
+            "vmaCreatePool",
+            memory_type = create_info.inner.memoryTypeIndex,
+            block_size = create_info.inner.blockSize
+        )
+        .entered();
+        unsafe {
+            let mut ffi_pool: ffi::VmaPool = std::mem::zeroed();
+            let result = ffi::vmaCreatePool(self.internal, &create_info.inner, &mut ffi_pool)
+                .result()
+                .context("vmaCreatePool");
+            #[cfg(feature = "tracing")]
+            super::instrumentation::trace_result("vmaCreatePool", &result);
+            #[cfg(feature = "recording")]
+            self.record(|| Call::CreatePool {
+                pool: handle(ffi_pool),
+                memory_type_index: create_info.inner.memoryTypeIndex,
+                flags: AllocatorPoolCreateFlags::from_bits_truncate(create_info.inner.flags),
+                block_size: create_info.inner.blockSize,
+                min_block_count: create_info.inner.minBlockCount as u64,
+                max_block_count: create_info.inner.maxBlockCount as u64,
+                min_allocation_alignment: create_info.inner.minAllocationAlignment,
+                result: CallResult::from(&result),
+            });
+            result?;
+            Ok(AllocatorPool {
+                pool: PoolHandle(ffi_pool),
+                allocator: self.clone(),
+            })
+        }
+    }
+
+    pub fn default_pool(self: &Arc<Self>) -> AllocatorPool {
+        AllocatorPool {
+            pool: PoolHandle(std::ptr::null_mut()),
+            allocator: self.clone(),
+        }
+    }
+}
+
+impl Drop for AllocatorPool {
+    fn drop(&mut self) {
+        if self.pool.is_default() {
+            return;
+        }
+        let leak_report = self
+            .allocator
+            .free_leaked_allocations("AllocatorPool", Some(self.pool));
+        // Reported allocations are already gone, this drops the untracked ones
+        #[cfg(feature = "track_allocations")]
+        self.allocator.allocation_registry.remove_pool(self.pool);
+        unsafe {
+            ffi::vmaDestroyPool(self.allocator.internal, self.pool.0);
+        }
+        #[cfg(feature = "recording")]
+        self.allocator.record(|| Call::DestroyPool {
+            pool: handle(self.pool.0),
+        });
+        if let Some(leak_report) = leak_report {
+            panic!("{}", leak_report);
+        }
+    }
+}
+
+impl AllocatorPool {
+    pub fn set_name(&self, name: Option<&CStr>) {
+        if self.pool.0.is_null() {
+            return;
+        }
+        unsafe {
+            ffi::vmaSetPoolName(
+                self.allocator.internal,
+                self.pool.0,
+                name.map_or(std::ptr::null(), CStr::as_ptr),
+            );
+        }
+    }
+    pub fn name(&self) -> Option<&CStr> {
+        if self.pool.0.is_null() {
+            return None;
+        }
+        let mut ptr: *const ::std::os::raw::c_char = std::ptr::null();
+        unsafe {
+            ffi::vmaGetPoolName(self.allocator.internal, self.pool.0, &mut ptr);
+            if ptr.is_null() {
+                return None;
+            }
+            Some(CStr::from_ptr(ptr))
+        }
+    }
+    /// Retrieves statistics of existing `AllocatorPool` object.
+    pub fn get_statistics(&self) -> Result<Statistics> {
+        unsafe {
+            let mut pool_stats: ffi::VmaStatistics = std::mem::zeroed();
+            ffi::vmaGetPoolStatistics(self.allocator.internal, self.pool.0, &mut pool_stats);
+            Ok(Statistics::from(&pool_stats))
+        }
+    }
+
+    /// Retrieves statistics of existing `AllocatorPool` object.
+    pub fn calculate_statistics(&self) -> Result<DetailedStatistics> {
+        unsafe {
+            let mut pool_stats: ffi::VmaDetailedStatistics = std::mem::zeroed();
+            ffi::vmaCalculatePoolStatistics(self.allocator.internal, self.pool.0, &mut pool_stats);
+            Ok(DetailedStatistics::from(&pool_stats))
+        }
+    }
+
+    /// Checks magic number in margins around all allocations in given memory pool in search for corruptions.
+    ///
+    /// Corruption detection is enabled only with the `detect_corruption` feature, which defines
+    /// `VMA_DEBUG_DETECT_CORRUPTION` and a nonzero `VMA_DEBUG_MARGIN`, and the pool is created in memory type that is
+    /// `vk::MemoryPropertyFlags::HOST_VISIBLE` and `vk::MemoryPropertyFlags::HOST_COHERENT`.
+    ///
+    /// Possible error values:
+    ///
+    /// - `Error::FeatureNotEnabled` - corruption detection is not enabled for specified pool.
+    /// - `Error::CorruptionDetected` - corruption detection has been performed and found memory corruptions around one of the allocations.
+    ///   `VMA_ASSERT` is also fired in that case.
+    /// - Other value: Error returned by Vulkan, e.g. memory mapping failure.
+    pub fn check_corruption(&self) -> Result<()> {
+        let result = unsafe {
+            ffi::vmaCheckPoolCorruption(self.allocator.internal, self.pool.0)
+                .result()
+                .map_err(|result| Error::corruption_check("vmaCheckPoolCorruption", result))
+        };
+        #[cfg(feature = "recording")]
+        self.allocator.record(|| Call::CheckPoolCorruption {
+            pool: handle(self.pool.0),
+            result: CallResult::from(&result),
+        });
+        result
+    }
+}
+
+pub trait Alloc {
+    fn allocator(&self) -> &Allocator;
+    fn pool(&self) -> PoolHandle;
+    /// Helps to find memory type index, given memory type bits and allocation info.
+    ///
+    /// This algorithm tries to find a memory type that:
+    ///
+    /// - Is allowed by memory type bits.
+    /// - Contains all the flags from `allocation_info.required_flags`.
+    /// - Matches intended usage.
+    /// - Has as many flags from `allocation_info.preferred_flags` as possible.
+    ///
+    /// Returns `Error::NoSuitableMemoryType` if not found. Receiving such a result
+    /// from this function or any other allocating function probably means that your
+    /// device doesn't support any memory type with requested features for the specific
+    /// type of resource you want to use it for. Please check parameters of your
+    /// resource, like image layout (OPTIMAL versus LINEAR) or mip level count.
+    unsafe fn find_memory_type_index(
+        &self,
+        memory_type_bits: u32,
+        allocation_info: &AllocationCreateInfo,
+    ) -> Result<u32> {
+        let mut memory_type_index: u32 = 0;
+        let mut allocation_info: ffi::VmaAllocationCreateInfo = allocation_info.into();
+        allocation_info.pool = self.pool().0;
+        ffi::vmaFindMemoryTypeIndex(
+            self.allocator().internal,
+            memory_type_bits,
+            &allocation_info,
+            &mut memory_type_index,
+        )
+        .result()
+        .map_err(|result| {
+            Error::allocation(
+                "vmaFindMemoryTypeIndex",
+                None,
+                Some(memory_type_bits),
+                &allocation_info,
+                result,
+            )
+        })?;
+
+        Ok(memory_type_index)
+    }
+
+    /// Helps to find memory type index, given buffer info and allocation info.
+    ///
+    /// It can be useful e.g. to determine value to be used as `AllocatorPoolCreateInfo::memory_type_index`.
+    /// It internally creates a temporary, dummy buffer that never has memory bound.
+    /// It is just a convenience function, equivalent to calling:
+    ///
+    /// - `vk::Device::create_buffer`
+    /// - `vk::Device::get_buffer_memory_requirements`
+    /// - `Allocator::find_memory_type_index`
+    /// - `vk::Device::destroy_buffer`
+    unsafe fn find_memory_type_index_for_buffer_info(
+        &self,
+        buffer_info: &vk::BufferCreateInfo,
+        allocation_info: &AllocationCreateInfo,
+    ) -> Result<u32> {
+        let mut allocation_info: ffi::VmaAllocationCreateInfo = allocation_info.into();
+        allocation_info.pool = self.pool().0;
+        let mut memory_type_index: u32 = 0;
+        ffi::vmaFindMemoryTypeIndexForBufferInfo(
+            self.allocator().internal,
+            buffer_info,
+            &allocation_info,
+            &mut memory_type_index,
+        )
+        .result()
+        .map_err(|result| {
+            Error::allocation(
+                "vmaFindMemoryTypeIndexForBufferInfo",
+                Some(buffer_info.size),
+                None,
+                &allocation_info,
+                result,
+            )
+        })?;
+
+        Ok(memory_type_index)
+    }
+
+    /// Helps to find memory type index, given image info and allocation info.
+    ///
+    /// It can be useful e.g. to determine value to be used as `AllocatorPoolCreateInfo::memory_type_index`.
+    /// It internally creates a temporary, dummy image that never has memory bound.
+    /// It is just a convenience function, equivalent to calling:
+    ///
+    /// - `vk::Device::create_image`
+    /// - `vk::Device::get_image_memory_requirements`
+    /// - `Allocator::find_memory_type_index`
+    /// - `vk::Device::destroy_image`
+    unsafe fn find_memory_type_index_for_image_info(
+        &self,
+        image_info: vk::ImageCreateInfo,
+        allocation_info: &AllocationCreateInfo,
+    ) -> Result<u32> {
+        let mut allocation_info: ffi::VmaAllocationCreateInfo = allocation_info.into();
+        allocation_info.pool = self.pool().0;
+        let mut memory_type_index: u32 = 0;
+        ffi::vmaFindMemoryTypeIndexForImageInfo(
+            self.allocator().internal,
+            &image_info,
+            &allocation_info,
+            &mut memory_type_index,
+        )
+        .result()
+        .map_err(|result| {
+            Error::allocation(
+                "vmaFindMemoryTypeIndexForImageInfo",
+                None,
+                None,
+                &allocation_info,
+                result,
+            )
+        })?;
+
+        Ok(memory_type_index)
+    }
+
+    /// General purpose memory allocation.
+    ///
+    /// You should free the memory using `Allocator::free_memory` or 'Allocator::free_memory_pages'.
+    ///
+    /// It is recommended to use `Allocator::allocate_memory_for_buffer`, `Allocator::allocate_memory_for_image`,
+    /// `Allocator::create_buffer`, `Allocator::create_image` instead whenever possible.
+    #[track_caller]
+    unsafe fn allocate_memory(
+        &self,
+        memory_requirements: &vk::MemoryRequirements,
+        create_info: &AllocationCreateInfo,
+    ) -> Result<Allocation> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(
+            target: "vk_mem",
+            "vmaAllocateMemory",
+            size = memory_requirements.size
+        )
+        .entered();
+        #[cfg(feature = "recording")]
+        let recorded_info = create_info;
+        let mut create_info: ffi::VmaAllocationCreateInfo = create_info.into();
+        create_info.pool = self.pool().0;
+        let mut allocation: ffi::VmaAllocation = std::mem::zeroed();
+        let result = ffi::vmaAllocateMemory(
+            self.allocator().internal,
+            memory_requirements,
+            &create_info,
+            &mut allocation,
+            std::ptr::null_mut(),
+        )
+        .result()
+        .map_err(|result| {
+            Error::allocation(
+                "vmaAllocateMemory",
+                Some(memory_requirements.size),
+                Some(memory_requirements.memory_type_bits),
+                &create_info,
+                result,
+            )
+        });
+        #[cfg(feature = "tracing")]
+        self.allocator().trace_allocations(
+            "vmaAllocateMemory",
+            self.pool(),
+            &[allocation],
+            &result,
+        );
+        #[cfg(feature = "recording")]
+        self.allocator().record(|| Call::AllocateMemory {
+            pool: handle(self.pool().0),
+            allocations: vec![handle(allocation)],
+            memory_requirements: *memory_requirements,
+            create_info: recorded_info.clone(),
+            result: CallResult::from(&result),
+        });
+        result?;
+
+        Ok(Allocation::new(allocation, self.allocator(), self.pool()))
+    }
+
+    /// General purpose memory allocation for multiple allocation objects at once.
+    ///
+    /// You should free the memory using `Allocator::free_memory` or `Allocator::free_memory_pages`.
+    ///
+    /// Word "pages" is just a suggestion to use this function to allocate pieces of memory needed for sparse binding.
+    /// It is just a general purpose allocation function able to make multiple allocations at once.
+    /// It may be internally optimized to be more efficient than calling `Allocator::allocate_memory` `allocations.len()` times.
+    ///
+    /// All allocations are made using same parameters. All of them are created out of the same memory pool and type.
+    #[track_caller]
+    unsafe fn allocate_memory_pages(
+        &self,
+        memory_requirements: &vk::MemoryRequirements,
+        create_info: &AllocationCreateInfo,
+        allocation_count: usize,
+    ) -> Result<Vec<Allocation>> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(
+            target: "vk_mem",
+            "vmaAllocateMemoryPages",
+            size = memory_requirements.size,
+            allocation_count
+        )
+        .entered();
+        #[cfg(feature = "recording")]
+        let recorded_info = create_info;
+        let mut create_info: ffi::VmaAllocationCreateInfo = create_info.into();
+        create_info.pool = self.pool().0;
+        let mut allocations: Vec<ffi::VmaAllocation> = vec![std::mem::zeroed(); allocation_count];
+        let result = ffi::vmaAllocateMemoryPages(
+            self.allocator().internal,
+            memory_requirements,
+            &create_info,
+            allocation_count,
+            allocations.as_mut_ptr(),
+            std::ptr::null_mut(),
+        )
+        .result()
+        .map_err(|result| {
+            Error::allocation(
+                "vmaAllocateMemoryPages",
+                Some(memory_requirements.size),
+                Some(memory_requirements.memory_type_bits),
+                &create_info,
+                result,
+            )
+        });
+        #[cfg(feature = "tracing")]
+        self.allocator().trace_allocations(
+            "vmaAllocateMemoryPages",
+            self.pool(),
+            &allocations,
+            &result,
+        );
+        #[cfg(feature = "recording")]
+        self.allocator().record(|| Call::AllocateMemory {
+            pool: handle(self.pool().0),
+            allocations: allocations
+                .iter()
+                .map(|&allocation| handle(allocation))
+                .collect(),
+            memory_requirements: *memory_requirements,
+            create_info: recorded_info.clone(),
+            result: CallResult::from(&result),
+        });
+        result?;
+
+        // Not a closure, so that `Allocation::new` sees the caller of this function
+        let mut result = Vec::with_capacity(allocations.len());
+        for allocation in allocations {
+            result.push(Allocation::new(allocation, self.allocator(), self.pool()));
+        }
+
+        Ok(result)
+    }
+
+    /// Buffer specialized memory allocation.
+    ///
+    /// You should free the memory using `Allocator::free_memory` or 'Allocator::free_memory_pages'.
+    #[track_caller]
+    unsafe fn allocate_memory_for_buffer(
+        &self,
+        buffer: vk::Buffer,
+        create_info: &AllocationCreateInfo,
+    ) -> Result<Allocation> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(target: "vk_mem", "vmaAllocateMemoryForBuffer").entered();
+        #[cfg(feature = "recording")]
+        let recorded_info = create_info;
+        let mut create_info: ffi::VmaAllocationCreateInfo = create_info.into();
+        create_info.pool = self.pool().0;
+        let mut allocation = MaybeUninit::zeroed();
+        let mut allocation_info = MaybeUninit::zeroed();
+        let result = ffi::vmaAllocateMemoryForBuffer(
+            self.allocator().internal,
+            buffer,
+            &create_info,
+            allocation.as_mut_ptr(),
+            allocation_info.as_mut_ptr(),
+        )
+        .result()
+        .map_err(|result| {
+            Error::allocation(
+                "vmaAllocateMemoryForBuffer",
+                None,
+                None,
+                &create_info,
+                result,
+            )
+        });
+        let allocation = allocation.assume_init();
+        #[cfg(feature = "tracing")]
+        self.allocator().trace_allocations(
+            "vmaAllocateMemoryForBuffer",
+            self.pool(),
+            &[allocation],
+            &result,
+        );
+        #[cfg(feature = "recording")]
+        self.allocator().record(|| Call::AllocateMemory {
+            pool: handle(self.pool().0),
+            allocations: vec![handle(allocation)],
+            memory_requirements: if result.is_ok() {
+                self.allocator().recorded_requirements(allocation)
+            } else {
+                vk::MemoryRequirements::default()
+            },
+            create_info: recorded_info.clone(),
+            result: CallResult::from(&result),
+        });
+        result?;
+
+        Ok(Allocation::new(allocation, self.allocator(), self.pool()))
+    }
+
+    /// Image specialized memory allocation.
+    ///
+    /// You should free the memory using `Allocator::free_memory` or 'Allocator::free_memory_pages'.
+    #[track_caller]
+    unsafe fn allocate_memory_for_image(
+        &self,
+        image: vk::Image,
+        create_info: &AllocationCreateInfo,
+    ) -> Result<Allocation> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(target: "vk_mem", "vmaAllocateMemoryForImage").entered();
+        #[cfg(feature = "recording")]
+        let recorded_info = create_info;
+        let mut create_info: ffi::VmaAllocationCreateInfo = create_info.into();
+        create_info.pool = self.pool().0;
+        let mut allocation = MaybeUninit::zeroed();
+        let result = ffi::vmaAllocateMemoryForImage(
+            self.allocator().internal,
+            image,
+            &create_info,
+            allocation.as_mut_ptr(),
+            std::ptr::null_mut(),
+        )
+        .result()
+        .map_err(|result| {
+            Error::allocation(
+                "vmaAllocateMemoryForImage",
+                None,
+                None,
+                &create_info,
+                result,
+            )
+        });
+        let allocation = allocation.assume_init();
+        #[cfg(feature = "tracing")]
+        self.allocator().trace_allocations(
+            "vmaAllocateMemoryForImage",
+            self.pool(),
+            &[allocation],
+            &result,
+        );
+        #[cfg(feature = "recording")]
+        self.allocator().record(|| Call::AllocateMemory {
+            pool: handle(self.pool().0),
+            allocations: vec![handle(allocation)],
+            memory_requirements: if result.is_ok() {
+                self.allocator().recorded_requirements(allocation)
+            } else {
+                vk::MemoryRequirements::default()
+            },
+            create_info: recorded_info.clone(),
+            result: CallResult::from(&result),
+        });
+        result?;
+
+        Ok(Allocation::new(allocation, self.allocator(), self.pool()))
+    }
+
+    /// This function automatically creates a buffer, allocates appropriate memory
+    /// for it, and binds the buffer with the memory.
+    ///
+    /// If the function succeeded, you must destroy both buffer and allocation when you
+    /// no longer need them using either convenience function `Allocator::destroy_buffer` or
+    /// separately, using `ash::Device::destroy_buffer` and `Allocator::free_memory`.
+    ///
+    /// If `AllocatorCreateFlags::KHR_DEDICATED_ALLOCATION` flag was used,
+    /// VK_KHR_dedicated_allocation extension is used internally to query driver whether
+    /// it requires or prefers the new buffer to have dedicated allocation. If yes,
+    /// and if dedicated allocation is possible (AllocationCreateInfo::pool is null
+    /// and `AllocationCreateFlags::NEVER_ALLOCATE` is not used), it creates dedicated
+    /// allocation for this buffer, just like when using `AllocationCreateFlags::DEDICATED_MEMORY`.
+    #[track_caller]
+    unsafe fn create_buffer(
+        &self,
+        buffer_info: &vk::BufferCreateInfo,
+        create_info: &AllocationCreateInfo,
+    ) -> Result<(vk::Buffer, Allocation)> {
+        #[cfg(feature = "tracing")]
+        let _span =
+            tracing::debug_span!(target: "vk_mem", "vmaCreateBuffer", size = buffer_info.size)
+                .entered();
+        #[cfg(feature = "recording")]
+        let recorded_info = create_info;
+        let mut create_info: ffi::VmaAllocationCreateInfo = create_info.into();
+        create_info.pool = self.pool().0;
+        let mut buffer = MaybeUninit::zeroed();
+        let mut allocation = MaybeUninit::zeroed();
+        let result = ffi::vmaCreateBuffer(
+            self.allocator().internal,
+            &*buffer_info,
+            &create_info,
+            buffer.as_mut_ptr(),
+            allocation.as_mut_ptr(),
+            std::ptr::null_mut(),
+        )
+        .result()
+        .map_err(|result| {
+            Error::allocation(
+                "vmaCreateBuffer",
+                Some(buffer_info.size),
+                None,
+                &create_info,
+                result,
+            )
+        });
+        #[cfg(feature = "tracing")]
+        self.allocator().trace_allocations(
+            "vmaCreateBuffer",
+            self.pool(),
+            &[allocation.assume_init()],
+            &result,
+        );
+        #[cfg(feature = "recording")]
+        self.allocator().record(|| Call::CreateBuffer {
+            pool: handle(self.pool().0),
+            allocation: handle(allocation.assume_init()),
+            size: buffer_info.size,
+            usage: buffer_info.usage,
+            min_alignment: None,
+            create_info: recorded_info.clone(),
+            result: CallResult::from(&result),
+        });
+        result?;
+
+        Ok((
+            buffer.assume_init(),
+            Allocation::new(allocation.assume_init(), self.allocator(), self.pool()),
+        ))
+    }
+    /// brief Creates a buffer with additional minimum alignment.
+    ///
+    /// Similar to vmaCreateBuffer() but provides additional parameter `minAlignment` which allows to specify custom,
+    /// minimum alignment to be used when placing the buffer inside a larger memory block, which may be needed e.g.
+    /// for interop with OpenGL.
+    #[track_caller]
+    unsafe fn create_buffer_with_alignment(
+        &self,
+        buffer_info: &vk::BufferCreateInfo,
+        create_info: &AllocationCreateInfo,
+        min_alignment: vk::DeviceSize,
+    ) -> Result<(vk::Buffer, Allocation)> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(
+            target: "vk_mem",
+            "vmaCreateBufferWithAlignment",
+            size = buffer_info.size,
+            min_alignment
+        )
+        .entered();
+        #[cfg(feature = "recording")]
+        let recorded_info = create_info;
+        let mut create_info: ffi::VmaAllocationCreateInfo = create_info.into();
+        create_info.pool = self.pool().0;
+        let mut buffer = MaybeUninit::zeroed();
+        let mut allocation = MaybeUninit::zeroed();
+        let result = ffi::vmaCreateBufferWithAlignment(
+            self.allocator().internal,
+            &*buffer_info,
+            &create_info,
+            min_alignment,
+            buffer.as_mut_ptr(),
+            allocation.as_mut_ptr(),
+            std::ptr::null_mut(),
+        )
+        .result()
+        .map_err(|result| {
+            Error::allocation(
+                "vmaCreateBufferWithAlignment",
+                Some(buffer_info.size),
+                None,
+                &create_info,
+                result,
+            )
+        });
+        #[cfg(feature = "tracing")]
+        self.allocator().trace_allocations(
+            "vmaCreateBufferWithAlignment",
+            self.pool(),
+            &[allocation.assume_init()],
+            &result,
+        );
+        #[cfg(feature = "recording")]
+        self.allocator().record(|| Call::CreateBuffer {
+            pool: handle(self.pool().0),
+            allocation: handle(allocation.assume_init()),
+            size: buffer_info.size,
+            usage: buffer_info.usage,
+            min_alignment: Some(min_alignment),
+            create_info: recorded_info.clone(),
+            result: CallResult::from(&result),
+        });
+        result?;
+
+        Ok((
+            buffer.assume_init(),
+            Allocation::new(allocation.assume_init(), self.allocator(), self.pool()),
+        ))
+    }
+    /// This function automatically creates an image, allocates appropriate memory
+    /// for it, and binds the image with the memory.
+    ///
+    /// If the function succeeded, you must destroy both image and allocation when you
+    /// no longer need them using either convenience function `Allocator::destroy_image` or
+    /// separately, using `ash::Device::destroy_image` and `Allocator::free_memory`.
+    ///
+    /// If `AllocatorCreateFlags::KHR_DEDICATED_ALLOCATION` flag was used,
+    /// `VK_KHR_dedicated_allocation extension` is used internally to query driver whether
+    /// it requires or prefers the new image to have dedicated allocation. If yes,
+    /// and if dedicated allocation is possible (AllocationCreateInfo::pool is null
+    /// and `AllocationCreateFlags::NEVER_ALLOCATE` is not used), it creates dedicated
+    /// allocation for this image, just like when using `AllocationCreateFlags::DEDICATED_MEMORY`.
+    ///
+    /// If `VK_ERROR_VALIDAITON_FAILED_EXT` is returned, VMA may have encountered a problem
+    /// that is not caught by the validation layers. One example is if you try to create a 0x0
+    /// image, a panic will occur and `VK_ERROR_VALIDAITON_FAILED_EXT` is thrown.
+    #[track_caller]
+    unsafe fn create_image(
+        &self,
+        image_info: &vk::ImageCreateInfo,
+        create_info: &AllocationCreateInfo,
+    ) -> Result<(vk::Image, Allocation)> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(
+            target: "vk_mem",
+            "vmaCreateImage",
+            width = image_info.extent.width,
+            height = image_info.extent.height
+        )
+        .entered();
+        #[cfg(feature = "recording")]
+        let recorded_info = create_info;
+        let mut create_info: ffi::VmaAllocationCreateInfo = create_info.into();
+        create_info.pool = self.pool().0;
+        let mut image = MaybeUninit::zeroed();
+        let mut allocation = MaybeUninit::zeroed();
+        let result = ffi::vmaCreateImage(
+            self.allocator().internal,
+            &*image_info,
+            &create_info,
+            image.as_mut_ptr(),
+            allocation.as_mut_ptr(),
+            std::ptr::null_mut(),
+        )
+        .result()
+        .map_err(|result| Error::allocation("vmaCreateImage", None, None, &create_info, result));
+        #[cfg(feature = "tracing")]
+        self.allocator().trace_allocations(
+            "vmaCreateImage",
+            self.pool(),
+            &[allocation.assume_init()],
+            &result,
+        );
+        #[cfg(feature = "recording")]
+        self.allocator().record(|| Call::CreateImage {
+            pool: handle(self.pool().0),
+            allocation: handle(allocation.assume_init()),
+            image: RecordedImage::from(image_info),
+            create_info: recorded_info.clone(),
+            result: CallResult::from(&result),
+        });
+        result?;
+
+        Ok((
+            image.assume_init(),
+            Allocation::new(allocation.assume_init(), self.allocator(), self.pool()),
+        ))
+    }
+
+    /// Same as `Alloc::create_buffer`, but also attaches `user_data` to the allocation.
+    ///
+    /// The value can be retrieved with `Allocator::user_data` and is dropped when the
+    /// allocation is freed, see `Allocator::set_user_data`.
+    #[track_caller]
+    unsafe fn create_buffer_with_user_data<U: Send + Sync + 'static>(
+        &self,
+        buffer_info: &vk::BufferCreateInfo,
+        create_info: &AllocationCreateInfo,
+        user_data: U,
+    ) -> Result<(vk::Buffer, Allocation)>
+    where
+        Self: Sized,
+    {
+        let (buffer, mut allocation) = self.create_buffer(buffer_info, create_info)?;
+        self.allocator().set_user_data(&mut allocation, user_data);
+        Ok((buffer, allocation))
+    }
+
+    /// Same as `Alloc::create_image`, but also attaches `user_data` to the allocation.
+    ///
+    /// The value can be retrieved with `Allocator::user_data` and is dropped when the
+    /// allocation is freed, see `Allocator::set_user_data`.
+    #[track_caller]
+    unsafe fn create_image_with_user_data<U: Send + Sync + 'static>(
+        &self,
+        image_info: &vk::ImageCreateInfo,
+        create_info: &AllocationCreateInfo,
+        user_data: U,
+    ) -> Result<(vk::Image, Allocation)>
+    where
+        Self: Sized,
+    {
+        let (image, mut allocation) = self.create_image(image_info, create_info)?;
+        self.allocator().set_user_data(&mut allocation, user_data);
+        Ok((image, allocation))
+    }
+}
+
+impl Alloc for AllocatorPool {
+    fn allocator(&self) -> &Allocator {
+        self.allocator.as_ref()
+    }
+
+    fn pool(&self) -> PoolHandle {
+        self.pool
+    }
+}
+impl Alloc for Allocator {
+    fn allocator(&self) -> &Allocator {
+        self
+    }
+
+    fn pool(&self) -> PoolHandle {
+        PoolHandle(std::ptr::null_mut())
+    }
+}
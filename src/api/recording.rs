@@ -0,0 +1,1763 @@
+//! Recording of allocator calls to a compact binary file, enabled by the `recording` feature,
+//! and their replay against another allocator.
+//!
+//! ```no_run
+//! # fn record(create_info: vk_mem::AllocatorCreateInfo<&spark::Instance, &spark::Device>) -> std::io::Result<()> {
+//! let recorder = vk_mem::Recorder::create("allocations.vmarec")?;
+//! let allocator = vk_mem::Allocator::new(create_info.recorder(recorder.clone()));
+//! // ...
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The file starts with the magic bytes `VKMEMREC` and the little-endian `u32` format version,
+//! followed by one record per call: opcode, thread index, nanoseconds since the recorder was
+//! created, and the parameters and result of the call. Allocations, pools and virtual blocks
+//! are identified by their raw VMA handles, which may be reused once freed.
+
+use std::cell::Cell;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use std::mem;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::ffi;
+use super::statistics::Bytes;
+use super::vk;
+use super::{
+    Alloc, Allocation, AllocationCreateFlags, AllocationCreateInfo, AllocationInfo, Allocator,
+    AllocatorCreateFlags, AllocatorPool, AllocatorPoolCreateFlags, DefragmentationContext,
+    DefragmentationInfo, DefragmentationMoveOperation, Error, MemoryUsage, PoolCreateInfo,
+    VirtualAllocation, VirtualAllocationCreateFlags, VirtualAllocationCreateInfo, VirtualBlock,
+    VirtualBlockCreateFlags, VirtualBlockCreateInfo,
+};
+
+const MAGIC: &[u8; 8] = b"VKMEMREC";
+
+/// Version of the file format written by `Recorder`.
+pub const RECORDING_VERSION: u32 = 1;
+
+/// Outcome of a recorded call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CallResult {
+    Success,
+    OutOfDeviceMemory,
+    NoSuitableMemoryType,
+    /// Any other error.
+    Failed,
+}
+
+impl<T> From<&super::Result<T>> for CallResult {
+    fn from(result: &super::Result<T>) -> Self {
+        match result {
+            Ok(_) => CallResult::Success,
+            Err(Error::OutOfDeviceMemory { .. }) => CallResult::OutOfDeviceMemory,
+            Err(Error::NoSuitableMemoryType { .. }) => CallResult::NoSuitableMemoryType,
+            Err(_) => CallResult::Failed,
+        }
+    }
+}
+
+/// Parameters of a recorded `Alloc::create_image` call.
+#[derive(Debug, Clone, Copy)]
+pub struct RecordedImage {
+    pub image_type: vk::ImageType,
+    pub format: vk::Format,
+    pub extent: vk::Extent3D,
+    pub mip_levels: u32,
+    pub array_layers: u32,
+    pub samples: vk::SampleCountFlags,
+    pub tiling: vk::ImageTiling,
+    pub usage: vk::ImageUsageFlags,
+}
+
+impl From<&vk::ImageCreateInfo> for RecordedImage {
+    fn from(info: &vk::ImageCreateInfo) -> Self {
+        RecordedImage {
+            image_type: info.image_type,
+            format: info.format,
+            extent: info.extent,
+            mip_levels: info.mip_levels,
+            array_layers: info.array_layers,
+            samples: info.samples,
+            tiling: info.tiling,
+            usage: info.usage,
+        }
+    }
+}
+
+/// Move of a recorded defragmentation pass, as the mover left it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecordedMove {
+    pub src_allocation: u64,
+    pub dst_tmp_allocation: u64,
+    pub operation: DefragmentationMoveOperation,
+}
+
+/// Recorded call, with the handles of the objects it created or used.
+///
+/// Queries that don't change the state of the allocator are not recorded, except for corruption
+/// checks. `Alloc::allocate_memory_for_buffer` and `Alloc::allocate_memory_for_image` are
+/// recorded as `Call::AllocateMemory` with the size and memory type of the resulting allocation,
+/// as the requirements of the resource are unknown. When they fail, the requirements are
+/// recorded as zero and the call is skipped by `replay`.
+///
+/// Binding and aliasing resources are recorded, but can't be replayed as the recording doesn't
+/// contain the Vulkan objects the application created itself, see `Call::is_replayable`.
+#[derive(Debug, Clone)]
+pub enum Call {
+    CreateAllocator {
+        flags: AllocatorCreateFlags,
+        preferred_large_heap_block_size: vk::DeviceSize,
+        memory_properties: Box<vk::PhysicalDeviceMemoryProperties>,
+    },
+    DestroyAllocator,
+    SetCurrentFrameIndex {
+        frame_index: u32,
+    },
+    CreatePool {
+        pool: u64,
+        memory_type_index: u32,
+        flags: AllocatorPoolCreateFlags,
+        block_size: vk::DeviceSize,
+        min_block_count: u64,
+        max_block_count: u64,
+        min_allocation_alignment: vk::DeviceSize,
+        result: CallResult,
+    },
+    DestroyPool {
+        pool: u64,
+    },
+    /// `Alloc::allocate_memory`, or `Alloc::allocate_memory_pages` with multiple allocations.
+    AllocateMemory {
+        pool: u64,
+        allocations: Vec<u64>,
+        memory_requirements: vk::MemoryRequirements,
+        create_info: AllocationCreateInfo,
+        result: CallResult,
+    },
+    CreateBuffer {
+        pool: u64,
+        allocation: u64,
+        size: vk::DeviceSize,
+        usage: vk::BufferUsageFlags,
+        /// Set for `Alloc::create_buffer_with_alignment`.
+        min_alignment: Option<vk::DeviceSize>,
+        create_info: AllocationCreateInfo,
+        result: CallResult,
+    },
+    CreateImage {
+        pool: u64,
+        allocation: u64,
+        image: RecordedImage,
+        create_info: AllocationCreateInfo,
+        result: CallResult,
+    },
+    /// Any of `free_memory`, `free_memory_pages`, `destroy_buffer` and `destroy_image`.
+    FreeMemory {
+        allocations: Vec<u64>,
+    },
+    MapMemory {
+        allocation: u64,
+        result: CallResult,
+    },
+    UnmapMemory {
+        allocation: u64,
+    },
+    FlushAllocation {
+        allocation: u64,
+        offset: vk::DeviceSize,
+        size: vk::DeviceSize,
+        result: CallResult,
+    },
+    InvalidateAllocation {
+        allocation: u64,
+        offset: vk::DeviceSize,
+        size: vk::DeviceSize,
+        result: CallResult,
+    },
+    /// `Allocator::flush_allocations`, with empty `offsets` or `sizes` if they were `None`.
+    FlushAllocations {
+        allocations: Vec<u64>,
+        offsets: Vec<vk::DeviceSize>,
+        sizes: Vec<vk::DeviceSize>,
+        result: CallResult,
+    },
+    /// `Allocator::invalidate_allocations`, with empty `offsets` or `sizes` if they were `None`.
+    InvalidateAllocations {
+        allocations: Vec<u64>,
+        offsets: Vec<vk::DeviceSize>,
+        sizes: Vec<vk::DeviceSize>,
+        result: CallResult,
+    },
+    SetAllocationName {
+        allocation: u64,
+        name: String,
+    },
+    /// `Allocator::check_corruption`.
+    CheckCorruption {
+        /// Raw `memory_types` argument.
+        memory_types: u32,
+        result: CallResult,
+    },
+    /// `AllocatorPool::check_corruption`.
+    CheckPoolCorruption {
+        pool: u64,
+        result: CallResult,
+    },
+    CreateVirtualBlock {
+        block: u64,
+        size: vk::DeviceSize,
+        flags: VirtualBlockCreateFlags,
+        result: CallResult,
+    },
+    DestroyVirtualBlock {
+        block: u64,
+    },
+    VirtualAllocate {
+        block: u64,
+        allocation: u64,
+        size: vk::DeviceSize,
+        alignment: vk::DeviceSize,
+        flags: VirtualAllocationCreateFlags,
+        result: CallResult,
+    },
+    VirtualFree {
+        block: u64,
+        allocation: u64,
+    },
+    ClearVirtualBlock {
+        block: u64,
+    },
+    BeginDefragmentation {
+        context: u64,
+        /// 0 to defragment the default pools.
+        pool: u64,
+        /// Raw `VmaDefragmentationFlags`.
+        flags: u32,
+        max_bytes_per_pass: vk::DeviceSize,
+        max_allocations_per_pass: u32,
+        result: CallResult,
+    },
+    /// `DefragmentationContext::begin_pass`, with no moves if there was nothing left to move.
+    DefragmentationPass {
+        context: u64,
+        moves: Vec<RecordedMove>,
+    },
+    /// `DefragmentationContext::end`, or dropping the context.
+    EndDefragmentation {
+        context: u64,
+    },
+    /// `Allocator::bind_buffer_memory` or `Allocator::bind_buffer_memory2`.
+    BindBufferMemory {
+        allocation: u64,
+        allocation_local_offset: vk::DeviceSize,
+        result: CallResult,
+    },
+    /// `Allocator::bind_image_memory` or `Allocator::bind_image_memory2`.
+    BindImageMemory {
+        allocation: u64,
+        allocation_local_offset: vk::DeviceSize,
+        result: CallResult,
+    },
+    CreateAliasingBuffer {
+        allocation: u64,
+        size: vk::DeviceSize,
+        usage: vk::BufferUsageFlags,
+        result: CallResult,
+    },
+    CreateAliasingImage {
+        allocation: u64,
+        image: RecordedImage,
+        result: CallResult,
+    },
+}
+
+impl Call {
+    /// Name of the call, e.g. `CreateBuffer`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Call::CreateAllocator { .. } => "CreateAllocator",
+            Call::DestroyAllocator => "DestroyAllocator",
+            Call::SetCurrentFrameIndex { .. } => "SetCurrentFrameIndex",
+            Call::CreatePool { .. } => "CreatePool",
+            Call::DestroyPool { .. } => "DestroyPool",
+            Call::AllocateMemory { .. } => "AllocateMemory",
+            Call::CreateBuffer { .. } => "CreateBuffer",
+            Call::CreateImage { .. } => "CreateImage",
+            Call::FreeMemory { .. } => "FreeMemory",
+            Call::MapMemory { .. } => "MapMemory",
+            Call::UnmapMemory { .. } => "UnmapMemory",
+            Call::FlushAllocation { .. } => "FlushAllocation",
+            Call::InvalidateAllocation { .. } => "InvalidateAllocation",
+            Call::FlushAllocations { .. } => "FlushAllocations",
+            Call::InvalidateAllocations { .. } => "InvalidateAllocations",
+            Call::SetAllocationName { .. } => "SetAllocationName",
+            Call::CheckCorruption { .. } => "CheckCorruption",
+            Call::CheckPoolCorruption { .. } => "CheckPoolCorruption",
+            Call::CreateVirtualBlock { .. } => "CreateVirtualBlock",
+            Call::DestroyVirtualBlock { .. } => "DestroyVirtualBlock",
+            Call::VirtualAllocate { .. } => "VirtualAllocate",
+            Call::VirtualFree { .. } => "VirtualFree",
+            Call::ClearVirtualBlock { .. } => "ClearVirtualBlock",
+            Call::BeginDefragmentation { .. } => "BeginDefragmentation",
+            Call::DefragmentationPass { .. } => "DefragmentationPass",
+            Call::EndDefragmentation { .. } => "EndDefragmentation",
+            Call::BindBufferMemory { .. } => "BindBufferMemory",
+            Call::BindImageMemory { .. } => "BindImageMemory",
+            Call::CreateAliasingBuffer { .. } => "CreateAliasingBuffer",
+            Call::CreateAliasingImage { .. } => "CreateAliasingImage",
+        }
+    }
+
+    /// Whether `replay` can re-execute the call. Binding and aliasing resources need Vulkan
+    /// objects that were created by the application, and are only counted in
+    /// `ReplayStats::unreplayable_calls`.
+    pub fn is_replayable(&self) -> bool {
+        !matches!(
+            self,
+            Call::BindBufferMemory { .. }
+                | Call::BindImageMemory { .. }
+                | Call::CreateAliasingBuffer { .. }
+                | Call::CreateAliasingImage { .. }
+        )
+    }
+
+    /// Result the call had when it was recorded.
+    pub fn result(&self) -> CallResult {
+        match self {
+            Call::CreatePool { result, .. }
+            | Call::AllocateMemory { result, .. }
+            | Call::CreateBuffer { result, .. }
+            | Call::CreateImage { result, .. }
+            | Call::MapMemory { result, .. }
+            | Call::FlushAllocation { result, .. }
+            | Call::InvalidateAllocation { result, .. }
+            | Call::FlushAllocations { result, .. }
+            | Call::InvalidateAllocations { result, .. }
+            | Call::CheckCorruption { result, .. }
+            | Call::CheckPoolCorruption { result, .. }
+            | Call::CreateVirtualBlock { result, .. }
+            | Call::VirtualAllocate { result, .. }
+            | Call::BeginDefragmentation { result, .. }
+            | Call::BindBufferMemory { result, .. }
+            | Call::BindImageMemory { result, .. }
+            | Call::CreateAliasingBuffer { result, .. }
+            | Call::CreateAliasingImage { result, .. } => *result,
+            _ => CallResult::Success,
+        }
+    }
+}
+
+/// Call read from a recording.
+#[derive(Debug, Clone)]
+pub struct Record {
+    /// Index of the thread that made the call, in order of the first call of every thread.
+    pub thread: u32,
+    /// Time of the call since the recorder was created.
+    pub time: Duration,
+    pub call: Call,
+}
+
+/// Writes the calls of the allocators and virtual blocks it is attached to, see
+/// `AllocatorCreateInfo::recorder` and `VirtualBlockCreateInfo::recorder`.
+///
+/// Write errors don't affect the allocator. The first one is printed to stderr and stops the
+/// recording.
+pub struct Recorder {
+    start: Instant,
+    output: Mutex<Box<dyn Write + Send>>,
+    failed: AtomicBool,
+}
+
+impl Recorder {
+    /// Creates a recorder writing to `output`, which should be buffered.
+    pub fn new<W: Write + Send + 'static>(mut output: W) -> io::Result<Arc<Self>> {
+        output.write_all(MAGIC)?;
+        output.write_all(&RECORDING_VERSION.to_le_bytes())?;
+        Ok(Arc::new(Recorder {
+            start: Instant::now(),
+            output: Mutex::new(Box::new(output)),
+            failed: AtomicBool::new(false),
+        }))
+    }
+
+    /// Creates a recorder writing to the file at `path`.
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Arc<Self>> {
+        Self::new(BufWriter::new(File::create(path)?))
+    }
+
+    /// Flushes the recorded calls to the output.
+    pub fn flush(&self) -> io::Result<()> {
+        self.output.lock().unwrap().flush()
+    }
+
+    pub(crate) fn record(&self, call: Call) {
+        if self.failed.load(Ordering::Relaxed) {
+            return;
+        }
+        let mut encoder = Encoder(Vec::with_capacity(64));
+        encoder.u32(thread_index());
+        encoder.u64(self.start.elapsed().as_nanos() as u64);
+        encoder.call(&call);
+
+        let mut output = self.output.lock().unwrap();
+        if let Err(error) = output.write_all(&encoder.0) {
+            if !self.failed.swap(true, Ordering::Relaxed) {
+                eprintln!("vk-mem: recording stopped: {}", error);
+            }
+        }
+    }
+}
+
+/// Small index of the current thread, stable for the lifetime of the process.
+fn thread_index() -> u32 {
+    static NEXT_INDEX: AtomicU32 = AtomicU32::new(0);
+    thread_local! {
+        static INDEX: Cell<Option<u32>> = Cell::new(None);
+    }
+    INDEX.with(|index| match index.get() {
+        Some(index) => index,
+        None => {
+            let next = NEXT_INDEX.fetch_add(1, Ordering::Relaxed);
+            index.set(Some(next));
+            next
+        }
+    })
+}
+
+/// Reads the records of a recording made by `Recorder`.
+pub struct RecordingReader<R> {
+    input: R,
+}
+
+impl<R: Read> RecordingReader<R> {
+    /// Checks the header of the recording.
+    pub fn new(mut input: R) -> io::Result<Self> {
+        let mut header = [0; 12];
+        input.read_exact(&mut header)?;
+        if &header[..8] != MAGIC {
+            return Err(invalid_data("not a vk-mem recording"));
+        }
+        let version = u32::from_le_bytes([header[8], header[9], header[10], header[11]]);
+        if version != RECORDING_VERSION {
+            return Err(invalid_data(format!(
+                "unsupported recording version {}, expected {}",
+                version, RECORDING_VERSION
+            )));
+        }
+        Ok(RecordingReader { input })
+    }
+
+    fn read_record(&mut self) -> io::Result<Option<Record>> {
+        let mut opcode = [0];
+        if self.input.read(&mut opcode)? == 0 {
+            return Ok(None);
+        }
+        let mut decoder = Decoder(&mut self.input);
+        let thread = decoder.u32()?;
+        let time = Duration::from_nanos(decoder.u64()?);
+        let call = decoder.call(opcode[0])?;
+        Ok(Some(Record { thread, time, call }))
+    }
+}
+
+impl<R: Read> Iterator for RecordingReader<R> {
+    type Item = io::Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_record().transpose()
+    }
+}
+
+fn invalid_data<E: Into<Box<dyn std::error::Error + Send + Sync>>>(error: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+/// Converts a Vulkan enum or flags type to its raw value.
+fn raw<T: Copy>(value: T) -> u32 {
+    assert_eq!(mem::size_of::<T>(), 4);
+    unsafe { mem::transmute_copy(&value) }
+}
+
+/// Converts a raw value back to a Vulkan enum or flags type.
+fn from_raw<T: Copy>(value: u32) -> T {
+    assert_eq!(mem::size_of::<T>(), 4);
+    unsafe { mem::transmute_copy(&value) }
+}
+
+/// `MemoryUsage` values by their index in the file format.
+#[allow(deprecated)]
+const MEMORY_USAGES: [MemoryUsage; 10] = [
+    MemoryUsage::Unknown,
+    MemoryUsage::GpuOnly,
+    MemoryUsage::CpuOnly,
+    MemoryUsage::CpuToGpu,
+    MemoryUsage::GpuToCpu,
+    MemoryUsage::CpuCopy,
+    MemoryUsage::GpuLazy,
+    MemoryUsage::Auto,
+    MemoryUsage::AutoPreferDevice,
+    MemoryUsage::AutoPreferHost,
+];
+
+mod opcode {
+    pub const CREATE_ALLOCATOR: u8 = 1;
+    pub const DESTROY_ALLOCATOR: u8 = 2;
+    pub const SET_CURRENT_FRAME_INDEX: u8 = 3;
+    pub const CREATE_POOL: u8 = 4;
+    pub const DESTROY_POOL: u8 = 5;
+    pub const ALLOCATE_MEMORY: u8 = 6;
+    pub const CREATE_BUFFER: u8 = 7;
+    pub const CREATE_IMAGE: u8 = 8;
+    pub const FREE_MEMORY: u8 = 9;
+    pub const MAP_MEMORY: u8 = 10;
+    pub const UNMAP_MEMORY: u8 = 11;
+    pub const FLUSH_ALLOCATION: u8 = 12;
+    pub const INVALIDATE_ALLOCATION: u8 = 13;
+    pub const SET_ALLOCATION_NAME: u8 = 14;
+    pub const CREATE_VIRTUAL_BLOCK: u8 = 15;
+    pub const DESTROY_VIRTUAL_BLOCK: u8 = 16;
+    pub const VIRTUAL_ALLOCATE: u8 = 17;
+    pub const VIRTUAL_FREE: u8 = 18;
+    pub const CLEAR_VIRTUAL_BLOCK: u8 = 19;
+    pub const BEGIN_DEFRAGMENTATION: u8 = 20;
+    pub const DEFRAGMENTATION_PASS: u8 = 21;
+    pub const END_DEFRAGMENTATION: u8 = 22;
+    pub const BIND_BUFFER_MEMORY: u8 = 23;
+    pub const BIND_IMAGE_MEMORY: u8 = 24;
+    pub const CREATE_ALIASING_BUFFER: u8 = 25;
+    pub const CREATE_ALIASING_IMAGE: u8 = 26;
+    pub const FLUSH_ALLOCATIONS: u8 = 27;
+    pub const INVALIDATE_ALLOCATIONS: u8 = 28;
+    pub const CHECK_CORRUPTION: u8 = 29;
+    pub const CHECK_POOL_CORRUPTION: u8 = 30;
+}
+
+/// Little-endian encoder of a single record, written with one `write_all` so that records of
+/// different threads don't interleave.
+struct Encoder(Vec<u8>);
+
+impl Encoder {
+    fn u8(&mut self, value: u8) {
+        self.0.push(value);
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn f32(&mut self, value: f32) {
+        self.u32(value.to_bits());
+    }
+
+    /// List of handles, or of other `u64` values such as offsets.
+    fn handles(&mut self, handles: &[u64]) {
+        self.u32(handles.len() as u32);
+        for &handle in handles {
+            self.u64(handle);
+        }
+    }
+
+    fn string(&mut self, value: &str) {
+        self.u32(value.len() as u32);
+        self.0.extend_from_slice(value.as_bytes());
+    }
+
+    fn result(&mut self, result: CallResult) {
+        self.u8(match result {
+            CallResult::Success => 0,
+            CallResult::OutOfDeviceMemory => 1,
+            CallResult::NoSuitableMemoryType => 2,
+            CallResult::Failed => 3,
+        });
+    }
+
+    fn create_info(&mut self, info: &AllocationCreateInfo) {
+        self.u32(info.flags.bits());
+        let usage = MEMORY_USAGES
+            .iter()
+            .position(|&usage| usage == info.usage)
+            .unwrap_or(0);
+        self.u8(usage as u8);
+        self.u32(raw(info.required_flags));
+        self.u32(raw(info.preferred_flags));
+        self.u32(info.memory_type_bits);
+        self.f32(info.priority);
+    }
+
+    fn image(&mut self, image: &RecordedImage) {
+        self.u32(raw(image.image_type));
+        self.u32(raw(image.format));
+        self.u32(image.extent.width);
+        self.u32(image.extent.height);
+        self.u32(image.extent.depth);
+        self.u32(image.mip_levels);
+        self.u32(image.array_layers);
+        self.u32(raw(image.samples));
+        self.u32(raw(image.tiling));
+        self.u32(raw(image.usage));
+    }
+
+    fn call(&mut self, call: &Call) {
+        // The opcode goes first, before the thread index and time
+        let header = mem::take(&mut self.0);
+        self.0.push(0);
+        self.0.extend_from_slice(&header);
+
+        let opcode = match call {
+            Call::CreateAllocator {
+                flags,
+                preferred_large_heap_block_size,
+                memory_properties,
+            } => {
+                self.u32(flags.bits());
+                self.u64(*preferred_large_heap_block_size);
+                self.u32(memory_properties.memory_heap_count);
+                for heap in
+                    &memory_properties.memory_heaps[..memory_properties.memory_heap_count as usize]
+                {
+                    self.u64(heap.size);
+                    self.u32(raw(heap.flags));
+                }
+                self.u32(memory_properties.memory_type_count);
+                for memory_type in
+                    &memory_properties.memory_types[..memory_properties.memory_type_count as usize]
+                {
+                    self.u32(raw(memory_type.property_flags));
+                    self.u32(memory_type.heap_index);
+                }
+                opcode::CREATE_ALLOCATOR
+            }
+            Call::DestroyAllocator => opcode::DESTROY_ALLOCATOR,
+            Call::SetCurrentFrameIndex { frame_index } => {
+                self.u32(*frame_index);
+                opcode::SET_CURRENT_FRAME_INDEX
+            }
+            Call::CreatePool {
+                pool,
+                memory_type_index,
+                flags,
+                block_size,
+                min_block_count,
+                max_block_count,
+                min_allocation_alignment,
+                result,
+            } => {
+                self.u64(*pool);
+                self.u32(*memory_type_index);
+                self.u32(flags.bits());
+                self.u64(*block_size);
+                self.u64(*min_block_count);
+                self.u64(*max_block_count);
+                self.u64(*min_allocation_alignment);
+                self.result(*result);
+                opcode::CREATE_POOL
+            }
+            Call::DestroyPool { pool } => {
+                self.u64(*pool);
+                opcode::DESTROY_POOL
+            }
+            Call::AllocateMemory {
+                pool,
+                allocations,
+                memory_requirements,
+                create_info,
+                result,
+            } => {
+                self.u64(*pool);
+                self.handles(allocations);
+                self.u64(memory_requirements.size);
+                self.u64(memory_requirements.alignment);
+                self.u32(memory_requirements.memory_type_bits);
+                self.create_info(create_info);
+                self.result(*result);
+                opcode::ALLOCATE_MEMORY
+            }
+            Call::CreateBuffer {
+                pool,
+                allocation,
+                size,
+                usage,
+                min_alignment,
+                create_info,
+                result,
+            } => {
+                self.u64(*pool);
+                self.u64(*allocation);
+                self.u64(*size);
+                self.u32(raw(*usage));
+                self.u8(min_alignment.is_some() as u8);
+                self.u64(min_alignment.unwrap_or(0));
+                self.create_info(create_info);
+                self.result(*result);
+                opcode::CREATE_BUFFER
+            }
+            Call::CreateImage {
+                pool,
+                allocation,
+                image,
+                create_info,
+                result,
+            } => {
+                self.u64(*pool);
+                self.u64(*allocation);
+                self.image(image);
+                self.create_info(create_info);
+                self.result(*result);
+                opcode::CREATE_IMAGE
+            }
+            Call::FreeMemory { allocations } => {
+                self.handles(allocations);
+                opcode::FREE_MEMORY
+            }
+            Call::MapMemory { allocation, result } => {
+                self.u64(*allocation);
+                self.result(*result);
+                opcode::MAP_MEMORY
+            }
+            Call::UnmapMemory { allocation } => {
+                self.u64(*allocation);
+                opcode::UNMAP_MEMORY
+            }
+            Call::FlushAllocation {
+                allocation,
+                offset,
+                size,
+                result,
+            } => {
+                self.u64(*allocation);
+                self.u64(*offset);
+                self.u64(*size);
+                self.result(*result);
+                opcode::FLUSH_ALLOCATION
+            }
+            Call::InvalidateAllocation {
+                allocation,
+                offset,
+                size,
+                result,
+            } => {
+                self.u64(*allocation);
+                self.u64(*offset);
+                self.u64(*size);
+                self.result(*result);
+                opcode::INVALIDATE_ALLOCATION
+            }
+            Call::FlushAllocations {
+                allocations,
+                offsets,
+                sizes,
+                result,
+            } => {
+                self.handles(allocations);
+                self.handles(offsets);
+                self.handles(sizes);
+                self.result(*result);
+                opcode::FLUSH_ALLOCATIONS
+            }
+            Call::InvalidateAllocations {
+                allocations,
+                offsets,
+                sizes,
+                result,
+            } => {
+                self.handles(allocations);
+                self.handles(offsets);
+                self.handles(sizes);
+                self.result(*result);
+                opcode::INVALIDATE_ALLOCATIONS
+            }
+            Call::SetAllocationName { allocation, name } => {
+                self.u64(*allocation);
+                self.string(name);
+                opcode::SET_ALLOCATION_NAME
+            }
+            Call::CheckCorruption {
+                memory_types,
+                result,
+            } => {
+                self.u32(*memory_types);
+                self.result(*result);
+                opcode::CHECK_CORRUPTION
+            }
+            Call::CheckPoolCorruption { pool, result } => {
+                self.u64(*pool);
+                self.result(*result);
+                opcode::CHECK_POOL_CORRUPTION
+            }
+            Call::CreateVirtualBlock {
+                block,
+                size,
+                flags,
+                result,
+            } => {
+                self.u64(*block);
+                self.u64(*size);
+                self.u32(flags.bits());
+                self.result(*result);
+                opcode::CREATE_VIRTUAL_BLOCK
+            }
+            Call::DestroyVirtualBlock { block } => {
+                self.u64(*block);
+                opcode::DESTROY_VIRTUAL_BLOCK
+            }
+            Call::VirtualAllocate {
+                block,
+                allocation,
+                size,
+                alignment,
+                flags,
+                result,
+            } => {
+                self.u64(*block);
+                self.u64(*allocation);
+                self.u64(*size);
+                self.u64(*alignment);
+                self.u32(flags.bits());
+                self.result(*result);
+                opcode::VIRTUAL_ALLOCATE
+            }
+            Call::VirtualFree { block, allocation } => {
+                self.u64(*block);
+                self.u64(*allocation);
+                opcode::VIRTUAL_FREE
+            }
+            Call::ClearVirtualBlock { block } => {
+                self.u64(*block);
+                opcode::CLEAR_VIRTUAL_BLOCK
+            }
+            Call::BeginDefragmentation {
+                context,
+                pool,
+                flags,
+                max_bytes_per_pass,
+                max_allocations_per_pass,
+                result,
+            } => {
+                self.u64(*context);
+                self.u64(*pool);
+                self.u32(*flags);
+                self.u64(*max_bytes_per_pass);
+                self.u32(*max_allocations_per_pass);
+                self.result(*result);
+                opcode::BEGIN_DEFRAGMENTATION
+            }
+            Call::DefragmentationPass { context, moves } => {
+                self.u64(*context);
+                self.u32(moves.len() as u32);
+                for move_ in moves {
+                    self.u64(move_.src_allocation);
+                    self.u64(move_.dst_tmp_allocation);
+                    self.u8(move_.operation as u8);
+                }
+                opcode::DEFRAGMENTATION_PASS
+            }
+            Call::EndDefragmentation { context } => {
+                self.u64(*context);
+                opcode::END_DEFRAGMENTATION
+            }
+            Call::BindBufferMemory {
+                allocation,
+                allocation_local_offset,
+                result,
+            } => {
+                self.u64(*allocation);
+                self.u64(*allocation_local_offset);
+                self.result(*result);
+                opcode::BIND_BUFFER_MEMORY
+            }
+            Call::BindImageMemory {
+                allocation,
+                allocation_local_offset,
+                result,
+            } => {
+                self.u64(*allocation);
+                self.u64(*allocation_local_offset);
+                self.result(*result);
+                opcode::BIND_IMAGE_MEMORY
+            }
+            Call::CreateAliasingBuffer {
+                allocation,
+                size,
+                usage,
+                result,
+            } => {
+                self.u64(*allocation);
+                self.u64(*size);
+                self.u32(raw(*usage));
+                self.result(*result);
+                opcode::CREATE_ALIASING_BUFFER
+            }
+            Call::CreateAliasingImage {
+                allocation,
+                image,
+                result,
+            } => {
+                self.u64(*allocation);
+                self.image(image);
+                self.result(*result);
+                opcode::CREATE_ALIASING_IMAGE
+            }
+        };
+        self.0[0] = opcode;
+    }
+}
+
+struct Decoder<'a, R>(&'a mut R);
+
+impl<'a, R: Read> Decoder<'a, R> {
+    fn bytes<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+        let mut bytes = [0; N];
+        self.0.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.bytes::<1>()?[0])
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_le_bytes(self.bytes()?))
+    }
+
+    fn u64(&mut self) -> io::Result<u64> {
+        Ok(u64::from_le_bytes(self.bytes()?))
+    }
+
+    fn f32(&mut self) -> io::Result<f32> {
+        Ok(f32::from_bits(self.u32()?))
+    }
+
+    fn handles(&mut self) -> io::Result<Vec<u64>> {
+        let count = self.u32()?;
+        (0..count).map(|_| self.u64()).collect()
+    }
+
+    fn string(&mut self) -> io::Result<String> {
+        // Read up to the length rather than allocating it upfront, it may be corrupted
+        let len = self.u32()? as u64;
+        let mut bytes = Vec::new();
+        (&mut self.0).take(len).read_to_end(&mut bytes)?;
+        if bytes.len() as u64 != len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        String::from_utf8(bytes).map_err(invalid_data)
+    }
+
+    fn result(&mut self) -> io::Result<CallResult> {
+        match self.u8()? {
+            0 => Ok(CallResult::Success),
+            1 => Ok(CallResult::OutOfDeviceMemory),
+            2 => Ok(CallResult::NoSuitableMemoryType),
+            3 => Ok(CallResult::Failed),
+            result => Err(invalid_data(format!("invalid call result {}", result))),
+        }
+    }
+
+    fn create_info(&mut self) -> io::Result<AllocationCreateInfo> {
+        Ok(AllocationCreateInfo {
+            flags: AllocationCreateFlags::from_bits_truncate(self.u32()?),
+            usage: MEMORY_USAGES
+                .get(self.u8()? as usize)
+                .copied()
+                .unwrap_or(MemoryUsage::Unknown),
+            required_flags: from_raw(self.u32()?),
+            preferred_flags: from_raw(self.u32()?),
+            memory_type_bits: self.u32()?,
+            user_data: 0,
+            priority: self.f32()?,
+        })
+    }
+
+    fn image(&mut self) -> io::Result<RecordedImage> {
+        Ok(RecordedImage {
+            image_type: from_raw(self.u32()?),
+            format: from_raw(self.u32()?),
+            extent: vk::Extent3D {
+                width: self.u32()?,
+                height: self.u32()?,
+                depth: self.u32()?,
+            },
+            mip_levels: self.u32()?,
+            array_layers: self.u32()?,
+            samples: from_raw(self.u32()?),
+            tiling: from_raw(self.u32()?),
+            usage: from_raw(self.u32()?),
+        })
+    }
+
+    fn recorded_move(&mut self) -> io::Result<RecordedMove> {
+        Ok(RecordedMove {
+            src_allocation: self.u64()?,
+            dst_tmp_allocation: self.u64()?,
+            operation: match self.u8()? {
+                0 => DefragmentationMoveOperation::VMA_DEFRAGMENTATION_MOVE_OPERATION_COPY,
+                1 => DefragmentationMoveOperation::VMA_DEFRAGMENTATION_MOVE_OPERATION_IGNORE,
+                2 => DefragmentationMoveOperation::VMA_DEFRAGMENTATION_MOVE_OPERATION_DESTROY,
+                operation => {
+                    return Err(invalid_data(format!(
+                        "invalid defragmentation move operation {}",
+                        operation
+                    )))
+                }
+            },
+        })
+    }
+
+    fn call(&mut self, opcode: u8) -> io::Result<Call> {
+        let call = match opcode {
+            opcode::CREATE_ALLOCATOR => {
+                let flags = AllocatorCreateFlags::from_bits_truncate(self.u32()?);
+                let preferred_large_heap_block_size = self.u64()?;
+                let mut memory_properties = Box::new(vk::PhysicalDeviceMemoryProperties {
+                    memory_heap_count: self.u32()?,
+                    ..Default::default()
+                });
+                let heaps = memory_properties
+                    .memory_heaps
+                    .get_mut(..memory_properties.memory_heap_count as usize)
+                    .ok_or_else(|| invalid_data("too many memory heaps"))?;
+                for heap in heaps {
+                    heap.size = self.u64()?;
+                    heap.flags = from_raw(self.u32()?);
+                }
+                memory_properties.memory_type_count = self.u32()?;
+                let memory_types = memory_properties
+                    .memory_types
+                    .get_mut(..memory_properties.memory_type_count as usize)
+                    .ok_or_else(|| invalid_data("too many memory types"))?;
+                for memory_type in memory_types {
+                    memory_type.property_flags = from_raw(self.u32()?);
+                    memory_type.heap_index = self.u32()?;
+                }
+                Call::CreateAllocator {
+                    flags,
+                    preferred_large_heap_block_size,
+                    memory_properties,
+                }
+            }
+            opcode::DESTROY_ALLOCATOR => Call::DestroyAllocator,
+            opcode::SET_CURRENT_FRAME_INDEX => Call::SetCurrentFrameIndex {
+                frame_index: self.u32()?,
+            },
+            opcode::CREATE_POOL => Call::CreatePool {
+                pool: self.u64()?,
+                memory_type_index: self.u32()?,
+                flags: AllocatorPoolCreateFlags::from_bits_truncate(self.u32()?),
+                block_size: self.u64()?,
+                min_block_count: self.u64()?,
+                max_block_count: self.u64()?,
+                min_allocation_alignment: self.u64()?,
+                result: self.result()?,
+            },
+            opcode::DESTROY_POOL => Call::DestroyPool { pool: self.u64()? },
+            opcode::ALLOCATE_MEMORY => Call::AllocateMemory {
+                pool: self.u64()?,
+                allocations: self.handles()?,
+                memory_requirements: vk::MemoryRequirements {
+                    size: self.u64()?,
+                    alignment: self.u64()?,
+                    memory_type_bits: self.u32()?,
+                },
+                create_info: self.create_info()?,
+                result: self.result()?,
+            },
+            opcode::CREATE_BUFFER => Call::CreateBuffer {
+                pool: self.u64()?,
+                allocation: self.u64()?,
+                size: self.u64()?,
+                usage: from_raw(self.u32()?),
+                min_alignment: {
+                    let has_min_alignment = self.u8()? != 0;
+                    let min_alignment = self.u64()?;
+                    Some(min_alignment).filter(|_| has_min_alignment)
+                },
+                create_info: self.create_info()?,
+                result: self.result()?,
+            },
+            opcode::CREATE_IMAGE => Call::CreateImage {
+                pool: self.u64()?,
+                allocation: self.u64()?,
+                image: self.image()?,
+                create_info: self.create_info()?,
+                result: self.result()?,
+            },
+            opcode::FREE_MEMORY => Call::FreeMemory {
+                allocations: self.handles()?,
+            },
+            opcode::MAP_MEMORY => Call::MapMemory {
+                allocation: self.u64()?,
+                result: self.result()?,
+            },
+            opcode::UNMAP_MEMORY => Call::UnmapMemory {
+                allocation: self.u64()?,
+            },
+            opcode::FLUSH_ALLOCATION => Call::FlushAllocation {
+                allocation: self.u64()?,
+                offset: self.u64()?,
+                size: self.u64()?,
+                result: self.result()?,
+            },
+            opcode::INVALIDATE_ALLOCATION => Call::InvalidateAllocation {
+                allocation: self.u64()?,
+                offset: self.u64()?,
+                size: self.u64()?,
+                result: self.result()?,
+            },
+            opcode::FLUSH_ALLOCATIONS => Call::FlushAllocations {
+                allocations: self.handles()?,
+                offsets: self.handles()?,
+                sizes: self.handles()?,
+                result: self.result()?,
+            },
+            opcode::INVALIDATE_ALLOCATIONS => Call::InvalidateAllocations {
+                allocations: self.handles()?,
+                offsets: self.handles()?,
+                sizes: self.handles()?,
+                result: self.result()?,
+            },
+            opcode::SET_ALLOCATION_NAME => Call::SetAllocationName {
+                allocation: self.u64()?,
+                name: self.string()?,
+            },
+            opcode::CHECK_CORRUPTION => Call::CheckCorruption {
+                memory_types: self.u32()?,
+                result: self.result()?,
+            },
+            opcode::CHECK_POOL_CORRUPTION => Call::CheckPoolCorruption {
+                pool: self.u64()?,
+                result: self.result()?,
+            },
+            opcode::CREATE_VIRTUAL_BLOCK => Call::CreateVirtualBlock {
+                block: self.u64()?,
+                size: self.u64()?,
+                flags: VirtualBlockCreateFlags::from_bits_truncate(self.u32()?),
+                result: self.result()?,
+            },
+            opcode::DESTROY_VIRTUAL_BLOCK => Call::DestroyVirtualBlock { block: self.u64()? },
+            opcode::VIRTUAL_ALLOCATE => Call::VirtualAllocate {
+                block: self.u64()?,
+                allocation: self.u64()?,
+                size: self.u64()?,
+                alignment: self.u64()?,
+                flags: VirtualAllocationCreateFlags::from_bits_truncate(self.u32()?),
+                result: self.result()?,
+            },
+            opcode::VIRTUAL_FREE => Call::VirtualFree {
+                block: self.u64()?,
+                allocation: self.u64()?,
+            },
+            opcode::CLEAR_VIRTUAL_BLOCK => Call::ClearVirtualBlock { block: self.u64()? },
+            opcode::BEGIN_DEFRAGMENTATION => Call::BeginDefragmentation {
+                context: self.u64()?,
+                pool: self.u64()?,
+                flags: self.u32()?,
+                max_bytes_per_pass: self.u64()?,
+                max_allocations_per_pass: self.u32()?,
+                result: self.result()?,
+            },
+            opcode::DEFRAGMENTATION_PASS => Call::DefragmentationPass {
+                context: self.u64()?,
+                moves: {
+                    let count = self.u32()?;
+                    (0..count)
+                        .map(|_| self.recorded_move())
+                        .collect::<io::Result<_>>()?
+                },
+            },
+            opcode::END_DEFRAGMENTATION => Call::EndDefragmentation {
+                context: self.u64()?,
+            },
+            opcode::BIND_BUFFER_MEMORY => Call::BindBufferMemory {
+                allocation: self.u64()?,
+                allocation_local_offset: self.u64()?,
+                result: self.result()?,
+            },
+            opcode::BIND_IMAGE_MEMORY => Call::BindImageMemory {
+                allocation: self.u64()?,
+                allocation_local_offset: self.u64()?,
+                result: self.result()?,
+            },
+            opcode::CREATE_ALIASING_BUFFER => Call::CreateAliasingBuffer {
+                allocation: self.u64()?,
+                size: self.u64()?,
+                usage: from_raw(self.u32()?),
+                result: self.result()?,
+            },
+            opcode::CREATE_ALIASING_IMAGE => Call::CreateAliasingImage {
+                allocation: self.u64()?,
+                image: self.image()?,
+                result: self.result()?,
+            },
+            opcode => return Err(invalid_data(format!("invalid opcode {}", opcode))),
+        };
+        Ok(call)
+    }
+}
+
+/// Number of calls of one kind and the time spent in them during `replay`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CallTiming {
+    pub count: u64,
+    pub duration: Duration,
+}
+
+/// Outcome of `replay`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplayStats {
+    /// Number of replayed calls.
+    pub calls: u64,
+    /// Number of calls whose result differs from the recorded one, e.g. allocations that
+    /// failed during replay but succeeded when recorded.
+    pub mismatched_results: u64,
+    /// Calls that refer to allocations, pools or virtual blocks the replay doesn't know,
+    /// e.g. because creating them failed.
+    pub skipped_calls: u64,
+    /// Calls that were recorded but can't be replayed, see `Call::is_replayable`. They are
+    /// not included in `calls`.
+    pub unreplayable_calls: u64,
+    /// Time spent in the replayed calls, excluding the bookkeeping of the replay itself.
+    pub duration: Duration,
+    /// Highest sum of `Statistics::block_bytes` over all heaps after any call.
+    pub peak_block_bytes: vk::DeviceSize,
+    /// Highest sum of `Statistics::allocation_bytes` over all heaps after any call.
+    pub peak_allocation_bytes: vk::DeviceSize,
+    /// Count and time of every kind of call, by `Call::name`.
+    pub per_call: BTreeMap<&'static str, CallTiming>,
+}
+
+impl fmt::Display for ReplayStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} calls in {:?}, {} with a different result than recorded, {} skipped",
+            self.calls, self.duration, self.mismatched_results, self.skipped_calls
+        )?;
+        if self.unreplayable_calls > 0 {
+            write!(
+                f,
+                "\n{} calls binding or aliasing resources can't be replayed, the recording \
+                 doesn't contain the Vulkan objects they use",
+                self.unreplayable_calls
+            )?;
+        }
+        write!(
+            f,
+            "\npeak memory: {} in blocks, {} in allocations",
+            Bytes(self.peak_block_bytes),
+            Bytes(self.peak_allocation_bytes)
+        )?;
+        for (name, timing) in &self.per_call {
+            write!(
+                f,
+                "\n  {}: {} calls, {:?}",
+                name, timing.count, timing.duration
+            )?;
+        }
+        Ok(())
+    }
+}
+
+enum Replayed {
+    Memory(Allocation),
+    Buffer(vk::Buffer, Allocation),
+    Image(vk::Image, Allocation),
+}
+
+impl Replayed {
+    fn allocation(&self) -> &Allocation {
+        match self {
+            Replayed::Memory(allocation)
+            | Replayed::Buffer(_, allocation)
+            | Replayed::Image(_, allocation) => allocation,
+        }
+    }
+}
+
+/// Objects created during a replay, by the handles they were recorded with.
+struct ReplayState<'a> {
+    /// First, so that defragmentation ends before the pools are destroyed.
+    defragmentations: HashMap<u64, DefragmentationContext<'a>>,
+    default_pool: AllocatorPool,
+    pools: HashMap<u64, AllocatorPool>,
+    allocations: HashMap<u64, Replayed>,
+    virtual_blocks: HashMap<u64, VirtualBlock>,
+    virtual_allocations: HashMap<(u64, u64), VirtualAllocation>,
+}
+
+impl<'a> ReplayState<'a> {
+    fn pool(&self, pool: u64) -> Option<&AllocatorPool> {
+        if pool == 0 {
+            Some(&self.default_pool)
+        } else {
+            self.pools.get(&pool)
+        }
+    }
+}
+
+/// Re-executes the calls of a recording on `allocator`, which may be created for a real
+/// device or with `Allocator::new_mock`, e.g. from the memory properties in
+/// `Call::CreateAllocator`.
+///
+/// Calls are replayed one after another in the order they were recorded, regardless of the
+/// thread that made them. Everything still alive at the end of the recording is freed.
+pub fn replay<I>(allocator: &Arc<Allocator>, records: I) -> io::Result<ReplayStats>
+where
+    I: IntoIterator<Item = io::Result<Record>>,
+{
+    let mut stats = ReplayStats::default();
+    let mut state = ReplayState {
+        defragmentations: HashMap::new(),
+        default_pool: allocator.default_pool(),
+        pools: HashMap::new(),
+        allocations: HashMap::new(),
+        virtual_blocks: HashMap::new(),
+        virtual_allocations: HashMap::new(),
+    };
+    for record in records {
+        let call = record?.call;
+        if !call.is_replayable() {
+            stats.unreplayable_calls += 1;
+            continue;
+        }
+        let start = Instant::now();
+        let result = unsafe { replay_call(allocator, &mut state, &call) };
+        let duration = start.elapsed();
+
+        stats.calls += 1;
+        stats.duration += duration;
+        let timing = stats.per_call.entry(call.name()).or_default();
+        timing.count += 1;
+        timing.duration += duration;
+        match result {
+            Some(result) if result != call.result() => stats.mismatched_results += 1,
+            Some(_) => {}
+            None => stats.skipped_calls += 1,
+        }
+
+        let budgets = allocator.get_heap_budgets().unwrap_or_default();
+        let block_bytes = budgets.iter().map(|b| b.statistics.block_bytes).sum();
+        let allocation_bytes = budgets.iter().map(|b| b.statistics.allocation_bytes).sum();
+        stats.peak_block_bytes = stats.peak_block_bytes.max(block_bytes);
+        stats.peak_allocation_bytes = stats.peak_allocation_bytes.max(allocation_bytes);
+    }
+
+    state.defragmentations.clear();
+    unsafe {
+        for (_, replayed) in state.allocations.drain() {
+            free(allocator, replayed);
+        }
+        for ((block, _), allocation) in state.virtual_allocations.drain() {
+            if let Some(virtual_block) = state.virtual_blocks.get(&block) {
+                virtual_block.free(allocation);
+            }
+        }
+    }
+    Ok(stats)
+}
+
+unsafe fn free(allocator: &Allocator, replayed: Replayed) {
+    match replayed {
+        Replayed::Memory(allocation) => allocator.free_memory(allocation),
+        Replayed::Buffer(buffer, allocation) => allocator.destroy_buffer(buffer, allocation),
+        Replayed::Image(image, allocation) => allocator.destroy_image(image, allocation),
+    }
+}
+
+/// Allocations of a batch call, or `None` if any of them is unknown.
+fn replayed_allocations<'s>(
+    state: &'s ReplayState<'_>,
+    allocations: &[u64],
+) -> Option<Vec<&'s Allocation>> {
+    allocations
+        .iter()
+        .map(|handle| state.allocations.get(handle).map(Replayed::allocation))
+        .collect()
+}
+
+/// Replays a single call, returning its result, or `None` if it was skipped.
+unsafe fn replay_call<'a>(
+    allocator: &'a Arc<Allocator>,
+    state: &mut ReplayState<'a>,
+    call: &Call,
+) -> Option<CallResult> {
+    let result = match call {
+        Call::CreateAllocator { .. } | Call::DestroyAllocator => CallResult::Success,
+        Call::SetCurrentFrameIndex { frame_index } => {
+            allocator.set_current_frame_index(*frame_index);
+            CallResult::Success
+        }
+        Call::CreatePool {
+            pool,
+            memory_type_index,
+            flags,
+            block_size,
+            min_block_count,
+            max_block_count,
+            min_allocation_alignment,
+            ..
+        } => {
+            let create_info = PoolCreateInfo::new()
+                .memory_type_index(*memory_type_index)
+                .flags(flags)
+                .block_size(*block_size)
+                .min_block_count(*min_block_count as usize)
+                .max_block_count(*max_block_count as usize)
+                .min_allocation_alignment(*min_allocation_alignment);
+            let result = allocator.create_pool(&create_info);
+            let call_result = CallResult::from(&result);
+            if let Ok(created) = result {
+                state.pools.insert(*pool, created);
+            }
+            call_result
+        }
+        Call::DestroyPool { pool } => {
+            state.pools.remove(pool)?;
+            CallResult::Success
+        }
+        Call::AllocateMemory {
+            pool: pool_handle,
+            allocations,
+            memory_requirements,
+            create_info,
+            ..
+        } => {
+            // Failed `allocate_memory_for_buffer` or `allocate_memory_for_image`
+            if memory_requirements.size == 0 {
+                return None;
+            }
+            let pool = state.pool(*pool_handle)?;
+            let result = if allocations.len() == 1 {
+                pool.allocate_memory(memory_requirements, create_info)
+                    .map(|allocation| vec![allocation])
+            } else {
+                pool.allocate_memory_pages(memory_requirements, create_info, allocations.len())
+            };
+            let call_result = CallResult::from(&result);
+            for (&handle, allocation) in allocations.iter().zip(result.into_iter().flatten()) {
+                state
+                    .allocations
+                    .insert(handle, Replayed::Memory(allocation));
+            }
+            call_result
+        }
+        Call::CreateBuffer {
+            pool: pool_handle,
+            allocation,
+            size,
+            usage,
+            min_alignment,
+            create_info,
+            ..
+        } => {
+            let pool = state.pool(*pool_handle)?;
+            let buffer_info = vk::BufferCreateInfo::builder()
+                .size(*size)
+                .usage(*usage)
+                .build();
+            let result = match min_alignment {
+                Some(min_alignment) => {
+                    pool.create_buffer_with_alignment(&buffer_info, create_info, *min_alignment)
+                }
+                None => pool.create_buffer(&buffer_info, create_info),
+            };
+            let call_result = CallResult::from(&result);
+            if let Ok((buffer, created)) = result {
+                state
+                    .allocations
+                    .insert(*allocation, Replayed::Buffer(buffer, created));
+            }
+            call_result
+        }
+        Call::CreateImage {
+            pool: pool_handle,
+            allocation,
+            image,
+            create_info,
+            ..
+        } => {
+            let pool = state.pool(*pool_handle)?;
+            let image_info = vk::ImageCreateInfo::builder()
+                .image_type(image.image_type)
+                .format(image.format)
+                .extent(image.extent)
+                .mip_levels(image.mip_levels)
+                .array_layers(image.array_layers)
+                .samples(image.samples)
+                .tiling(image.tiling)
+                .usage(image.usage)
+                .build();
+            let result = pool.create_image(&image_info, create_info);
+            let call_result = CallResult::from(&result);
+            if let Ok((created_image, created)) = result {
+                state
+                    .allocations
+                    .insert(*allocation, Replayed::Image(created_image, created));
+            }
+            call_result
+        }
+        Call::FreeMemory { allocations } => {
+            let mut skipped = false;
+            for handle in allocations {
+                match state.allocations.remove(handle) {
+                    Some(replayed) => free(allocator, replayed),
+                    None => skipped = true,
+                }
+            }
+            if skipped {
+                return None;
+            }
+            CallResult::Success
+        }
+        Call::MapMemory { allocation, .. } => match state.allocations.get_mut(allocation)? {
+            Replayed::Memory(allocation)
+            | Replayed::Buffer(_, allocation)
+            | Replayed::Image(_, allocation) => CallResult::from(&allocator.map_memory(allocation)),
+        },
+        Call::UnmapMemory { allocation } => match state.allocations.get_mut(allocation)? {
+            Replayed::Memory(allocation)
+            | Replayed::Buffer(_, allocation)
+            | Replayed::Image(_, allocation) => {
+                allocator.unmap_memory(allocation);
+                CallResult::Success
+            }
+        },
+        Call::FlushAllocation {
+            allocation,
+            offset,
+            size,
+            ..
+        } => match state.allocations.get(allocation)? {
+            Replayed::Memory(allocation)
+            | Replayed::Buffer(_, allocation)
+            | Replayed::Image(_, allocation) => CallResult::from(&allocator.flush_allocation(
+                allocation,
+                *offset as usize,
+                *size as usize,
+            )),
+        },
+        Call::InvalidateAllocation {
+            allocation,
+            offset,
+            size,
+            ..
+        } => {
+            match state.allocations.get(allocation)? {
+                Replayed::Memory(allocation)
+                | Replayed::Buffer(_, allocation)
+                | Replayed::Image(_, allocation) => CallResult::from(
+                    &allocator.invalidate_allocation(allocation, *offset as usize, *size as usize),
+                ),
+            }
+        }
+        Call::FlushAllocations {
+            allocations,
+            offsets,
+            sizes,
+            ..
+        } => {
+            let allocations = replayed_allocations(state, allocations)?;
+            CallResult::from(&allocator.flush_allocations(
+                allocations,
+                Some(offsets.as_slice()).filter(|offsets| !offsets.is_empty()),
+                Some(sizes.as_slice()).filter(|sizes| !sizes.is_empty()),
+            ))
+        }
+        Call::InvalidateAllocations {
+            allocations,
+            offsets,
+            sizes,
+            ..
+        } => {
+            let allocations = replayed_allocations(state, allocations)?;
+            CallResult::from(&allocator.invalidate_allocations(
+                allocations,
+                Some(offsets.as_slice()).filter(|offsets| !offsets.is_empty()),
+                Some(sizes.as_slice()).filter(|sizes| !sizes.is_empty()),
+            ))
+        }
+        Call::CheckCorruption { memory_types, .. } => {
+            CallResult::from(&allocator.check_corruption(from_raw(*memory_types)))
+        }
+        Call::CheckPoolCorruption { pool, .. } => {
+            CallResult::from(&state.pools.get(pool)?.check_corruption())
+        }
+        Call::SetAllocationName { allocation, name } => {
+            match state.allocations.get(allocation)? {
+                Replayed::Memory(allocation)
+                | Replayed::Buffer(_, allocation)
+                | Replayed::Image(_, allocation) => allocator.set_allocation_name(allocation, name),
+            }
+            CallResult::Success
+        }
+        Call::CreateVirtualBlock {
+            block, size, flags, ..
+        } => {
+            let mut create_info = VirtualBlockCreateInfo::new().size(*size);
+            create_info.inner.flags = flags.bits();
+            let result = VirtualBlock::new(create_info);
+            let call_result = CallResult::from(&result);
+            if let Ok(virtual_block) = result {
+                state.virtual_blocks.insert(*block, virtual_block);
+            }
+            call_result
+        }
+        Call::DestroyVirtualBlock { block } => {
+            let virtual_block = state.virtual_blocks.remove(block)?;
+            state
+                .virtual_allocations
+                .retain(|&(allocation_block, _), _| allocation_block != *block);
+            virtual_block.clear();
+            CallResult::Success
+        }
+        Call::VirtualAllocate {
+            block,
+            allocation,
+            size,
+            alignment,
+            flags,
+            ..
+        } => {
+            let virtual_block = state.virtual_blocks.get(block)?;
+            let result = virtual_block.allocate(VirtualAllocationCreateInfo {
+                size: *size,
+                alignment: *alignment,
+                user_data: 0,
+                flags: *flags,
+            });
+            let call_result = CallResult::from(&result);
+            if let Ok((created, _)) = result {
+                state
+                    .virtual_allocations
+                    .insert((*block, *allocation), created);
+            }
+            call_result
+        }
+        Call::VirtualFree { block, allocation } => {
+            let virtual_block = state.virtual_blocks.get(block)?;
+            let created = state.virtual_allocations.remove(&(*block, *allocation))?;
+            virtual_block.free(created);
+            CallResult::Success
+        }
+        Call::ClearVirtualBlock { block } => {
+            let virtual_block = state.virtual_blocks.get(block)?;
+            state
+                .virtual_allocations
+                .retain(|&(allocation_block, _), _| allocation_block != *block);
+            virtual_block.clear();
+            CallResult::Success
+        }
+        Call::BeginDefragmentation {
+            context,
+            pool,
+            flags,
+            max_bytes_per_pass,
+            max_allocations_per_pass,
+            ..
+        } => {
+            let pool = if *pool == 0 {
+                std::ptr::null_mut()
+            } else {
+                state.pools.get(pool)?.pool.0
+            };
+            let result = allocator.begin_defragmentation(&DefragmentationInfo {
+                flags: *flags,
+                pool,
+                maxBytesPerPass: *max_bytes_per_pass,
+                maxAllocationsPerPass: *max_allocations_per_pass,
+            });
+            let call_result = CallResult::from(&result);
+            if let Ok(created) = result {
+                state.defragmentations.insert(*context, created);
+            }
+            call_result
+        }
+        Call::DefragmentationPass { context, moves } => {
+            let defragmentation = state.defragmentations.get(context)?;
+            // The replay picks its own moves, the recorded operations are applied to those
+            // that move the same allocations
+            let mut operations = HashMap::new();
+            for recorded in moves {
+                if let Some(
+                    Replayed::Memory(allocation)
+                    | Replayed::Buffer(_, allocation)
+                    | Replayed::Image(_, allocation),
+                ) = state.allocations.get(&recorded.src_allocation)
+                {
+                    operations.insert(allocation.0, (recorded.src_allocation, recorded.operation));
+                }
+            }
+            let mut destroyed = Vec::new();
+            defragmentation.begin_pass(|replayed_moves| {
+                for replayed_move in replayed_moves {
+                    if let Some(&(handle, operation)) = operations.get(&replayed_move.srcAllocation)
+                    {
+                        replayed_move.operation = operation;
+                        if operation
+                            == DefragmentationMoveOperation::VMA_DEFRAGMENTATION_MOVE_OPERATION_DESTROY
+                        {
+                            destroyed.push(handle);
+                        }
+                    }
+                }
+            });
+            // Already freed at the end of the pass
+            for handle in destroyed {
+                state.allocations.remove(&handle);
+            }
+            CallResult::Success
+        }
+        Call::EndDefragmentation { context } => {
+            state.defragmentations.remove(context)?.end();
+            CallResult::Success
+        }
+        // Filtered out by `replay`
+        Call::BindBufferMemory { .. }
+        | Call::BindImageMemory { .. }
+        | Call::CreateAliasingBuffer { .. }
+        | Call::CreateAliasingImage { .. } => return None,
+    };
+    Some(result)
+}
+
+/// Raw VMA handle as recorded, 0 for null.
+pub(crate) fn handle<T>(raw: *mut T) -> u64 {
+    raw as usize as u64
+}
+
+impl Allocator {
+    /// Records the call built by `call`, if the allocator was created with a recorder.
+    pub(crate) fn record<F: FnOnce() -> Call>(&self, call: F) {
+        if let Some(recorder) = &self.recorder {
+            recorder.record(call());
+        }
+    }
+
+    /// Requirements replaying an allocation made for an existing buffer or image, which
+    /// is recorded as `Call::AllocateMemory`.
+    pub(crate) fn recorded_requirements(
+        &self,
+        allocation: ffi::VmaAllocation,
+    ) -> vk::MemoryRequirements {
+        let info = unsafe {
+            let mut info = mem::zeroed();
+            ffi::vmaGetAllocationInfo(self.internal, allocation, &mut info);
+            AllocationInfo::from(&info)
+        };
+        vk::MemoryRequirements {
+            size: info.size,
+            alignment: 1,
+            memory_type_bits: 1 << info.memory_type,
+        }
+    }
+}
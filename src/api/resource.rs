@@ -0,0 +1,240 @@
+use std::mem::ManuallyDrop;
+use std::ptr;
+use std::sync::Arc;
+
+use super::vk;
+use super::Alloc;
+use super::Allocation;
+use super::AllocationCreateInfo;
+use super::AllocationInfo;
+use super::Allocator;
+use super::PoolHandle;
+use super::Result;
+
+impl<T: Alloc> Alloc for Arc<T> {
+    fn allocator(&self) -> &Allocator {
+        (**self).allocator()
+    }
+
+    fn pool(&self) -> PoolHandle {
+        (**self).pool()
+    }
+}
+
+impl<T: Alloc> Alloc for &T {
+    fn allocator(&self) -> &Allocator {
+        (**self).allocator()
+    }
+
+    fn pool(&self) -> PoolHandle {
+        (**self).pool()
+    }
+}
+
+/// Vulkan buffer together with its memory, owned by an `Allocator` or `AllocatorPool`.
+///
+/// Both the buffer and the allocation are destroyed when this object is dropped.
+/// `A` is typically `Arc<Allocator>`, `Arc<AllocatorPool>` or a reference to either,
+/// which keeps the allocator (and the pool) alive for as long as the buffer exists.
+pub struct Buffer<A: Alloc> {
+    alloc: A,
+    buffer: vk::Buffer,
+    allocation: Allocation,
+    buffer_info: vk::BufferCreateInfo,
+    allocation_info: AllocationInfo,
+}
+unsafe impl<A: Alloc + Send> Send for Buffer<A> {}
+unsafe impl<A: Alloc + Sync> Sync for Buffer<A> {}
+
+impl<A: Alloc> Buffer<A> {
+    /// Creates a buffer, allocates memory for it and binds them together, see `Alloc::create_buffer`.
+    #[track_caller]
+    pub unsafe fn new(
+        alloc: A,
+        buffer_info: &vk::BufferCreateInfo,
+        create_info: &AllocationCreateInfo,
+    ) -> Result<Self> {
+        let (buffer, allocation) = alloc.create_buffer(buffer_info, create_info)?;
+        let allocation_info = alloc.allocator().get_allocation_info(&allocation)?;
+        Ok(Self::from_raw(
+            alloc,
+            buffer,
+            allocation,
+            buffer_info,
+            allocation_info,
+        ))
+    }
+
+    /// Takes ownership of a buffer and allocation previously created through `alloc`.
+    pub unsafe fn from_raw(
+        alloc: A,
+        buffer: vk::Buffer,
+        allocation: Allocation,
+        buffer_info: &vk::BufferCreateInfo,
+        allocation_info: AllocationInfo,
+    ) -> Self {
+        let mut buffer_info = *buffer_info;
+        buffer_info.p_next = ptr::null();
+        buffer_info.p_queue_family_indices = ptr::null();
+        Self {
+            alloc,
+            buffer,
+            allocation,
+            buffer_info,
+            allocation_info,
+        }
+    }
+
+    /// Releases ownership of the buffer and its allocation without destroying them.
+    ///
+    /// They have to be destroyed manually afterwards, e.g. with `Allocator::destroy_buffer`.
+    pub fn into_raw(self) -> (vk::Buffer, Allocation) {
+        let this = ManuallyDrop::new(self);
+        unsafe {
+            drop(ptr::read(&this.alloc));
+            drop(ptr::read(&this.allocation_info));
+            (this.buffer, ptr::read(&this.allocation))
+        }
+    }
+
+    /// Vulkan buffer handle.
+    pub fn handle(&self) -> vk::Buffer {
+        self.buffer
+    }
+
+    /// Allocation backing the buffer.
+    pub fn allocation(&self) -> &Allocation {
+        &self.allocation
+    }
+
+    /// Allocator or pool the buffer was created from.
+    pub fn alloc(&self) -> &A {
+        &self.alloc
+    }
+
+    /// Parameters the buffer was created with.
+    ///
+    /// `p_next` and `p_queue_family_indices` are not retained and are always null.
+    pub fn create_info(&self) -> &vk::BufferCreateInfo {
+        &self.buffer_info
+    }
+
+    /// Information about the allocation, as it was when the buffer was created.
+    pub fn allocation_info(&self) -> &AllocationInfo {
+        &self.allocation_info
+    }
+}
+
+impl<A: Alloc> Drop for Buffer<A> {
+    fn drop(&mut self) {
+        unsafe {
+            self.alloc
+                .allocator()
+                .destroy_buffer(self.buffer, ptr::read(&self.allocation));
+        }
+    }
+}
+
+/// Vulkan image together with its memory, owned by an `Allocator` or `AllocatorPool`.
+///
+/// Both the image and the allocation are destroyed when this object is dropped.
+/// `A` is typically `Arc<Allocator>`, `Arc<AllocatorPool>` or a reference to either,
+/// which keeps the allocator (and the pool) alive for as long as the image exists.
+pub struct Image<A: Alloc> {
+    alloc: A,
+    image: vk::Image,
+    allocation: Allocation,
+    image_info: vk::ImageCreateInfo,
+    allocation_info: AllocationInfo,
+}
+unsafe impl<A: Alloc + Send> Send for Image<A> {}
+unsafe impl<A: Alloc + Sync> Sync for Image<A> {}
+
+impl<A: Alloc> Image<A> {
+    /// Creates an image, allocates memory for it and binds them together, see `Alloc::create_image`.
+    #[track_caller]
+    pub unsafe fn new(
+        alloc: A,
+        image_info: &vk::ImageCreateInfo,
+        create_info: &AllocationCreateInfo,
+    ) -> Result<Self> {
+        let (image, allocation) = alloc.create_image(image_info, create_info)?;
+        let allocation_info = alloc.allocator().get_allocation_info(&allocation)?;
+        Ok(Self::from_raw(
+            alloc,
+            image,
+            allocation,
+            image_info,
+            allocation_info,
+        ))
+    }
+
+    /// Takes ownership of an image and allocation previously created through `alloc`.
+    pub unsafe fn from_raw(
+        alloc: A,
+        image: vk::Image,
+        allocation: Allocation,
+        image_info: &vk::ImageCreateInfo,
+        allocation_info: AllocationInfo,
+    ) -> Self {
+        let mut image_info = *image_info;
+        image_info.p_next = ptr::null();
+        image_info.p_queue_family_indices = ptr::null();
+        Self {
+            alloc,
+            image,
+            allocation,
+            image_info,
+            allocation_info,
+        }
+    }
+
+    /// Releases ownership of the image and its allocation without destroying them.
+    ///
+    /// They have to be destroyed manually afterwards, e.g. with `Allocator::destroy_image`.
+    pub fn into_raw(self) -> (vk::Image, Allocation) {
+        let this = ManuallyDrop::new(self);
+        unsafe {
+            drop(ptr::read(&this.alloc));
+            drop(ptr::read(&this.allocation_info));
+            (this.image, ptr::read(&this.allocation))
+        }
+    }
+
+    /// Vulkan image handle.
+    pub fn handle(&self) -> vk::Image {
+        self.image
+    }
+
+    /// Allocation backing the image.
+    pub fn allocation(&self) -> &Allocation {
+        &self.allocation
+    }
+
+    /// Allocator or pool the image was created from.
+    pub fn alloc(&self) -> &A {
+        &self.alloc
+    }
+
+    /// Parameters the image was created with.
+    ///
+    /// `p_next` and `p_queue_family_indices` are not retained and are always null.
+    pub fn create_info(&self) -> &vk::ImageCreateInfo {
+        &self.image_info
+    }
+
+    /// Information about the allocation, as it was when the image was created.
+    pub fn allocation_info(&self) -> &AllocationInfo {
+        &self.allocation_info
+    }
+}
+
+impl<A: Alloc> Drop for Image<A> {
+    fn drop(&mut self) {
+        unsafe {
+            self.alloc
+                .allocator()
+                .destroy_image(self.image, ptr::read(&self.allocation));
+        }
+    }
+}
@@ -0,0 +1,367 @@
+use std::fmt;
+use std::ops::{Add, Sub};
+
+use super::ffi;
+use super::vk;
+
+/// Calculated statistics of memory usage e.g. in a specific memory type, heap, custom pool,
+/// or total.
+///
+/// These are fast to calculate, see `AllocatorPool::get_statistics` and `Budget::statistics`.
+///
+/// Subtraction is meant to measure growth between two snapshots and saturates at zero.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Statistics {
+    /// Number of `vk::DeviceMemory` objects - Vulkan memory blocks allocated.
+    pub block_count: u32,
+    /// Number of `Allocation` objects allocated.
+    ///
+    /// Dedicated allocations have their own blocks, so each one adds 1 to `allocation_count`
+    /// as well as `block_count`.
+    pub allocation_count: u32,
+    /// Number of bytes allocated in `vk::DeviceMemory` blocks.
+    pub block_bytes: vk::DeviceSize,
+    /// Total number of bytes occupied by all `Allocation` objects.
+    ///
+    /// Always less or equal than `block_bytes`. Difference `block_bytes - allocation_bytes`
+    /// is the amount of memory allocated from Vulkan but unused by any `Allocation`.
+    pub allocation_bytes: vk::DeviceSize,
+}
+
+/// More detailed statistics than `Statistics`, slower to calculate.
+///
+/// Adding two values combines their minimums and maximums. Subtracting can't undo that,
+/// so the minimums and maximums of the left-hand side are kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DetailedStatistics {
+    /// Basic statistics.
+    pub statistics: Statistics,
+    /// Number of free ranges of memory between allocations.
+    pub unused_range_count: u32,
+    /// Smallest allocation size. `VK_WHOLE_SIZE` if there are 0 allocations.
+    pub allocation_size_min: vk::DeviceSize,
+    /// Largest allocation size. 0 if there are 0 allocations.
+    pub allocation_size_max: vk::DeviceSize,
+    /// Smallest empty range size. `VK_WHOLE_SIZE` if there are 0 empty ranges.
+    pub unused_range_size_min: vk::DeviceSize,
+    /// Largest empty range size. 0 if there are 0 empty ranges.
+    pub unused_range_size_max: vk::DeviceSize,
+}
+
+/// General statistics from current state of the `Allocator`, see `Allocator::calculate_statistics`.
+///
+/// Adding or subtracting two values panics if they describe a different number of memory
+/// types or heaps.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TotalStatistics {
+    /// Statistics of every memory type, indexed by memory type index.
+    pub per_memory_type: Vec<DetailedStatistics>,
+    /// Statistics of every memory heap, indexed by memory heap index.
+    pub per_heap: Vec<DetailedStatistics>,
+    /// Statistics summed over all memory types.
+    pub total: DetailedStatistics,
+}
+
+/// Statistics of current memory usage and available budget for a specific memory heap,
+/// see `Allocator::get_heap_budgets`.
+///
+/// Subtraction is meant to measure growth between two snapshots and saturates at zero.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Budget {
+    /// Statistics fetched from the library.
+    pub statistics: Statistics,
+    /// Estimated current memory usage of the program, in bytes.
+    ///
+    /// Fetched from system using `VK_EXT_memory_budget` extension if enabled.
+    ///
+    /// It might be different than `statistics.block_bytes` (usually higher) due to additional
+    /// implicit objects also occupying the memory, like swapchain, pipelines, descriptor heaps,
+    /// command buffers, or `vk::DeviceMemory` blocks allocated outside of this library,
+    /// if any.
+    pub usage: vk::DeviceSize,
+    /// Estimated amount of memory available to the program, in bytes.
+    ///
+    /// Fetched from system using `VK_EXT_memory_budget` extension if enabled.
+    ///
+    /// It might be different (most probably smaller) than the heap size due to factors
+    /// external to the program, decided by the operating system.
+    /// Difference `budget - usage` is the amount of additional memory that can probably
+    /// be allocated without problems. Exceeding the budget may result in various problems.
+    pub budget: vk::DeviceSize,
+}
+
+impl Default for DetailedStatistics {
+    /// Statistics of no allocations, with the minimums at `VK_WHOLE_SIZE` like VMA
+    /// reports them.
+    fn default() -> Self {
+        DetailedStatistics {
+            statistics: Statistics::default(),
+            unused_range_count: 0,
+            allocation_size_min: vk::DeviceSize::MAX,
+            allocation_size_max: 0,
+            unused_range_size_min: vk::DeviceSize::MAX,
+            unused_range_size_max: 0,
+        }
+    }
+}
+
+impl TotalStatistics {
+    /// Converts `stats`, keeping only the first `memory_type_count` memory types and
+    /// `memory_heap_count` heaps.
+    pub(crate) fn from_ffi(
+        stats: &ffi::VmaTotalStatistics,
+        memory_type_count: u32,
+        memory_heap_count: u32,
+    ) -> Self {
+        TotalStatistics {
+            per_memory_type: stats.memoryType[..memory_type_count as usize]
+                .iter()
+                .map(DetailedStatistics::from)
+                .collect(),
+            per_heap: stats.memoryHeap[..memory_heap_count as usize]
+                .iter()
+                .map(DetailedStatistics::from)
+                .collect(),
+            total: DetailedStatistics::from(&stats.total),
+        }
+    }
+}
+
+impl From<&ffi::VmaStatistics> for Statistics {
+    fn from(stats: &ffi::VmaStatistics) -> Self {
+        Statistics {
+            block_count: stats.blockCount,
+            allocation_count: stats.allocationCount,
+            block_bytes: stats.blockBytes,
+            allocation_bytes: stats.allocationBytes,
+        }
+    }
+}
+
+impl From<&ffi::VmaDetailedStatistics> for DetailedStatistics {
+    fn from(stats: &ffi::VmaDetailedStatistics) -> Self {
+        DetailedStatistics {
+            statistics: Statistics::from(&stats.statistics),
+            unused_range_count: stats.unusedRangeCount,
+            allocation_size_min: stats.allocationSizeMin,
+            allocation_size_max: stats.allocationSizeMax,
+            unused_range_size_min: stats.unusedRangeSizeMin,
+            unused_range_size_max: stats.unusedRangeSizeMax,
+        }
+    }
+}
+
+impl From<&ffi::VmaBudget> for Budget {
+    fn from(budget: &ffi::VmaBudget) -> Self {
+        Budget {
+            statistics: Statistics::from(&budget.statistics),
+            usage: budget.usage,
+            budget: budget.budget,
+        }
+    }
+}
+
+impl Add for Statistics {
+    type Output = Statistics;
+
+    fn add(self, rhs: Statistics) -> Statistics {
+        Statistics {
+            block_count: self.block_count + rhs.block_count,
+            allocation_count: self.allocation_count + rhs.allocation_count,
+            block_bytes: self.block_bytes + rhs.block_bytes,
+            allocation_bytes: self.allocation_bytes + rhs.allocation_bytes,
+        }
+    }
+}
+
+impl Sub for Statistics {
+    type Output = Statistics;
+
+    fn sub(self, rhs: Statistics) -> Statistics {
+        Statistics {
+            block_count: self.block_count.saturating_sub(rhs.block_count),
+            allocation_count: self.allocation_count.saturating_sub(rhs.allocation_count),
+            block_bytes: self.block_bytes.saturating_sub(rhs.block_bytes),
+            allocation_bytes: self.allocation_bytes.saturating_sub(rhs.allocation_bytes),
+        }
+    }
+}
+
+impl Add for DetailedStatistics {
+    type Output = DetailedStatistics;
+
+    fn add(self, rhs: DetailedStatistics) -> DetailedStatistics {
+        DetailedStatistics {
+            statistics: self.statistics + rhs.statistics,
+            unused_range_count: self.unused_range_count + rhs.unused_range_count,
+            allocation_size_min: self.allocation_size_min.min(rhs.allocation_size_min),
+            allocation_size_max: self.allocation_size_max.max(rhs.allocation_size_max),
+            unused_range_size_min: self.unused_range_size_min.min(rhs.unused_range_size_min),
+            unused_range_size_max: self.unused_range_size_max.max(rhs.unused_range_size_max),
+        }
+    }
+}
+
+impl Sub for DetailedStatistics {
+    type Output = DetailedStatistics;
+
+    fn sub(self, rhs: DetailedStatistics) -> DetailedStatistics {
+        DetailedStatistics {
+            statistics: self.statistics - rhs.statistics,
+            unused_range_count: self
+                .unused_range_count
+                .saturating_sub(rhs.unused_range_count),
+            ..self
+        }
+    }
+}
+
+impl Add for TotalStatistics {
+    type Output = TotalStatistics;
+
+    fn add(self, rhs: TotalStatistics) -> TotalStatistics {
+        TotalStatistics {
+            per_memory_type: zip_with(self.per_memory_type, rhs.per_memory_type, Add::add),
+            per_heap: zip_with(self.per_heap, rhs.per_heap, Add::add),
+            total: self.total + rhs.total,
+        }
+    }
+}
+
+impl Sub for TotalStatistics {
+    type Output = TotalStatistics;
+
+    fn sub(self, rhs: TotalStatistics) -> TotalStatistics {
+        TotalStatistics {
+            per_memory_type: zip_with(self.per_memory_type, rhs.per_memory_type, Sub::sub),
+            per_heap: zip_with(self.per_heap, rhs.per_heap, Sub::sub),
+            total: self.total - rhs.total,
+        }
+    }
+}
+
+impl Add for Budget {
+    type Output = Budget;
+
+    fn add(self, rhs: Budget) -> Budget {
+        Budget {
+            statistics: self.statistics + rhs.statistics,
+            usage: self.usage + rhs.usage,
+            budget: self.budget + rhs.budget,
+        }
+    }
+}
+
+impl Sub for Budget {
+    type Output = Budget;
+
+    fn sub(self, rhs: Budget) -> Budget {
+        Budget {
+            statistics: self.statistics - rhs.statistics,
+            usage: self.usage.saturating_sub(rhs.usage),
+            budget: self.budget.saturating_sub(rhs.budget),
+        }
+    }
+}
+
+fn zip_with(
+    lhs: Vec<DetailedStatistics>,
+    rhs: Vec<DetailedStatistics>,
+    op: impl Fn(DetailedStatistics, DetailedStatistics) -> DetailedStatistics,
+) -> Vec<DetailedStatistics> {
+    assert_eq!(
+        lhs.len(),
+        rhs.len(),
+        "statistics of allocators with different memory layouts can't be combined"
+    );
+    lhs.into_iter()
+        .zip(rhs)
+        .map(|(lhs, rhs)| op(lhs, rhs))
+        .collect()
+}
+
+/// Formats a number of bytes with a binary unit, e.g. `1.50 MiB`.
+pub(crate) struct Bytes(pub vk::DeviceSize);
+
+impl fmt::Display for Bytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+        if self.0 < 1024 {
+            return write!(f, "{} B", self.0);
+        }
+        let mut value = self.0 as f64 / 1024.0;
+        let mut unit = 0;
+        while value >= 1024.0 && unit + 1 < UNITS.len() {
+            value /= 1024.0;
+            unit += 1;
+        }
+        write!(f, "{:.2} {}", value, UNITS[unit])
+    }
+}
+
+impl fmt::Display for Statistics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} allocations ({}) in {} blocks ({})",
+            self.allocation_count,
+            Bytes(self.allocation_bytes),
+            self.block_count,
+            Bytes(self.block_bytes)
+        )
+    }
+}
+
+impl fmt::Display for DetailedStatistics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.statistics)?;
+        if self.statistics.allocation_count > 0 {
+            write!(
+                f,
+                ", allocation sizes {}..{}",
+                Bytes(self.allocation_size_min),
+                Bytes(self.allocation_size_max)
+            )?;
+        }
+        write!(f, ", {} unused ranges", self.unused_range_count)?;
+        if self.unused_range_count > 0 {
+            write!(
+                f,
+                " of sizes {}..{}",
+                Bytes(self.unused_range_size_min),
+                Bytes(self.unused_range_size_max)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Prints the total followed by one line for every heap and memory type that has any blocks.
+impl fmt::Display for TotalStatistics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Total: {}", self.total)?;
+        for (index, stats) in self.per_heap.iter().enumerate() {
+            if stats.statistics.block_count > 0 {
+                write!(f, "\nHeap {}: {}", index, stats)?;
+            }
+        }
+        for (index, stats) in self.per_memory_type.iter().enumerate() {
+            if stats.statistics.block_count > 0 {
+                write!(f, "\nMemory type {}: {}", index, stats)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Budget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} of {} used, {}",
+            Bytes(self.usage),
+            Bytes(self.budget),
+            self.statistics
+        )
+    }
+}
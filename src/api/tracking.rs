@@ -0,0 +1,116 @@
+//! Registry of live allocations with the call site that made them, enabled by the
+//! `track_allocations` feature.
+
+use std::backtrace::{Backtrace, BacktraceStatus};
+use std::collections::HashMap;
+use std::panic::Location;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use super::ffi;
+use super::vk;
+use super::AllocationInfo;
+use super::Allocator;
+use super::PoolHandle;
+
+/// Registry entry of an allocation made through the `Alloc` trait, see
+/// `Allocator::live_allocations`.
+#[derive(Debug, Clone)]
+pub struct AllocationRecord {
+    /// Code that called the `Alloc` function, or `Buffer::new` and `Image::new`.
+    pub location: &'static Location<'static>,
+    /// Backtrace of the allocation, only captured if enabled through `RUST_BACKTRACE` or
+    /// `RUST_LIB_BACKTRACE`, see `std::backtrace::Backtrace::capture`.
+    pub backtrace: Option<Arc<Backtrace>>,
+    /// Size of the allocation in bytes.
+    pub size: vk::DeviceSize,
+    /// Memory type index of the allocation.
+    pub memory_type: u32,
+    /// Custom pool of the allocation, `None` for the default pools.
+    pub pool: Option<PoolHandle>,
+    /// Time the allocation was made.
+    pub timestamp: Instant,
+}
+
+/// Live allocations of an allocator, keyed by their raw handle.
+///
+/// Every record is numbered, as the timestamps of allocations made in quick succession may
+/// be equal.
+#[derive(Default)]
+pub(crate) struct AllocationRegistry {
+    records: Mutex<HashMap<usize, (u64, AllocationRecord)>>,
+    next_sequence: AtomicU64,
+}
+
+impl AllocationRegistry {
+    pub(crate) fn location(
+        &self,
+        allocation: ffi::VmaAllocation,
+    ) -> Option<&'static Location<'static>> {
+        let records = self.records.lock().unwrap();
+        records
+            .get(&(allocation as usize))
+            .map(|(_, record)| record.location)
+    }
+
+    pub(crate) fn remove(&self, allocation: ffi::VmaAllocation) {
+        self.records.lock().unwrap().remove(&(allocation as usize));
+    }
+
+    /// Forgets all allocations of `pool`, which is being destroyed.
+    pub(crate) fn remove_pool(&self, pool: PoolHandle) {
+        let mut records = self.records.lock().unwrap();
+        records.retain(|_, (_, record)| record.pool != Some(pool));
+    }
+}
+
+impl Allocator {
+    /// Adds `allocation`, made by the code at `location`, to the registry.
+    pub(crate) fn track_allocation(
+        &self,
+        allocation: ffi::VmaAllocation,
+        pool: PoolHandle,
+        location: &'static Location<'static>,
+    ) {
+        let info = unsafe {
+            let mut info = std::mem::zeroed();
+            ffi::vmaGetAllocationInfo(self.internal, allocation, &mut info);
+            AllocationInfo::from(&info)
+        };
+        let backtrace = Backtrace::capture();
+        let record = AllocationRecord {
+            location,
+            backtrace: match backtrace.status() {
+                BacktraceStatus::Captured => Some(Arc::new(backtrace)),
+                _ => None,
+            },
+            size: info.size,
+            memory_type: info.memory_type,
+            pool: Some(pool).filter(|pool| !pool.is_default()),
+            timestamp: Instant::now(),
+        };
+        let registry = &self.allocation_registry;
+        let sequence = registry.next_sequence.fetch_add(1, Ordering::Relaxed);
+        let mut records = registry.records.lock().unwrap();
+        records.insert(allocation as usize, (sequence, record));
+    }
+
+    /// Returns the allocations made through the `Alloc` trait that have not been freed yet,
+    /// oldest first.
+    ///
+    /// Useful to find out which code is responsible for a block in the stats dump, e.g. by
+    /// grouping the records by `AllocationRecord::location` and summing their sizes.
+    pub fn live_allocations(&self) -> Vec<AllocationRecord> {
+        let mut records: Vec<(u64, AllocationRecord)> = self
+            .allocation_registry
+            .records
+            .lock()
+            .unwrap()
+            .values()
+            .cloned()
+            .collect();
+        records.sort_by_key(|(sequence, _)| *sequence);
+        records.into_iter().map(|(_, record)| record).collect()
+    }
+}
@@ -0,0 +1,94 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use super::ffi;
+use super::Allocation;
+use super::Allocator;
+
+/// Typed user data attached to allocations, keyed by the raw allocation handle.
+///
+/// Kept on the Rust side instead of in VMA's `pUserData`, so it doesn't interfere with
+/// `Allocator::set_allocation_user_data` and `AllocationInfo::user_data`.
+#[derive(Default)]
+pub(crate) struct UserDataMap {
+    values: Mutex<HashMap<usize, UserData>>,
+}
+
+struct UserData {
+    value: Box<dyn Any + Send + Sync>,
+    type_name: &'static str,
+}
+
+impl UserDataMap {
+    fn insert<U: Send + Sync + 'static>(
+        &self,
+        allocation: ffi::VmaAllocation,
+        value: U,
+    ) -> Option<UserData> {
+        let user_data = UserData {
+            value: Box::new(value),
+            type_name: std::any::type_name::<U>(),
+        };
+        self.values
+            .lock()
+            .unwrap()
+            .insert(allocation as usize, user_data)
+    }
+
+    fn get(&self, allocation: ffi::VmaAllocation) -> Option<*const (dyn Any + Send + Sync)> {
+        let values = self.values.lock().unwrap();
+        values
+            .get(&(allocation as usize))
+            .map(|user_data| &*user_data.value as *const _)
+    }
+
+    /// Type name of the value attached to `allocation`, for leak reports.
+    pub(crate) fn type_name(&self, allocation: ffi::VmaAllocation) -> Option<&'static str> {
+        let values = self.values.lock().unwrap();
+        values
+            .get(&(allocation as usize))
+            .map(|user_data| user_data.type_name)
+    }
+
+    /// Drops the value attached to `allocation`, called whenever an allocation is freed.
+    pub(crate) fn remove(&self, allocation: ffi::VmaAllocation) {
+        // Dropped outside of the lock, the value's destructor may use the allocator again
+        let value = self.values.lock().unwrap().remove(&(allocation as usize));
+        drop(value);
+    }
+}
+
+impl Allocator {
+    /// Attaches `user_data` to `allocation`, dropping the value attached previously, if any.
+    ///
+    /// The value is owned by the allocator and dropped when the allocation is freed with
+    /// `Allocator::free_memory`, `Allocator::free_memory_pages`, `Allocator::destroy_buffer`
+    /// or `Allocator::destroy_image`. It is independent of the raw pointer set with
+    /// `Allocator::set_allocation_user_data`.
+    pub fn set_user_data<U: Send + Sync + 'static>(
+        &self,
+        allocation: &mut Allocation,
+        user_data: U,
+    ) {
+        let previous = self
+            .user_data
+            .insert(self.raw_allocation(allocation), user_data);
+        drop(previous);
+    }
+
+    /// Returns the value attached to `allocation` with `Allocator::set_user_data` or one of the
+    /// `Alloc::*_with_user_data` functions.
+    ///
+    /// Returns `None` if no value is attached or if it is not of type `U`.
+    pub fn user_data<'a, U: Send + Sync + 'static>(
+        &'a self,
+        allocation: &'a Allocation,
+    ) -> Option<&'a U> {
+        let value = self.user_data.get(self.raw_allocation(allocation))?;
+        // The boxed value only leaves the map when the allocation is freed or its user data is
+        // replaced, which need the allocation by value or by mutable reference. The exception is
+        // the unsafe `free_memory_pages`, after which the allocations must not be used anymore.
+        unsafe { (*value).downcast_ref() }
+    }
+}
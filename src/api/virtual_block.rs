@@ -0,0 +1,248 @@
+use super::error::ResultExt;
+use super::ffi;
+use super::leak::LeakDetection;
+#[cfg(feature = "recording")]
+use super::recording::{handle, Call, CallResult, Recorder};
+use super::vk;
+use super::{DetailedStatistics, Error, LeakedAllocation, Result, Statistics};
+use std::any::Any;
+use std::ffi::CStr;
+use std::mem;
+use std::os::raw::c_char;
+use std::ptr;
+use std::sync::Arc;
+
+use super::definitions::*;
+
+/// Handle to a virtual block object that allows to use core allocation algorithm without allocating any real GPU memory.
+///
+/// For more info: <https://gpuopen-librariesandsdks.github.io/VulkanMemoryAllocator/html/virtual_allocator.html>
+pub struct VirtualBlock {
+    internal: ffi::VmaVirtualBlock,
+    _host_allocation_callbacks: Option<Arc<dyn Any + Send + Sync>>,
+    leak_detection: Option<LeakDetection>,
+    #[cfg(feature = "recording")]
+    recorder: Option<Arc<Recorder>>,
+}
+
+/// Represents single memory allocation done inside VirtualBlock.
+#[derive(Debug)]
+pub struct VirtualAllocation(ffi::VmaVirtualAllocation);
+unsafe impl Send for VirtualAllocation {}
+unsafe impl Sync for VirtualAllocation {}
+
+impl VirtualBlock {
+    /// Creates new VirtualBlock object.
+    pub fn new(create_info: VirtualBlockCreateInfo) -> Result<Self> {
+        unsafe {
+            let mut internal: ffi::VmaVirtualBlock = mem::zeroed();
+            let result = ffi::vmaCreateVirtualBlock(&create_info.inner as *const _, &mut internal)
+                .result()
+                .context("vmaCreateVirtualBlock");
+            #[cfg(feature = "recording")]
+            if let Some(recorder) = &create_info.recorder {
+                recorder.record(Call::CreateVirtualBlock {
+                    block: handle(internal),
+                    size: create_info.inner.size,
+                    flags: VirtualBlockCreateFlags::from_bits_truncate(create_info.inner.flags),
+                    result: CallResult::from(&result),
+                });
+            }
+            result?;
+
+            Ok(VirtualBlock {
+                internal,
+                _host_allocation_callbacks: create_info.host_allocation_callbacks,
+                leak_detection: create_info.leak_handler.map(LeakDetection::new),
+                #[cfg(feature = "recording")]
+                recorder: create_info.recorder,
+            })
+        }
+    }
+
+    /// Allocates new virtual allocation inside given VirtualBlock.
+    ///
+    /// Possible error values:
+    ///
+    /// - `Error::OutOfDeviceMemory` - Allocation failed due to not enough free space in the virtual block.
+    ///     (despite the function doesn't ever allocate actual GPU memory)
+    pub unsafe fn allocate(
+        &self,
+        allocation_info: VirtualAllocationCreateInfo,
+    ) -> Result<(VirtualAllocation, u64)> {
+        let create_info: ffi::VmaVirtualAllocationCreateInfo = allocation_info.into();
+        let mut allocation: ffi::VmaVirtualAllocation = std::mem::zeroed();
+        let mut offset = 0;
+        let result =
+            ffi::vmaVirtualAllocate(self.internal, &create_info, &mut allocation, &mut offset)
+                .result()
+                .map_err(|result| {
+                    Error::sized("vmaVirtualAllocate", Some(create_info.size), result)
+                });
+        #[cfg(feature = "recording")]
+        self.record(|| Call::VirtualAllocate {
+            block: handle(self.internal),
+            allocation: handle(allocation),
+            size: allocation_info.size,
+            alignment: allocation_info.alignment,
+            flags: allocation_info.flags,
+            result: CallResult::from(&result),
+        });
+        result?;
+        if let Some(leak_detection) = &self.leak_detection {
+            leak_detection.insert(allocation as usize, 0);
+        }
+        Ok((VirtualAllocation(allocation), offset))
+    }
+
+    /// Frees virtual allocation inside given VirtualBlock.
+    ///
+    /// It is correct to call this function with `allocation == VK_NULL_HANDLE` - it does nothing.
+    pub unsafe fn free(&self, allocation: VirtualAllocation) {
+        ffi::vmaVirtualFree(self.internal, allocation.0);
+        if let Some(leak_detection) = &self.leak_detection {
+            leak_detection.remove(allocation.0 as usize);
+        }
+        #[cfg(feature = "recording")]
+        self.record(|| Call::VirtualFree {
+            block: handle(self.internal),
+            allocation: handle(allocation.0),
+        });
+    }
+
+    /// Frees all virtual allocations inside given VirtualBlock.
+    ///
+    /// You must either call this function or free each virtual allocation individually with vmaVirtualFree()
+    /// before destroying a virtual block. Otherwise, an assert is called.
+    ///
+    /// If you keep pointer to some additional metadata associated with your virtual allocation in its `user_data`,
+    /// don't forget to free it as well.
+    ///
+    /// Any VirtualAllocations created previously in the VirtualBlock will no longer be valid!
+    pub unsafe fn clear(&self) {
+        ffi::vmaClearVirtualBlock(self.internal);
+        if let Some(leak_detection) = &self.leak_detection {
+            leak_detection.take(None);
+        }
+        #[cfg(feature = "recording")]
+        self.record(|| Call::ClearVirtualBlock {
+            block: handle(self.internal),
+        });
+    }
+
+    /// Records the call built by `call`, if the block was created with a recorder.
+    #[cfg(feature = "recording")]
+    fn record<F: FnOnce() -> Call>(&self, call: F) {
+        if let Some(recorder) = &self.recorder {
+            recorder.record(call());
+        }
+    }
+
+    /// Returns information about a specific virtual allocation within a virtual block, like its size and user_data pointer.
+    pub unsafe fn get_allocation_info(
+        &self,
+        allocation: &VirtualAllocation,
+    ) -> Result<VirtualAllocationInfo> {
+        let mut allocation_info: ffi::VmaVirtualAllocationInfo = mem::zeroed();
+        ffi::vmaGetVirtualAllocationInfo(self.internal, allocation.0, &mut allocation_info);
+        Ok(allocation_info.into())
+    }
+
+    /// Changes custom pointer associated with given virtual allocation.
+    pub unsafe fn set_allocation_user_data(
+        &self,
+        allocation: &mut VirtualAllocation,
+        user_data: *mut ::std::os::raw::c_void,
+    ) {
+        ffi::vmaSetVirtualAllocationUserData(self.internal, allocation.0, user_data);
+    }
+
+    /// Returns true if the VirtualBlock is empty - contains 0 virtual allocations and has all its space available for new allocations.
+    pub fn is_empty(&self) -> bool {
+        unsafe { ffi::vmaIsVirtualBlockEmpty(self.internal) != 0 }
+    }
+
+    /// Calculates and returns statistics about virtual allocations and memory usage in given VirtualBlock.
+    ///
+    /// This function is fast to call. For more detailed statistics, see `VirtualBlock::detailed_statistics`.
+    /// `Statistics::block_count` is always 1 and `Statistics::block_bytes` is the size of the block.
+    pub fn statistics(&self) -> Statistics {
+        unsafe {
+            let mut stats: ffi::VmaStatistics = mem::zeroed();
+            ffi::vmaGetVirtualBlockStatistics(self.internal, &mut stats);
+            Statistics::from(&stats)
+        }
+    }
+
+    /// Calculates and returns detailed statistics about virtual allocations and memory usage in given VirtualBlock.
+    ///
+    /// This function is slow to call. Use for debugging purposes, e.g. to measure fragmentation
+    /// through `DetailedStatistics::unused_range_count`.
+    pub fn detailed_statistics(&self) -> DetailedStatistics {
+        unsafe {
+            let mut stats: ffi::VmaDetailedStatistics = mem::zeroed();
+            ffi::vmaCalculateVirtualBlockStatistics(self.internal, &mut stats);
+            DetailedStatistics::from(&stats)
+        }
+    }
+
+    /// Builds a string in JSON format with statistics of the VirtualBlock.
+    ///
+    /// With `detailed_map` the dump also lists every virtual allocation and free range.
+    pub fn stats_string(&self, detailed_map: bool) -> String {
+        unsafe {
+            let mut stats_string: *mut c_char = ptr::null_mut();
+            ffi::vmaBuildVirtualBlockStatsString(
+                self.internal,
+                &mut stats_string,
+                detailed_map as vk::Bool32,
+            );
+            let result = CStr::from_ptr(stats_string).to_string_lossy().into_owned();
+            ffi::vmaFreeVirtualBlockStatsString(self.internal, stats_string);
+            result
+        }
+    }
+}
+
+/// Custom `Drop` implementation to clean up internal VirtualBlock instance
+impl Drop for VirtualBlock {
+    fn drop(&mut self) {
+        let mut leak_report = None;
+        if let Some(leak_detection) = &self.leak_detection {
+            let allocations = leak_detection
+                .take(None)
+                .into_iter()
+                .map(|allocation| unsafe {
+                    let mut info: ffi::VmaVirtualAllocationInfo = mem::zeroed();
+                    ffi::vmaGetVirtualAllocationInfo(
+                        self.internal,
+                        allocation as ffi::VmaVirtualAllocation,
+                        &mut info,
+                    );
+                    LeakedAllocation {
+                        offset: info.offset,
+                        size: info.size,
+                        memory_type: None,
+                        name: None,
+                        user_data: info.pUserData as usize,
+                        user_data_type: None,
+                        location: None,
+                    }
+                })
+                .collect();
+            leak_report = leak_detection.report("VirtualBlock", allocations);
+            unsafe { ffi::vmaClearVirtualBlock(self.internal) };
+        }
+        #[cfg(feature = "recording")]
+        self.record(|| Call::DestroyVirtualBlock {
+            block: handle(self.internal),
+        });
+        unsafe {
+            ffi::vmaDestroyVirtualBlock(self.internal);
+            self.internal = std::ptr::null_mut();
+        }
+        if let Some(leak_report) = leak_report {
+            panic!("{}", leak_report);
+        }
+    }
+}
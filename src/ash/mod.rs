@@ -0,0 +1,108 @@
+//! `vk-mem` for `ash`, enabled by the `ash` feature.
+//!
+//! Every type of this module takes its handles and structures from `ash::vk`, re-exported as
+//! `vk_mem::ash::vk`, independently of whether the `spark` feature is enabled too.
+
+pub use ::ash::vk;
+
+#[path = "../api/mod.rs"]
+mod api;
+pub use self::api::*;
+
+/// Handle parameter of a Vulkan function that may be null.
+pub(crate) type Nullable<H> = H;
+
+/// Function pointer returned by `vkGetInstanceProcAddr` and `vkGetDeviceProcAddr`.
+pub(crate) type FnVoidFunction = unsafe extern "system" fn();
+
+/// Types the bindings in `ffi` refer to, with the function pointer types of `ash` under the
+/// names bindgen generates.
+pub(crate) mod ffi_types {
+    pub use super::vk::*;
+
+    pub type FnGetInstanceProcAddr = PFN_vkGetInstanceProcAddr;
+    pub type FnGetDeviceProcAddr = PFN_vkGetDeviceProcAddr;
+    pub type FnGetPhysicalDeviceProperties = PFN_vkGetPhysicalDeviceProperties;
+    pub type FnGetPhysicalDeviceMemoryProperties = PFN_vkGetPhysicalDeviceMemoryProperties;
+    pub type FnAllocateMemory = PFN_vkAllocateMemory;
+    pub type FnFreeMemory = PFN_vkFreeMemory;
+    pub type FnMapMemory = PFN_vkMapMemory;
+    pub type FnUnmapMemory = PFN_vkUnmapMemory;
+    pub type FnFlushMappedMemoryRanges = PFN_vkFlushMappedMemoryRanges;
+    pub type FnInvalidateMappedMemoryRanges = PFN_vkInvalidateMappedMemoryRanges;
+    pub type FnBindBufferMemory = PFN_vkBindBufferMemory;
+    pub type FnBindImageMemory = PFN_vkBindImageMemory;
+    pub type FnGetBufferMemoryRequirements = PFN_vkGetBufferMemoryRequirements;
+    pub type FnGetImageMemoryRequirements = PFN_vkGetImageMemoryRequirements;
+    pub type FnCreateBuffer = PFN_vkCreateBuffer;
+    pub type FnDestroyBuffer = PFN_vkDestroyBuffer;
+    pub type FnCreateImage = PFN_vkCreateImage;
+    pub type FnDestroyImage = PFN_vkDestroyImage;
+    pub type FnCmdCopyBuffer = PFN_vkCmdCopyBuffer;
+    pub type FnGetBufferMemoryRequirements2 = PFN_vkGetBufferMemoryRequirements2;
+    pub type FnGetImageMemoryRequirements2 = PFN_vkGetImageMemoryRequirements2;
+    pub type FnBindBufferMemory2 = PFN_vkBindBufferMemory2;
+    pub type FnBindImageMemory2 = PFN_vkBindImageMemory2;
+    pub type FnGetPhysicalDeviceMemoryProperties2 = PFN_vkGetPhysicalDeviceMemoryProperties2;
+    pub type FnGetDeviceBufferMemoryRequirements = PFN_vkGetDeviceBufferMemoryRequirements;
+    pub type FnGetDeviceImageMemoryRequirements = PFN_vkGetDeviceImageMemoryRequirements;
+}
+
+/// `ash` loads every function pointer eagerly and replaces the ones that are unavailable
+/// with stubs that panic, so a Vulkan 1.0 device without the 1.1 and 1.3 functions panics
+/// only once VMA calls them.
+unsafe impl Backend for ::ash::Device {
+    type Instance = ::ash::Instance;
+
+    fn instance_handle(instance: &::ash::Instance) -> vk::Instance {
+        instance.handle()
+    }
+
+    fn device_handle(&self) -> vk::Device {
+        self.handle()
+    }
+
+    unsafe fn memory_properties(
+        instance: &::ash::Instance,
+        physical_device: vk::PhysicalDevice,
+    ) -> vk::PhysicalDeviceMemoryProperties {
+        instance.get_physical_device_memory_properties(physical_device)
+    }
+
+    fn vulkan_functions(&self, instance: &::ash::Instance) -> ffi::VmaVulkanFunctions {
+        let instance_1_0 = instance.fp_v1_0();
+        let instance_1_1 = instance.fp_v1_1();
+        let device_1_0 = self.fp_v1_0();
+        let device_1_1 = self.fp_v1_1();
+        let device_1_3 = self.fp_v1_3();
+        ffi::VmaVulkanFunctions {
+            vkGetInstanceProcAddr: get_instance_proc_addr_stub,
+            vkGetDeviceProcAddr: get_device_proc_stub,
+            vkGetPhysicalDeviceProperties: instance_1_0.get_physical_device_properties,
+            vkGetPhysicalDeviceMemoryProperties: instance_1_0.get_physical_device_memory_properties,
+            vkAllocateMemory: device_1_0.allocate_memory,
+            vkFreeMemory: device_1_0.free_memory,
+            vkMapMemory: device_1_0.map_memory,
+            vkUnmapMemory: device_1_0.unmap_memory,
+            vkFlushMappedMemoryRanges: device_1_0.flush_mapped_memory_ranges,
+            vkInvalidateMappedMemoryRanges: device_1_0.invalidate_mapped_memory_ranges,
+            vkBindBufferMemory: device_1_0.bind_buffer_memory,
+            vkBindImageMemory: device_1_0.bind_image_memory,
+            vkGetBufferMemoryRequirements: device_1_0.get_buffer_memory_requirements,
+            vkGetImageMemoryRequirements: device_1_0.get_image_memory_requirements,
+            vkCreateBuffer: device_1_0.create_buffer,
+            vkDestroyBuffer: device_1_0.destroy_buffer,
+            vkCreateImage: device_1_0.create_image,
+            vkDestroyImage: device_1_0.destroy_image,
+            vkCmdCopyBuffer: device_1_0.cmd_copy_buffer,
+            vkGetBufferMemoryRequirements2KHR: device_1_1.get_buffer_memory_requirements2,
+            vkGetImageMemoryRequirements2KHR: device_1_1.get_image_memory_requirements2,
+            vkBindBufferMemory2KHR: device_1_1.bind_buffer_memory2,
+            vkBindImageMemory2KHR: device_1_1.bind_image_memory2,
+            vkGetPhysicalDeviceMemoryProperties2KHR: instance_1_1
+                .get_physical_device_memory_properties2,
+            vkGetDeviceBufferMemoryRequirements: device_1_3.get_device_buffer_memory_requirements,
+            vkGetDeviceImageMemoryRequirements: device_1_3.get_device_image_memory_requirements,
+        }
+    }
+}
@@ -0,0 +1,81 @@
+//! Replays a recording made with `vk_mem::Recorder` on a mock device and reports timing and
+//! peak memory.
+//!
+//! Usage: `vk-mem-replay [RECORDING]`
+//!
+//! Reads from stdin when no recording is given, or when it is `-`. The mock device gets the
+//! memory heaps and types of the recorded device. To replay on a real device, create an
+//! allocator for it and pass the records to `vk_mem::replay`.
+
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::iter;
+use std::process;
+use std::sync::Arc;
+
+use vk_mem::mock::MockDevice;
+use vk_mem::{Call, RecordingReader};
+
+const USAGE: &str = "usage: vk-mem-replay [RECORDING]";
+
+fn main() {
+    if let Err(message) = run() {
+        eprintln!("vk-mem-replay: {}", message);
+        process::exit(1);
+    }
+}
+
+fn run() -> Result<(), String> {
+    let mut input = None;
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            _ if input.is_none() => input = Some(arg),
+            _ => return Err(USAGE.to_owned()),
+        }
+    }
+
+    let reader: Box<dyn Read> = match input.as_deref() {
+        None | Some("-") => Box::new(io::stdin()),
+        Some(path) => {
+            Box::new(File::open(path).map_err(|error| format!("can't open {}: {}", path, error))?)
+        }
+    };
+    let mut records = RecordingReader::new(BufReader::new(reader))
+        .map_err(|error| format!("invalid recording: {}", error))?;
+
+    // Records are replayed as they are read, the allocator is created by the first one
+    let first = records
+        .next()
+        .transpose()
+        .map_err(|error| format!("invalid recording: {}", error))?
+        .ok_or("the recording is empty")?;
+    let (flags, preferred_large_heap_block_size, memory_properties) = match &first.call {
+        Call::CreateAllocator {
+            flags,
+            preferred_large_heap_block_size,
+            memory_properties,
+        } => (
+            *flags,
+            *preferred_large_heap_block_size,
+            **memory_properties,
+        ),
+        _ => return Err("the recording doesn't start with an allocator".to_owned()),
+    };
+    let device = MockDevice::new(memory_properties);
+    let create_info = device
+        .create_info()
+        .flags(flags)
+        .preferred_large_heap_block_size(preferred_large_heap_block_size);
+    let allocator = vk_mem::Allocator::new_mock(create_info)
+        .map(Arc::new)
+        .map_err(|error| format!("can't create the allocator: {}", error))?;
+
+    let stats = vk_mem::replay(&allocator, iter::once(Ok(first)).chain(records))
+        .map_err(|error| format!("replay failed: {}", error))?;
+    println!("{}", stats);
+    Ok(())
+}
@@ -0,0 +1,43 @@
+/// VMA configuration macros the bundled library was compiled with.
+///
+/// Set through the `debug_margin`, `detect_corruption`, `debug_initialize_allocations`,
+/// `debug_min_buffer_image_granularity`, `heavy_assert` and `stl_containers` crate features.
+/// The `VMA_DEBUG_MARGIN` and `VMA_DEBUG_MIN_BUFFER_IMAGE_GRANULARITY` environment variables
+/// override the numeric values at build time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BuildConfig {
+    /// `VMA_DEBUG_MARGIN`: bytes of margin before and after every allocation, 0 if disabled.
+    pub debug_margin: u64,
+    /// `VMA_DEBUG_DETECT_CORRUPTION`: the margins are filled with a magic value and
+    /// validated by `Allocator::check_corruption` and `AllocatorPool::check_corruption`.
+    pub detect_corruption: bool,
+    /// `VMA_DEBUG_INITIALIZE_ALLOCATIONS`: new and freed allocations are filled with a bit
+    /// pattern, if they are `HOST_VISIBLE`.
+    pub initialize_allocations: bool,
+    /// `VMA_DEBUG_MIN_BUFFER_IMAGE_GRANULARITY`: minimum `bufferImageGranularity` enforced
+    /// between buffers and images, 1 if disabled.
+    pub min_buffer_image_granularity: u64,
+    /// `VMA_HEAVY_ASSERT`: expensive internal consistency checks, only active together with
+    /// `VMA_ASSERT`, i.e. in debug builds.
+    pub heavy_assert: bool,
+    /// `VMA_USE_STL_CONTAINERS`: VMA uses STL containers instead of its own.
+    pub use_stl_containers: bool,
+}
+
+/// Returns the VMA configuration the bundled library was compiled with.
+pub fn build_config() -> BuildConfig {
+    BuildConfig {
+        debug_margin: parse(env!("VK_MEM_DEBUG_MARGIN")),
+        detect_corruption: parse(env!("VK_MEM_DEBUG_DETECT_CORRUPTION")),
+        initialize_allocations: parse(env!("VK_MEM_DEBUG_INITIALIZE_ALLOCATIONS")),
+        min_buffer_image_granularity: parse(env!("VK_MEM_DEBUG_MIN_BUFFER_IMAGE_GRANULARITY")),
+        heavy_assert: parse(env!("VK_MEM_HEAVY_ASSERT")),
+        use_stl_containers: parse(env!("VK_MEM_USE_STL_CONTAINERS")),
+    }
+}
+
+fn parse<T: std::str::FromStr>(value: &str) -> T {
+    value
+        .parse()
+        .unwrap_or_else(|_| panic!("invalid build configuration value {:?}", value))
+}
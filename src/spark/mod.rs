@@ -0,0 +1,80 @@
+//! `vk-mem` for `spark`, enabled by the `spark` feature and re-exported at the crate root.
+//!
+//! Every type of this module takes its handles and structures from `spark::vk`, re-exported as
+//! `vk_mem::spark::vk`.
+
+pub use ::spark::vk;
+
+#[path = "../api/mod.rs"]
+mod api;
+pub use self::api::*;
+
+/// Handle parameter of a Vulkan function that may be null.
+pub(crate) type Nullable<H> = Option<H>;
+
+/// Function pointer returned by `vkGetInstanceProcAddr` and `vkGetDeviceProcAddr`.
+pub(crate) type FnVoidFunction = vk::FnVoidFunction;
+
+/// Types the bindings in `ffi` refer to, `spark::vk` already has them under the names bindgen
+/// generates.
+pub(crate) mod ffi_types {
+    pub use super::vk::*;
+}
+
+unsafe impl Backend for ::spark::Device {
+    type Instance = ::spark::Instance;
+
+    fn instance_handle(instance: &::spark::Instance) -> vk::Instance {
+        instance.handle
+    }
+
+    fn device_handle(&self) -> vk::Device {
+        self.handle
+    }
+
+    unsafe fn memory_properties(
+        instance: &::spark::Instance,
+        physical_device: vk::PhysicalDevice,
+    ) -> vk::PhysicalDeviceMemoryProperties {
+        instance.get_physical_device_memory_properties(physical_device)
+    }
+
+    fn vulkan_functions(&self, instance: &::spark::Instance) -> ffi::VmaVulkanFunctions {
+        ffi::VmaVulkanFunctions {
+            vkGetInstanceProcAddr: get_instance_proc_addr_stub,
+            vkGetDeviceProcAddr: get_device_proc_stub,
+            vkGetPhysicalDeviceProperties: instance.fp_get_physical_device_properties.unwrap(),
+            vkGetPhysicalDeviceMemoryProperties: instance
+                .fp_get_physical_device_memory_properties
+                .unwrap(),
+            vkAllocateMemory: self.fp_allocate_memory.unwrap(),
+            vkFreeMemory: self.fp_free_memory.unwrap(),
+            vkMapMemory: self.fp_map_memory.unwrap(),
+            vkUnmapMemory: self.fp_unmap_memory.unwrap(),
+            vkFlushMappedMemoryRanges: self.fp_flush_mapped_memory_ranges.unwrap(),
+            vkInvalidateMappedMemoryRanges: self.fp_invalidate_mapped_memory_ranges.unwrap(),
+            vkBindBufferMemory: self.fp_bind_buffer_memory.unwrap(),
+            vkBindImageMemory: self.fp_bind_image_memory.unwrap(),
+            vkGetBufferMemoryRequirements: self.fp_get_buffer_memory_requirements.unwrap(),
+            vkGetImageMemoryRequirements: self.fp_get_image_memory_requirements.unwrap(),
+            vkCreateBuffer: self.fp_create_buffer.unwrap(),
+            vkDestroyBuffer: self.fp_destroy_buffer.unwrap(),
+            vkCreateImage: self.fp_create_image.unwrap(),
+            vkDestroyImage: self.fp_destroy_image.unwrap(),
+            vkCmdCopyBuffer: self.fp_cmd_copy_buffer.unwrap(),
+            vkGetBufferMemoryRequirements2KHR: self.fp_get_buffer_memory_requirements2.unwrap(),
+            vkGetImageMemoryRequirements2KHR: self.fp_get_image_memory_requirements2.unwrap(),
+            vkBindBufferMemory2KHR: self.fp_bind_buffer_memory2.unwrap(),
+            vkBindImageMemory2KHR: self.fp_bind_image_memory2.unwrap(),
+            vkGetPhysicalDeviceMemoryProperties2KHR: instance
+                .fp_get_physical_device_memory_properties2
+                .unwrap(),
+            vkGetDeviceBufferMemoryRequirements: self
+                .fp_get_device_buffer_memory_requirements
+                .unwrap(),
+            vkGetDeviceImageMemoryRequirements: self
+                .fp_get_device_image_memory_requirements
+                .unwrap(),
+        }
+    }
+}
@@ -1,37 +1,47 @@
 extern crate ash;
 extern crate vk_mem;
 
+// The tests on a real device create it with `ash`
+#[cfg(feature = "ash")]
 use ash::{extensions::ext::DebugUtils, vk};
-use std::{os::raw::c_void, sync::Arc};
+#[cfg(feature = "ash")]
+use std::os::raw::c_void;
+use std::sync::Arc;
 use vk_mem::Alloc;
+#[cfg(feature = "ash")]
+use vk_mem::ash::Alloc as _;
 
+#[cfg(feature = "ash")]
 fn extension_names() -> Vec<*const i8> {
     vec![DebugUtils::name().as_ptr()]
 }
 
+#[cfg(feature = "ash")]
 unsafe extern "system" fn vulkan_debug_callback(
-    _message_severity: spark::vk::DebugUtilsMessageSeverityFlagsEXT,
-    _message_types: spark::vk::DebugUtilsMessageTypeFlagsEXT,
-    p_callback_data: *const spark::vk::DebugUtilsMessengerCallbackDataEXT,
+    _message_severity: vk::DebugUtilsMessageSeverityFlagsEXT,
+    _message_types: vk::DebugUtilsMessageTypeFlagsEXT,
+    p_callback_data: *const vk::DebugUtilsMessengerCallbackDataEXT,
     _p_user_data: *mut c_void,
-) -> spark::vk::Bool32 {
+) -> vk::Bool32 {
     let p_callback_data = &*p_callback_data;
     println!(
         "{:?}",
         ::std::ffi::CStr::from_ptr(p_callback_data.p_message)
     );
-    spark::vk::FALSE
+    vk::FALSE
 }
 
+#[cfg(feature = "ash")]
 pub struct TestHarness {
     pub entry: ash::Entry,
     pub instance: ash::Instance,
     pub device: ash::Device,
-    pub physical_device: spark::vk::PhysicalDevice,
-    pub debug_callback: spark::vk::DebugUtilsMessengerEXT,
+    pub physical_device: vk::PhysicalDevice,
+    pub debug_callback: vk::DebugUtilsMessengerEXT,
     pub debug_report_loader: ash::extensions::ext::DebugUtils,
 }
 
+#[cfg(feature = "ash")]
 impl Drop for TestHarness {
     fn drop(&mut self) {
         unsafe {
@@ -43,15 +53,16 @@ impl Drop for TestHarness {
         }
     }
 }
+#[cfg(feature = "ash")]
 impl TestHarness {
     pub fn new() -> Self {
         let app_name = ::std::ffi::CString::new("vk-mem testing").unwrap();
-        let app_info = spark::vk::ApplicationInfo::builder()
+        let app_info = vk::ApplicationInfo::builder()
             .application_name(&app_name)
             .application_version(0)
             .engine_name(&app_name)
             .engine_version(0)
-            .api_version(spark::vk::make_api_version(0, 1, 3, 0));
+            .api_version(vk::make_api_version(0, 1, 3, 0));
 
         let layer_names = [::std::ffi::CString::new("VK_LAYER_KHRONOS_validation").unwrap()];
         let layers_names_raw: Vec<*const i8> = layer_names
@@ -60,7 +71,7 @@ impl TestHarness {
             .collect();
 
         let extension_names_raw = extension_names();
-        let create_info = spark::vk::InstanceCreateInfo::builder()
+        let create_info = vk::InstanceCreateInfo::builder()
             .application_info(&app_info)
             .enabled_layer_names(&layers_names_raw)
             .enabled_extension_names(&extension_names_raw);
@@ -72,10 +83,10 @@ impl TestHarness {
                 .expect("Instance creation error")
         };
 
-        let debug_info = spark::vk::DebugUtilsMessengerCreateInfoEXT::builder()
+        let debug_info = vk::DebugUtilsMessengerCreateInfoEXT::builder()
             .message_severity(
-                spark::vk::DebugUtilsMessageSeverityFlagsEXT::ERROR
-                    | spark::vk::DebugUtilsMessageSeverityFlagsEXT::WARNING,
+                vk::DebugUtilsMessageSeverityFlagsEXT::ERROR
+                    | vk::DebugUtilsMessageSeverityFlagsEXT::WARNING,
             )
             .message_type(
                 vk::DebugUtilsMessageTypeFlagsEXT::GENERAL
@@ -115,13 +126,13 @@ impl TestHarness {
 
         let priorities = [1.0];
 
-        let queue_info = [spark::vk::DeviceQueueCreateInfo::builder()
+        let queue_info = [vk::DeviceQueueCreateInfo::builder()
             .queue_family_index(queue_family_index as u32)
             .queue_priorities(&priorities)
             .build()];
 
         let device_create_info =
-            spark::vk::DeviceCreateInfo::builder().queue_create_infos(&queue_info);
+            vk::DeviceCreateInfo::builder().queue_create_infos(&queue_info);
 
         let device: ash::Device = unsafe {
             instance
@@ -139,41 +150,44 @@ impl TestHarness {
         }
     }
 
-    pub fn create_allocator(&self) -> vk_mem::Allocator {
+    pub fn create_allocator(&self) -> vk_mem::ash::Allocator {
         let create_info =
-            vk_mem::AllocatorCreateInfo::new(&self.instance, &self.device, self.physical_device);
-        vk_mem::Allocator::new(create_info).unwrap()
+            vk_mem::ash::AllocatorCreateInfo::new(&self.instance, &self.device, self.physical_device);
+        vk_mem::ash::Allocator::new(create_info).unwrap()
     }
 }
 
+#[cfg(feature = "ash")]
 #[test]
 fn create_harness() {
     let _ = TestHarness::new();
 }
 
+#[cfg(feature = "ash")]
 #[test]
 fn create_allocator() {
     let harness = TestHarness::new();
     let _ = harness.create_allocator();
 }
 
+#[cfg(feature = "ash")]
 #[test]
 fn create_gpu_buffer() {
     let harness = TestHarness::new();
     let allocator = harness.create_allocator();
-    let allocation_info = vk_mem::AllocationCreateInfo {
-        usage: vk_mem::MemoryUsage::Auto,
+    let allocation_info = vk_mem::ash::AllocationCreateInfo {
+        usage: vk_mem::ash::MemoryUsage::Auto,
         ..Default::default()
     };
 
     unsafe {
         let (buffer, allocation) = allocator
             .create_buffer(
-                &spark::vk::BufferCreateInfo::builder()
+                &vk::BufferCreateInfo::builder()
                     .size(16 * 1024)
                     .usage(
-                        spark::vk::BufferUsageFlags::VERTEX_BUFFER
-                            | spark::vk::BufferUsageFlags::TRANSFER_DST,
+                        vk::BufferUsageFlags::VERTEX_BUFFER
+                            | vk::BufferUsageFlags::TRANSFER_DST,
                     )
                     .build(),
                 &allocation_info,
@@ -185,25 +199,26 @@ fn create_gpu_buffer() {
     }
 }
 
+#[cfg(feature = "ash")]
 #[test]
 fn create_cpu_buffer_preferred() {
     let harness = TestHarness::new();
     let allocator = harness.create_allocator();
-    let allocation_info = vk_mem::AllocationCreateInfo {
-        required_flags: spark::vk::MemoryPropertyFlags::HOST_VISIBLE,
-        preferred_flags: spark::vk::MemoryPropertyFlags::HOST_COHERENT
-            | spark::vk::MemoryPropertyFlags::HOST_CACHED,
-        flags: vk_mem::AllocationCreateFlags::MAPPED,
+    let allocation_info = vk_mem::ash::AllocationCreateInfo {
+        required_flags: vk::MemoryPropertyFlags::HOST_VISIBLE,
+        preferred_flags: vk::MemoryPropertyFlags::HOST_COHERENT
+            | vk::MemoryPropertyFlags::HOST_CACHED,
+        flags: vk_mem::ash::AllocationCreateFlags::MAPPED,
         ..Default::default()
     };
     unsafe {
         let (buffer, allocation) = allocator
             .create_buffer(
-                &spark::vk::BufferCreateInfo::builder()
+                &vk::BufferCreateInfo::builder()
                     .size(16 * 1024)
                     .usage(
-                        spark::vk::BufferUsageFlags::VERTEX_BUFFER
-                            | spark::vk::BufferUsageFlags::TRANSFER_DST,
+                        vk::BufferUsageFlags::VERTEX_BUFFER
+                            | vk::BufferUsageFlags::TRANSFER_DST,
                     )
                     .build(),
                 &allocation_info,
@@ -215,22 +230,23 @@ fn create_cpu_buffer_preferred() {
     }
 }
 
+#[cfg(feature = "ash")]
 #[test]
 fn create_gpu_buffer_pool() {
     let harness = TestHarness::new();
     let allocator = harness.create_allocator();
     let allocator = Arc::new(allocator);
 
-    let buffer_info = spark::vk::BufferCreateInfo::builder()
+    let buffer_info = vk::BufferCreateInfo::builder()
         .size(16 * 1024)
-        .usage(spark::vk::BufferUsageFlags::UNIFORM_BUFFER | spark::vk::BufferUsageFlags::TRANSFER_DST)
+        .usage(vk::BufferUsageFlags::UNIFORM_BUFFER | vk::BufferUsageFlags::TRANSFER_DST)
         .build();
 
-    let allocation_info = vk_mem::AllocationCreateInfo {
-        required_flags: spark::vk::MemoryPropertyFlags::HOST_VISIBLE,
-        preferred_flags: spark::vk::MemoryPropertyFlags::HOST_COHERENT
-            | spark::vk::MemoryPropertyFlags::HOST_CACHED,
-        flags: vk_mem::AllocationCreateFlags::MAPPED,
+    let allocation_info = vk_mem::ash::AllocationCreateInfo {
+        required_flags: vk::MemoryPropertyFlags::HOST_VISIBLE,
+        preferred_flags: vk::MemoryPropertyFlags::HOST_COHERENT
+            | vk::MemoryPropertyFlags::HOST_CACHED,
+        flags: vk_mem::ash::AllocationCreateFlags::MAPPED,
 
         ..Default::default()
     };
@@ -240,7 +256,7 @@ fn create_gpu_buffer_pool() {
             .unwrap();
 
         // Create a pool that can have at most 2 blocks, 128 MiB each.
-        let pool_info = vk_mem::PoolCreateInfo::new()
+        let pool_info = vk_mem::ash::PoolCreateInfo::new()
             .memory_type_index(memory_type_index)
             .block_size(128 * 1024 * 1024)
             .max_block_count(2);
@@ -254,28 +270,29 @@ fn create_gpu_buffer_pool() {
     }
 }
 
+#[cfg(feature = "ash")]
 #[test]
 fn test_gpu_stats() {
     let harness = TestHarness::new();
     let allocator = harness.create_allocator();
-    let allocation_info = vk_mem::AllocationCreateInfo {
-        usage: vk_mem::MemoryUsage::Auto,
+    let allocation_info = vk_mem::ash::AllocationCreateInfo {
+        usage: vk_mem::ash::MemoryUsage::Auto,
         ..Default::default()
     };
 
     unsafe {
         let stats_1 = allocator.calculate_statistics().unwrap();
-        assert_eq!(stats_1.total.statistics.blockCount, 0);
-        assert_eq!(stats_1.total.statistics.allocationCount, 0);
-        assert_eq!(stats_1.total.statistics.allocationBytes, 0);
+        assert_eq!(stats_1.total.statistics.block_count, 0);
+        assert_eq!(stats_1.total.statistics.allocation_count, 0);
+        assert_eq!(stats_1.total.statistics.allocation_bytes, 0);
 
         let (buffer, allocation) = allocator
             .create_buffer(
-                &spark::vk::BufferCreateInfo::builder()
+                &vk::BufferCreateInfo::builder()
                     .size(16 * 1024)
                     .usage(
-                        spark::vk::BufferUsageFlags::VERTEX_BUFFER
-                            | spark::vk::BufferUsageFlags::TRANSFER_DST,
+                        vk::BufferUsageFlags::VERTEX_BUFFER
+                            | vk::BufferUsageFlags::TRANSFER_DST,
                     )
                     .build(),
                 &allocation_info,
@@ -283,16 +300,16 @@ fn test_gpu_stats() {
             .unwrap();
 
         let stats_2 = allocator.calculate_statistics().unwrap();
-        assert_eq!(stats_2.total.statistics.blockCount, 1);
-        assert_eq!(stats_2.total.statistics.allocationCount, 1);
-        assert_eq!(stats_2.total.statistics.allocationBytes, 16 * 1024);
+        assert_eq!(stats_2.total.statistics.block_count, 1);
+        assert_eq!(stats_2.total.statistics.allocation_count, 1);
+        assert_eq!(stats_2.total.statistics.allocation_bytes, 16 * 1024);
 
         allocator.destroy_buffer(buffer, allocation);
 
         let stats_3 = allocator.calculate_statistics().unwrap();
-        assert_eq!(stats_3.total.statistics.blockCount, 1);
-        assert_eq!(stats_3.total.statistics.allocationCount, 0);
-        assert_eq!(stats_3.total.statistics.allocationBytes, 0);
+        assert_eq!(stats_3.total.statistics.block_count, 1);
+        assert_eq!(stats_3.total.statistics.allocation_count, 0);
+        assert_eq!(stats_3.total.statistics.allocation_bytes, 0);
     }
 }
 
@@ -377,8 +394,1514 @@ fn virtual_block_out_of_space() {
     unsafe {
         match virtual_block.allocate(allocation_info) {
             Ok(_) => panic!("Created VirtualAllocation larger than VirtualBlock"),
-            Err(spark::vk::Result::ERROR_OUT_OF_DEVICE_MEMORY) => {},
+            Err(vk_mem::Error::OutOfDeviceMemory { .. }) => {},
             Err(_) => panic!("Unexpected VirtualBlock error"),
         }
     }
 }
+
+fn create_mock_allocator() -> (Arc<vk_mem::mock::MockDevice>, vk_mem::Allocator) {
+    let device =
+        vk_mem::mock::MockDevice::new(vk_mem::mock::MockDevice::default_memory_properties());
+    let allocator = vk_mem::Allocator::new_mock(device.create_info()).unwrap();
+    (device, allocator)
+}
+
+#[test]
+fn mock_create_allocator() {
+    let (device, allocator) = create_mock_allocator();
+    unsafe {
+        assert_eq!(allocator.get_memory_properties().memory_type_count, 4);
+    }
+    drop(allocator);
+    assert_eq!(device.memory_count(), 0);
+}
+
+#[cfg(feature = "ash")]
+#[test]
+fn mock_backends_side_by_side() {
+    let (spark_device, spark_allocator) = create_mock_allocator();
+    let ash_device = vk_mem::ash::mock::MockDevice::new(
+        vk_mem::ash::mock::MockDevice::default_memory_properties(),
+    );
+    let ash_allocator = vk_mem::ash::Allocator::new_mock(ash_device.create_info()).unwrap();
+    assert_ne!(
+        format!("{:?}", spark_allocator.id()),
+        format!("{:?}", ash_allocator.id())
+    );
+
+    unsafe {
+        let (spark_buffer, spark_allocation) = spark_allocator
+            .create_buffer(
+                &spark::vk::BufferCreateInfo::builder()
+                    .size(1024)
+                    .usage(spark::vk::BufferUsageFlags::VERTEX_BUFFER)
+                    .build(),
+                &vk_mem::AllocationCreateInfo::default(),
+            )
+            .unwrap();
+        let (ash_buffer, ash_allocation) = ash_allocator
+            .create_buffer(
+                &vk::BufferCreateInfo::builder()
+                    .size(1024)
+                    .usage(vk::BufferUsageFlags::VERTEX_BUFFER)
+                    .build(),
+                &vk_mem::ash::AllocationCreateInfo::default(),
+            )
+            .unwrap();
+        assert_eq!(spark_device.buffer_count(), 1);
+        assert_eq!(ash_device.buffer_count(), 1);
+
+        spark_allocator.destroy_buffer(spark_buffer, spark_allocation);
+        ash_allocator.destroy_buffer(ash_buffer, ash_allocation);
+    }
+}
+
+#[test]
+fn mock_create_buffer_and_image() {
+    let (device, allocator) = create_mock_allocator();
+    let allocation_info = vk_mem::AllocationCreateInfo {
+        usage: vk_mem::MemoryUsage::Auto,
+        ..Default::default()
+    };
+
+    unsafe {
+        let (buffer, buffer_allocation) = allocator
+            .create_buffer(
+                &spark::vk::BufferCreateInfo::builder()
+                    .size(16 * 1024)
+                    .usage(spark::vk::BufferUsageFlags::VERTEX_BUFFER)
+                    .build(),
+                &allocation_info,
+            )
+            .unwrap();
+        let (image, image_allocation) = allocator
+            .create_image(
+                &spark::vk::ImageCreateInfo::builder()
+                    .image_type(spark::vk::ImageType::N2D)
+                    .format(spark::vk::Format::R8G8B8A8_UNORM)
+                    .extent(spark::vk::Extent3D {
+                        width: 64,
+                        height: 64,
+                        depth: 1,
+                    })
+                    .mip_levels(1)
+                    .array_layers(1)
+                    .usage(spark::vk::ImageUsageFlags::SAMPLED)
+                    .build(),
+                &allocation_info,
+            )
+            .unwrap();
+        assert_eq!(device.buffer_count(), 1);
+        assert_eq!(device.image_count(), 1);
+        assert!(device.heap_usage(0) > 0);
+
+        allocator.destroy_buffer(buffer, buffer_allocation);
+        allocator.destroy_image(image, image_allocation);
+        assert_eq!(device.buffer_count(), 0);
+        assert_eq!(device.image_count(), 0);
+    }
+}
+
+#[test]
+fn mock_map_host_visible_buffer() {
+    let (_device, allocator) = create_mock_allocator();
+    let allocation_info = vk_mem::AllocationCreateInfo {
+        required_flags: spark::vk::MemoryPropertyFlags::HOST_VISIBLE,
+        flags: vk_mem::AllocationCreateFlags::MAPPED,
+        ..Default::default()
+    };
+
+    unsafe {
+        let (buffer, allocation) = allocator
+            .create_buffer(
+                &spark::vk::BufferCreateInfo::builder()
+                    .size(1024)
+                    .usage(spark::vk::BufferUsageFlags::TRANSFER_SRC)
+                    .build(),
+                &allocation_info,
+            )
+            .unwrap();
+        let mapped_data = allocator.get_allocation_info(&allocation).unwrap().mapped_data as *mut u8;
+        assert_ne!(mapped_data, std::ptr::null_mut());
+        std::ptr::write_bytes(mapped_data, 0xAB, 1024);
+        assert_eq!(*mapped_data.add(1023), 0xAB);
+        allocator.destroy_buffer(buffer, allocation);
+    }
+}
+
+#[test]
+fn mock_check_corruption() {
+    let config = vk_mem::build_config();
+    assert_eq!(config.detect_corruption, cfg!(feature = "detect_corruption"));
+    assert_eq!(config.heavy_assert, cfg!(feature = "heavy_assert"));
+    assert!(!config.detect_corruption || config.debug_margin > 0);
+
+    let (_device, allocator) = create_mock_allocator();
+    let allocator = Arc::new(allocator);
+    // Memory type 1 of the mock device is HOST_VISIBLE and HOST_COHERENT
+    let pool_info = vk_mem::PoolCreateInfo::new()
+        .memory_type_index(1)
+        .block_size(1024 * 1024);
+    let pool = allocator.create_pool(&pool_info).unwrap();
+
+    unsafe {
+        let (buffer, allocation) = pool
+            .create_buffer(
+                &spark::vk::BufferCreateInfo::builder()
+                    .size(1024)
+                    .usage(spark::vk::BufferUsageFlags::UNIFORM_BUFFER)
+                    .build(),
+                &vk_mem::AllocationCreateInfo::default(),
+            )
+            .unwrap();
+        match pool.check_corruption() {
+            Ok(()) => assert!(config.detect_corruption),
+            Err(vk_mem::Error::FeatureNotEnabled { .. }) => assert!(!config.detect_corruption),
+            Err(error) => panic!("Unexpected error {}", error),
+        }
+        allocator.destroy_buffer(buffer, allocation);
+    }
+}
+
+#[test]
+fn mock_pool_respects_max_block_count() {
+    let (_device, allocator) = create_mock_allocator();
+    let allocator = Arc::new(allocator);
+    let pool_info = vk_mem::PoolCreateInfo::new()
+        .memory_type_index(1)
+        .block_size(1024 * 1024)
+        .max_block_count(1);
+    let pool = allocator.create_pool(&pool_info).unwrap();
+
+    let buffer_info = spark::vk::BufferCreateInfo::builder()
+        .size(768 * 1024)
+        .usage(spark::vk::BufferUsageFlags::UNIFORM_BUFFER)
+        .build();
+    unsafe {
+        let (buffer, allocation) = pool
+            .create_buffer(&buffer_info, &vk_mem::AllocationCreateInfo::default())
+            .unwrap();
+        assert!(pool
+            .create_buffer(&buffer_info, &vk_mem::AllocationCreateInfo::default())
+            .is_err());
+        allocator.destroy_buffer(buffer, allocation);
+    }
+}
+
+#[test]
+fn mock_heap_exhaustion() {
+    let mut memory_properties = vk_mem::mock::MockDevice::default_memory_properties();
+    memory_properties.memory_heaps[0].size = 4 * 1024 * 1024;
+    let device = vk_mem::mock::MockDevice::new(memory_properties);
+    let allocator = vk_mem::Allocator::new_mock(device.create_info()).unwrap();
+    let allocation_info = vk_mem::AllocationCreateInfo {
+        required_flags: spark::vk::MemoryPropertyFlags::DEVICE_LOCAL,
+        flags: vk_mem::AllocationCreateFlags::DEDICATED_MEMORY,
+        ..Default::default()
+    };
+
+    unsafe {
+        let result = allocator.create_buffer(
+            &spark::vk::BufferCreateInfo::builder()
+                .size(8 * 1024 * 1024)
+                .usage(spark::vk::BufferUsageFlags::STORAGE_BUFFER)
+                .build(),
+            &allocation_info,
+        );
+        match result {
+            Err(vk_mem::Error::OutOfDeviceMemory { operation, size }) => {
+                assert_eq!(operation, "vmaCreateBuffer");
+                assert_eq!(size, Some(8 * 1024 * 1024));
+            }
+            _ => panic!("Unexpected result of exhausting the heap"),
+        }
+    }
+}
+
+#[test]
+fn mock_no_suitable_memory_type() {
+    let (_device, allocator) = create_mock_allocator();
+    let allocation_info = vk_mem::AllocationCreateInfo {
+        required_flags: spark::vk::MemoryPropertyFlags::LAZILY_ALLOCATED,
+        ..Default::default()
+    };
+
+    unsafe {
+        let error = allocator
+            .create_image(
+                &spark::vk::ImageCreateInfo::builder()
+                    .image_type(spark::vk::ImageType::N2D)
+                    .format(spark::vk::Format::R8G8B8A8_UNORM)
+                    .extent(spark::vk::Extent3D {
+                        width: 64,
+                        height: 64,
+                        depth: 1,
+                    })
+                    .mip_levels(1)
+                    .array_layers(1)
+                    .samples(spark::vk::SampleCountFlags::N1)
+                    .usage(spark::vk::ImageUsageFlags::SAMPLED)
+                    .build(),
+                &allocation_info,
+            )
+            .unwrap_err();
+        match error {
+            vk_mem::Error::NoSuitableMemoryType { required_flags, .. } => {
+                assert_eq!(required_flags, spark::vk::MemoryPropertyFlags::LAZILY_ALLOCATED);
+            }
+            _ => panic!("Unexpected error {:?}", error),
+        }
+        assert_eq!(error.operation(), "vmaCreateImage");
+        assert_eq!(error.vk_result(), spark::vk::Result::ERROR_FEATURE_NOT_PRESENT);
+        assert!(error.to_string().starts_with("vmaCreateImage failed: no suitable memory type"));
+    }
+}
+
+#[test]
+fn mock_owned_buffer_and_image() {
+    let (device, allocator) = create_mock_allocator();
+    let allocator = Arc::new(allocator);
+    let allocation_info = vk_mem::AllocationCreateInfo {
+        usage: vk_mem::MemoryUsage::Auto,
+        ..Default::default()
+    };
+
+    unsafe {
+        let buffer = vk_mem::Buffer::new(
+            allocator.clone(),
+            &spark::vk::BufferCreateInfo::builder()
+                .size(16 * 1024)
+                .usage(spark::vk::BufferUsageFlags::VERTEX_BUFFER)
+                .build(),
+            &allocation_info,
+        )
+        .unwrap();
+        assert_eq!(buffer.create_info().size, 16 * 1024);
+        assert!(buffer.allocation_info().size >= 16 * 1024);
+
+        let image = vk_mem::Image::new(
+            &*allocator,
+            &spark::vk::ImageCreateInfo::builder()
+                .image_type(spark::vk::ImageType::N2D)
+                .format(spark::vk::Format::R8G8B8A8_UNORM)
+                .extent(spark::vk::Extent3D {
+                    width: 64,
+                    height: 64,
+                    depth: 1,
+                })
+                .mip_levels(1)
+                .array_layers(1)
+                .usage(spark::vk::ImageUsageFlags::SAMPLED)
+                .build(),
+            &allocation_info,
+        )
+        .unwrap();
+        assert_eq!(device.buffer_count(), 1);
+        assert_eq!(device.image_count(), 1);
+
+        drop(buffer);
+        drop(image);
+        assert_eq!(device.buffer_count(), 0);
+        assert_eq!(device.image_count(), 0);
+
+        let buffer = vk_mem::Buffer::new(
+            allocator.clone(),
+            &spark::vk::BufferCreateInfo::builder()
+                .size(1024)
+                .usage(spark::vk::BufferUsageFlags::VERTEX_BUFFER)
+                .build(),
+            &allocation_info,
+        )
+        .unwrap();
+        let (buffer, allocation) = buffer.into_raw();
+        assert_eq!(device.buffer_count(), 1);
+        allocator.destroy_buffer(buffer, allocation);
+        assert_eq!(device.buffer_count(), 0);
+    }
+}
+
+#[test]
+fn mock_typed_user_data() {
+    let (_device, allocator) = create_mock_allocator();
+    let tag = Arc::new("vertices");
+
+    unsafe {
+        let (buffer, mut allocation) = allocator
+            .create_buffer_with_user_data(
+                &spark::vk::BufferCreateInfo::builder()
+                    .size(1024)
+                    .usage(spark::vk::BufferUsageFlags::VERTEX_BUFFER)
+                    .build(),
+                &vk_mem::AllocationCreateInfo::default(),
+                tag.clone(),
+            )
+            .unwrap();
+        assert_eq!(
+            allocator.user_data::<Arc<&str>>(&allocation).map(|tag| **tag),
+            Some("vertices")
+        );
+        assert!(allocator.user_data::<u32>(&allocation).is_none());
+        assert_eq!(Arc::strong_count(&tag), 2);
+
+        allocator.set_user_data(&mut allocation, 7u32);
+        assert_eq!(Arc::strong_count(&tag), 1);
+        assert_eq!(allocator.user_data::<u32>(&allocation), Some(&7));
+
+        allocator.set_user_data(&mut allocation, tag.clone());
+        allocator.destroy_buffer(buffer, allocation);
+        assert_eq!(Arc::strong_count(&tag), 1);
+    }
+}
+
+#[test]
+fn mock_alloc_trait_object() {
+    let (_device, allocator) = create_mock_allocator();
+    // The generic user data methods don't prevent using `Alloc` as a trait object
+    let alloc: &dyn vk_mem::Alloc = &allocator;
+
+    unsafe {
+        let (buffer, allocation) = alloc
+            .create_buffer(
+                &spark::vk::BufferCreateInfo::builder()
+                    .size(1024)
+                    .usage(spark::vk::BufferUsageFlags::VERTEX_BUFFER)
+                    .build(),
+                &vk_mem::AllocationCreateInfo::default(),
+            )
+            .unwrap();
+        alloc.allocator().destroy_buffer(buffer, allocation);
+    }
+}
+
+#[test]
+fn mock_reject_foreign_allocation() {
+    let (_device, allocator) = create_mock_allocator();
+    let (_other_device, other_allocator) = create_mock_allocator();
+    assert_ne!(allocator.id(), other_allocator.id());
+
+    unsafe {
+        let (buffer, allocation) = allocator
+            .create_buffer(
+                &spark::vk::BufferCreateInfo::builder()
+                    .size(1024)
+                    .usage(spark::vk::BufferUsageFlags::UNIFORM_BUFFER)
+                    .build(),
+                &vk_mem::AllocationCreateInfo::default(),
+            )
+            .unwrap();
+        assert_eq!(allocation.allocator_id(), allocator.id());
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            other_allocator.get_allocation_info(&allocation)
+        }));
+        assert!(result.is_err());
+
+        allocator.destroy_buffer(buffer, allocation);
+    }
+}
+
+#[test]
+fn mock_mapped_memory_flushes_non_coherent() {
+    let (device, allocator) = create_mock_allocator();
+    let allocation_info = vk_mem::AllocationCreateInfo {
+        required_flags: spark::vk::MemoryPropertyFlags::HOST_VISIBLE
+            | spark::vk::MemoryPropertyFlags::HOST_CACHED,
+        ..Default::default()
+    };
+
+    unsafe {
+        let (buffer, mut allocation) = allocator
+            .create_buffer(
+                &spark::vk::BufferCreateInfo::builder()
+                    .size(1024)
+                    .usage(spark::vk::BufferUsageFlags::TRANSFER_SRC)
+                    .build(),
+                &allocation_info,
+            )
+            .unwrap();
+
+        {
+            let mut mapped = allocator.map(&mut allocation).unwrap();
+            assert!(!mapped.is_coherent());
+            assert_eq!(mapped.size(), 1024);
+            mapped.write(16, &[1, 2, 3, 4]);
+            assert_eq!(mapped.as_slice::<u32>()[4], u32::from_ne_bytes([1, 2, 3, 4]));
+
+            // Overflowing and reversed ranges are rejected instead of widening the dirty range
+            let overflow = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                mapped.write(usize::MAX, &[1]);
+            }));
+            assert!(overflow.is_err());
+            let (start, end) = (20, 16);
+            let reversed = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                mapped.mark_dirty(start..end);
+            }));
+            assert!(reversed.is_err());
+        }
+        assert_eq!(device.flush_count(), 1);
+
+        {
+            let mapped = allocator.map(&mut allocation).unwrap();
+            assert_eq!(&mapped[16..20], &[1, 2, 3, 4]);
+        }
+        assert_eq!(device.flush_count(), 1);
+
+        allocator.destroy_buffer(buffer, allocation);
+    }
+}
+
+#[test]
+fn mock_copy_to_and_from_allocation() {
+    let (device, allocator) = create_mock_allocator();
+    let buffer_info = spark::vk::BufferCreateInfo::builder()
+        .size(4096)
+        .usage(spark::vk::BufferUsageFlags::TRANSFER_SRC)
+        .build();
+    let coherent_info = vk_mem::AllocationCreateInfo {
+        required_flags: spark::vk::MemoryPropertyFlags::HOST_VISIBLE
+            | spark::vk::MemoryPropertyFlags::HOST_COHERENT,
+        ..Default::default()
+    };
+    let cached_info = vk_mem::AllocationCreateInfo {
+        required_flags: spark::vk::MemoryPropertyFlags::HOST_VISIBLE
+            | spark::vk::MemoryPropertyFlags::HOST_CACHED,
+        ..Default::default()
+    };
+    let data: Vec<u8> = (0..100).collect();
+
+    unsafe {
+        let (coherent_buffer, coherent_allocation) =
+            allocator.create_buffer(&buffer_info, &coherent_info).unwrap();
+        allocator
+            .copy_to_allocation(&coherent_allocation, 10, &data)
+            .unwrap();
+        let mut readback = vec![0u8; data.len()];
+        allocator
+            .copy_from_allocation(&coherent_allocation, 10, &mut readback)
+            .unwrap();
+        assert_eq!(readback, data);
+        assert_eq!(device.flush_count(), 0);
+        assert_eq!(device.invalidate_count(), 0);
+
+        let (cached_buffer, cached_allocation) =
+            allocator.create_buffer(&buffer_info, &cached_info).unwrap();
+        allocator
+            .copy_to_allocation(&cached_allocation, 10, &data)
+            .unwrap();
+        let mut readback = vec![0u8; data.len()];
+        allocator
+            .copy_from_allocation(&cached_allocation, 10, &mut readback)
+            .unwrap();
+        assert_eq!(readback, data);
+        assert_eq!(device.flush_count(), 1);
+        assert_eq!(device.invalidate_count(), 1);
+
+        allocator.destroy_buffer(coherent_buffer, coherent_allocation);
+        allocator.destroy_buffer(cached_buffer, cached_allocation);
+    }
+}
+
+fn create_mock_host_buffer(
+    allocator: &vk_mem::Allocator,
+) -> (spark::vk::Buffer, vk_mem::Allocation) {
+    let buffer_info = spark::vk::BufferCreateInfo::builder()
+        .size(4096)
+        .usage(spark::vk::BufferUsageFlags::TRANSFER_SRC)
+        .build();
+    let allocation_info = vk_mem::AllocationCreateInfo {
+        required_flags: spark::vk::MemoryPropertyFlags::HOST_VISIBLE,
+        ..Default::default()
+    };
+    unsafe { allocator.create_buffer(&buffer_info, &allocation_info).unwrap() }
+}
+
+#[test]
+#[should_panic(expected = "range out of bounds of the allocation")]
+fn mock_copy_to_allocation_offset_overflow() {
+    let (_device, allocator) = create_mock_allocator();
+    let (_buffer, allocation) = create_mock_host_buffer(&allocator);
+    let _ = allocator.copy_to_allocation(&allocation, usize::MAX, &[0u8; 16]);
+}
+
+#[test]
+#[should_panic(expected = "range out of bounds of the allocation")]
+fn mock_copy_from_allocation_offset_overflow() {
+    let (_device, allocator) = create_mock_allocator();
+    let (_buffer, allocation) = create_mock_host_buffer(&allocator);
+    let _ = allocator.copy_from_allocation(&allocation, usize::MAX, &mut [0u8; 16]);
+}
+
+#[test]
+fn mock_allocator_info() {
+    let (_device, allocator) = create_mock_allocator();
+    let info = allocator.info();
+    let other_info = allocator.info();
+    assert_eq!(info.device, other_info.device);
+    assert_eq!(
+        allocator.memory_type_properties(2),
+        spark::vk::MemoryPropertyFlags::HOST_VISIBLE | spark::vk::MemoryPropertyFlags::HOST_CACHED
+    );
+}
+
+#[test]
+#[should_panic]
+fn mock_memory_type_properties_out_of_range() {
+    let (_device, allocator) = create_mock_allocator();
+    allocator.memory_type_properties(4);
+}
+
+#[test]
+fn mock_allocation_memory_properties() {
+    let (_device, allocator) = create_mock_allocator();
+    let allocation_info = vk_mem::AllocationCreateInfo {
+        usage: vk_mem::MemoryUsage::Auto,
+        flags: vk_mem::AllocationCreateFlags::HOST_ACCESS_SEQUENTIAL_WRITE,
+        ..Default::default()
+    };
+
+    unsafe {
+        let (buffer, allocation) = allocator
+            .create_buffer(
+                &spark::vk::BufferCreateInfo::builder()
+                    .size(1024)
+                    .usage(spark::vk::BufferUsageFlags::UNIFORM_BUFFER)
+                    .build(),
+                &allocation_info,
+            )
+            .unwrap();
+        let flags = allocator.get_allocation_memory_properties(&allocation);
+        assert!(flags.contains(spark::vk::MemoryPropertyFlags::HOST_VISIBLE));
+        let memory_type = allocator.get_allocation_info(&allocation).unwrap().memory_type;
+        assert_eq!(flags, allocator.memory_type_properties(memory_type));
+        allocator.destroy_buffer(buffer, allocation);
+    }
+}
+
+#[test]
+fn mock_allocation_name() {
+    let (_device, allocator) = create_mock_allocator();
+
+    unsafe {
+        let (buffer, allocation) = allocator
+            .create_buffer(
+                &spark::vk::BufferCreateInfo::builder()
+                    .size(1024)
+                    .usage(spark::vk::BufferUsageFlags::UNIFORM_BUFFER)
+                    .build(),
+                &vk_mem::AllocationCreateInfo::default(),
+            )
+            .unwrap();
+        assert_eq!(allocator.get_allocation_info(&allocation).unwrap().name, None);
+
+        allocator.set_allocation_name(&allocation, "terrain vertices");
+        assert_eq!(
+            allocator.get_allocation_info(&allocation).unwrap().name.as_deref(),
+            Some("terrain vertices")
+        );
+        allocator.destroy_buffer(buffer, allocation);
+    }
+}
+
+#[test]
+fn mock_create_aliasing_buffer() {
+    let (device, allocator) = create_mock_allocator();
+    let allocation_info = vk_mem::AllocationCreateInfo {
+        usage: vk_mem::MemoryUsage::Auto,
+        flags: vk_mem::AllocationCreateFlags::CAN_ALIAS,
+        ..Default::default()
+    };
+
+    unsafe {
+        let (image, allocation) = allocator
+            .create_image(
+                &spark::vk::ImageCreateInfo::builder()
+                    .image_type(spark::vk::ImageType::N2D)
+                    .format(spark::vk::Format::R8G8B8A8_UNORM)
+                    .extent(spark::vk::Extent3D {
+                        width: 64,
+                        height: 64,
+                        depth: 1,
+                    })
+                    .mip_levels(1)
+                    .array_layers(1)
+                    .usage(spark::vk::ImageUsageFlags::COLOR_ATTACHMENT)
+                    .build(),
+                &allocation_info,
+            )
+            .unwrap();
+        let aliasing_buffer = allocator
+            .create_aliasing_buffer(
+                &allocation,
+                &spark::vk::BufferCreateInfo::builder()
+                    .size(8 * 1024)
+                    .usage(spark::vk::BufferUsageFlags::STORAGE_BUFFER)
+                    .build(),
+            )
+            .unwrap();
+        assert_eq!(device.buffer_count(), 1);
+        assert_eq!(device.image_count(), 1);
+
+        device.destroy_buffer(aliasing_buffer);
+        allocator.destroy_image(image, allocation);
+        assert_eq!(device.buffer_count(), 0);
+        assert_eq!(device.image_count(), 0);
+    }
+}
+
+#[test]
+fn mock_create_aliasing_image() {
+    let (device, allocator) = create_mock_allocator();
+    let allocation_info = vk_mem::AllocationCreateInfo {
+        usage: vk_mem::MemoryUsage::Auto,
+        flags: vk_mem::AllocationCreateFlags::CAN_ALIAS,
+        ..Default::default()
+    };
+    let image_info = spark::vk::ImageCreateInfo::builder()
+        .image_type(spark::vk::ImageType::N2D)
+        .format(spark::vk::Format::R8G8B8A8_UNORM)
+        .extent(spark::vk::Extent3D {
+            width: 64,
+            height: 64,
+            depth: 1,
+        })
+        .mip_levels(1)
+        .array_layers(1)
+        .usage(spark::vk::ImageUsageFlags::COLOR_ATTACHMENT)
+        .build();
+
+    unsafe {
+        let (image, allocation) = allocator.create_image(&image_info, &allocation_info).unwrap();
+        let aliasing_image = allocator
+            .create_aliasing_image(&allocation, &image_info)
+            .unwrap();
+        assert_ne!(aliasing_image, image);
+        assert_eq!(device.image_count(), 2);
+
+        device.destroy_image(aliasing_image);
+        allocator.destroy_image(image, allocation);
+        assert_eq!(device.image_count(), 0);
+    }
+}
+
+#[test]
+fn mock_device_memory_callbacks() {
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    let allocated = Arc::new(AtomicU64::new(0));
+    let freed = Arc::new(AtomicU64::new(0));
+    let device =
+        vk_mem::mock::MockDevice::new(vk_mem::mock::MockDevice::default_memory_properties());
+    let allocator = vk_mem::Allocator::new_mock(device.create_info().device_memory_callbacks(
+        {
+            let allocated = allocated.clone();
+            move |_memory_type, _memory, size| {
+                allocated.fetch_add(size, Ordering::Relaxed);
+            }
+        },
+        {
+            let freed = freed.clone();
+            move |_memory_type, _memory, size| {
+                freed.fetch_add(size, Ordering::Relaxed);
+            }
+        },
+    ))
+    .unwrap();
+
+    unsafe {
+        let (buffer, allocation) = allocator
+            .create_buffer(
+                &spark::vk::BufferCreateInfo::builder()
+                    .size(16 * 1024)
+                    .usage(spark::vk::BufferUsageFlags::VERTEX_BUFFER)
+                    .build(),
+                &vk_mem::AllocationCreateInfo {
+                    usage: vk_mem::MemoryUsage::Auto,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(allocated.load(Ordering::Relaxed), device.heap_usage(0));
+        allocator.destroy_buffer(buffer, allocation);
+    }
+
+    drop(allocator);
+    assert!(allocated.load(Ordering::Relaxed) > 0);
+    assert_eq!(freed.load(Ordering::Relaxed), allocated.load(Ordering::Relaxed));
+}
+
+#[test]
+fn mock_host_allocation_callbacks() {
+    let callbacks = vk_mem::HostAllocationCallbacks::new();
+    let device =
+        vk_mem::mock::MockDevice::new(vk_mem::mock::MockDevice::default_memory_properties());
+    let allocator = vk_mem::Allocator::new_mock(
+        device
+            .create_info()
+            .host_allocation_callbacks(callbacks.clone()),
+    )
+    .unwrap();
+    assert!(callbacks.allocated_bytes() > 0);
+    assert!(callbacks.allocation_count() > 0);
+
+    drop(allocator);
+    assert_eq!(callbacks.allocated_bytes(), 0);
+    assert_eq!(callbacks.allocation_count(), 0);
+    assert!(callbacks.peak_allocated_bytes() > 0);
+}
+
+#[test]
+fn virtual_block_host_allocation_callbacks() {
+    let callbacks = vk_mem::HostAllocationCallbacks::with_allocator(std::alloc::System);
+    let create_info = vk_mem::VirtualBlockCreateInfo::new()
+        .size(16 * 1024 * 1024)
+        .host_allocation_callbacks(callbacks.clone());
+    let virtual_block = vk_mem::VirtualBlock::new(create_info).unwrap();
+    assert!(callbacks.allocation_count() > 0);
+
+    drop(virtual_block);
+    assert_eq!(callbacks.allocated_bytes(), 0);
+    assert_eq!(callbacks.allocation_count(), 0);
+}
+
+#[test]
+fn mock_statistics() {
+    let (_device, allocator) = create_mock_allocator();
+    let before = allocator.calculate_statistics().unwrap();
+    assert_eq!(before.per_memory_type.len(), 4);
+    assert_eq!(before.per_heap.len(), 2);
+    assert_eq!(before.total.statistics, vk_mem::Statistics::default());
+
+    unsafe {
+        let (buffer, allocation) = allocator
+            .create_buffer(
+                &spark::vk::BufferCreateInfo::builder()
+                    .size(16 * 1024)
+                    .usage(spark::vk::BufferUsageFlags::VERTEX_BUFFER)
+                    .build(),
+                &vk_mem::AllocationCreateInfo {
+                    usage: vk_mem::MemoryUsage::Auto,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        let after = allocator.calculate_statistics().unwrap();
+        let growth = after.clone() - before.clone();
+        assert_eq!(growth.total.statistics.allocation_count, 1);
+        assert_eq!(growth.total.statistics.allocation_bytes, 16 * 1024);
+        assert_eq!(before + growth, after);
+        assert!(after.to_string().starts_with("Total: 1 allocations (16.00 KiB)"));
+
+        let budgets = allocator.get_heap_budgets().unwrap();
+        assert_eq!(budgets.len(), 2);
+        let heap_statistics = budgets
+            .iter()
+            .fold(vk_mem::Statistics::default(), |sum, budget| sum + budget.statistics);
+        assert_eq!(heap_statistics, after.total.statistics);
+
+        allocator.destroy_buffer(buffer, allocation);
+    }
+}
+
+#[test]
+fn mock_build_stats_string() {
+    let (_device, allocator) = create_mock_allocator();
+    unsafe {
+        let (buffer, allocation) = allocator
+            .create_buffer(
+                &spark::vk::BufferCreateInfo::builder()
+                    .size(16 * 1024)
+                    .usage(spark::vk::BufferUsageFlags::VERTEX_BUFFER)
+                    .build(),
+                &vk_mem::AllocationCreateInfo {
+                    usage: vk_mem::MemoryUsage::Auto,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        allocator.set_allocation_name(&allocation, "vertices");
+
+        let stats = allocator.build_stats_string(true);
+        assert!(stats.contains("\"Total\""));
+        assert!(stats.contains("vertices"));
+
+        #[cfg(feature = "stats_dump")]
+        {
+            let dump = vk_mem::StatsDump::from_json(&stats).unwrap();
+            assert_eq!(dump.total.allocation_count, 1);
+            assert_eq!(dump.heaps.len(), 2);
+            let suballocation = dump
+                .blocks()
+                .flat_map(|(_, block)| &block.suballocations)
+                .find(|suballocation| suballocation.kind == vk_mem::SuballocationType::Buffer)
+                .unwrap();
+            assert_eq!(suballocation.size, 16 * 1024);
+            assert_eq!(suballocation.name.as_deref(), Some("vertices"));
+        }
+
+        allocator.destroy_buffer(buffer, allocation);
+    }
+}
+
+#[cfg(feature = "stats_dump")]
+#[test]
+fn mock_render_memory_map() {
+    let (_device, allocator) = create_mock_allocator();
+    unsafe {
+        let (image, allocation) = allocator
+            .create_image(
+                &spark::vk::ImageCreateInfo::builder()
+                    .image_type(spark::vk::ImageType::N2D)
+                    .format(spark::vk::Format::R8G8B8A8_UNORM)
+                    .extent(spark::vk::Extent3D {
+                        width: 64,
+                        height: 64,
+                        depth: 1,
+                    })
+                    .mip_levels(1)
+                    .array_layers(1)
+                    .usage(spark::vk::ImageUsageFlags::SAMPLED)
+                    .build(),
+                &vk_mem::AllocationCreateInfo {
+                    usage: vk_mem::MemoryUsage::Auto,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        allocator.set_allocation_name(&allocation, "albedo <main>");
+
+        let dump = vk_mem::StatsDump::from_json(&allocator.build_stats_string(true)).unwrap();
+        let svg = vk_mem::render_memory_map(&dump, &vk_mem::MemoryMapOptions::default());
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        assert!(svg.contains("Image (optimal)"));
+        assert!(svg.contains("albedo &lt;main&gt;"));
+
+        allocator.destroy_image(image, allocation);
+    }
+}
+
+#[test]
+fn mock_leak_report() {
+    let reports = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let device =
+        vk_mem::mock::MockDevice::new(vk_mem::mock::MockDevice::default_memory_properties());
+    let handler = {
+        let reports = reports.clone();
+        vk_mem::LeakHandler::Custom(Box::new(move |report: &vk_mem::LeakReport| {
+            reports.lock().unwrap().push(report.clone())
+        }))
+    };
+    let allocator =
+        vk_mem::Allocator::new_mock(device.create_info().leak_handler(handler)).unwrap();
+
+    unsafe {
+        let buffer_info = spark::vk::BufferCreateInfo::builder()
+            .size(4096)
+            .usage(spark::vk::BufferUsageFlags::UNIFORM_BUFFER)
+            .build();
+        let allocation_info = vk_mem::AllocationCreateInfo::default();
+        let (buffer, allocation) = allocator
+            .create_buffer(&buffer_info, &allocation_info)
+            .unwrap();
+        allocator.destroy_buffer(buffer, allocation);
+
+        let (_leaked_buffer, leaked_allocation) = allocator
+            .create_buffer_with_user_data(&buffer_info, &allocation_info, 42u32)
+            .unwrap();
+        allocator.set_allocation_name(&leaked_allocation, "leaked");
+    }
+    drop(allocator);
+
+    let reports = reports.lock().unwrap();
+    assert_eq!(reports.len(), 1);
+    assert_eq!(reports[0].owner, "Allocator");
+    assert_eq!(reports[0].allocations.len(), 1);
+    let leaked = &reports[0].allocations[0];
+    assert!(leaked.size >= 4096);
+    assert_eq!(leaked.name.as_deref(), Some("leaked"));
+    assert_eq!(leaked.user_data_type, Some("u32"));
+    assert!(reports[0].to_string().contains("name \"leaked\""));
+}
+
+#[cfg(feature = "track_allocations")]
+#[test]
+fn mock_track_allocations() {
+    let (_device, allocator) = create_mock_allocator();
+    let allocator = Arc::new(allocator);
+    let buffer_info = spark::vk::BufferCreateInfo::builder()
+        .size(64 * 1024)
+        .usage(spark::vk::BufferUsageFlags::STORAGE_BUFFER)
+        .build();
+    let allocation_info = vk_mem::AllocationCreateInfo::default();
+
+    unsafe {
+        let (buffer, allocation) = allocator
+            .create_buffer(&buffer_info, &allocation_info)
+            .unwrap();
+        let owned = vk_mem::Buffer::new(allocator.clone(), &buffer_info, &allocation_info).unwrap();
+
+        let records = allocator.live_allocations();
+        assert_eq!(records.len(), 2);
+        // Both report the call site in this file, not the crate internals
+        assert_eq!(records[0].location.file(), file!());
+        assert_eq!(records[1].location.file(), file!());
+        assert!(records[0].location.line() < records[1].location.line());
+        assert!(records[0].size >= 64 * 1024);
+        assert_eq!(records[0].pool, None);
+
+        allocator.destroy_buffer(buffer, allocation);
+        assert_eq!(allocator.live_allocations().len(), 1);
+        drop(owned);
+        assert!(allocator.live_allocations().is_empty());
+    }
+
+    // Leak reports of pools carry the call site too
+    let reports = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let device =
+        vk_mem::mock::MockDevice::new(vk_mem::mock::MockDevice::default_memory_properties());
+    let handler = {
+        let reports = reports.clone();
+        vk_mem::LeakHandler::Custom(Box::new(move |report: &vk_mem::LeakReport| {
+            reports.lock().unwrap().push(report.clone())
+        }))
+    };
+    let allocator =
+        Arc::new(vk_mem::Allocator::new_mock(device.create_info().leak_handler(handler)).unwrap());
+    let pool = allocator
+        .create_pool(&vk_mem::PoolCreateInfo::new().memory_type_index(0))
+        .unwrap();
+    let leaked = unsafe { pool.create_buffer(&buffer_info, &allocation_info) };
+    let leak_line = line!() - 1;
+    let (_leaked_buffer, _leaked_allocation) = leaked.unwrap();
+    drop(pool);
+
+    let reports = reports.lock().unwrap();
+    assert_eq!(reports.len(), 1);
+    assert_eq!(reports[0].owner, "AllocatorPool");
+    let location = reports[0].allocations[0].location.unwrap();
+    assert_eq!(location.file(), file!());
+    assert_eq!(location.line(), leak_line);
+    assert!(allocator.live_allocations().is_empty());
+}
+
+#[test]
+#[should_panic(expected = "VirtualBlock dropped with 1 live allocations")]
+fn virtual_block_leak_panics() {
+    let create_info = vk_mem::VirtualBlockCreateInfo::new()
+        .size(1024 * 1024)
+        .leak_handler(vk_mem::LeakHandler::Panic);
+    let virtual_block = vk_mem::VirtualBlock::new(create_info).unwrap();
+    let allocation_info = vk_mem::VirtualAllocationCreateInfo {
+        size: 4096,
+        alignment: 0,
+        user_data: 0,
+        flags: vk_mem::VirtualAllocationCreateFlags::empty(),
+    };
+    unsafe {
+        let (allocation, _) = virtual_block.allocate(allocation_info).unwrap();
+        virtual_block.free(allocation);
+        virtual_block.allocate(allocation_info).unwrap();
+    }
+}
+
+#[test]
+fn virtual_block_statistics() {
+    let create_info = vk_mem::VirtualBlockCreateInfo::new().size(16 * 1024 * 1024); // 16MB block
+    let virtual_block = vk_mem::VirtualBlock::new(create_info)
+        .expect("Couldn't create VirtualBlock");
+    assert!(virtual_block.is_empty());
+
+    let allocation_info = vk_mem::VirtualAllocationCreateInfo {
+        size: 4 * 1024 * 1024,
+        alignment: 0,
+        user_data: 0,
+        flags: vk_mem::VirtualAllocationCreateFlags::empty(),
+    };
+
+    unsafe {
+        let (virtual_alloc_0, _) = virtual_block.allocate(allocation_info).unwrap();
+        let (virtual_alloc_1, _) = virtual_block.allocate(allocation_info).unwrap();
+        let (virtual_alloc_2, _) = virtual_block.allocate(allocation_info).unwrap();
+        virtual_block.free(virtual_alloc_1);
+        assert!(!virtual_block.is_empty());
+
+        let statistics = virtual_block.statistics();
+        assert_eq!(statistics.block_count, 1);
+        assert_eq!(statistics.block_bytes, 16 * 1024 * 1024);
+        assert_eq!(statistics.allocation_count, 2);
+        assert_eq!(statistics.allocation_bytes, 8 * 1024 * 1024);
+
+        let detailed_statistics = virtual_block.detailed_statistics();
+        assert_eq!(detailed_statistics.statistics, statistics);
+        assert_eq!(detailed_statistics.unused_range_count, 2);
+
+        let stats_string = virtual_block.stats_string(true);
+        assert!(stats_string.contains("\"Suballocations\""));
+
+        virtual_block.free(virtual_alloc_0);
+        virtual_block.free(virtual_alloc_2);
+    }
+    assert!(virtual_block.is_empty());
+}
+
+struct FakeBudgetSource {
+    usage: std::sync::Mutex<Vec<u64>>,
+}
+
+impl vk_mem::BudgetSource for FakeBudgetSource {
+    fn heap_budgets(&self) -> vk_mem::Result<Vec<vk_mem::Budget>> {
+        Ok(self
+            .usage
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|&usage| vk_mem::Budget {
+                usage,
+                budget: 1000,
+                ..Default::default()
+            })
+            .collect())
+    }
+}
+
+#[test]
+fn budget_monitor_watermarks() {
+    use vk_mem::WatermarkCrossing::{Above, Below};
+
+    let source = Arc::new(FakeBudgetSource {
+        usage: std::sync::Mutex::new(vec![0, 0]),
+    });
+    let reported = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let mut monitor = vk_mem::BudgetMonitor::new(source.clone())
+        .watermarks(&[0.95, 0.8])
+        .hysteresis(0.05)
+        .on_crossing({
+            let reported = reported.clone();
+            move |event| reported.lock().unwrap().push(*event)
+        });
+    let mut poll = |usage: Vec<u64>| -> Vec<(u32, f32, vk_mem::WatermarkCrossing)> {
+        *source.usage.lock().unwrap() = usage;
+        monitor
+            .poll()
+            .unwrap()
+            .iter()
+            .map(|event| (event.heap_index, event.watermark, event.crossing))
+            .collect()
+    };
+
+    assert_eq!(poll(vec![500, 0]), vec![]);
+    assert_eq!(poll(vec![800, 0]), vec![(0, 0.8, Above)]);
+    // Within the hysteresis of the 80% watermark
+    assert_eq!(poll(vec![760, 0]), vec![]);
+    assert_eq!(poll(vec![800, 0]), vec![]);
+    assert_eq!(poll(vec![960, 990]), vec![(0, 0.95, Above), (1, 0.8, Above), (1, 0.95, Above)]);
+    assert_eq!(poll(vec![700, 990]), vec![(0, 0.95, Below), (0, 0.8, Below)]);
+    assert_eq!(poll(vec![700, 910]), vec![]);
+    assert_eq!(poll(vec![700, 899]), vec![(1, 0.95, Below)]);
+
+    assert_eq!(reported.lock().unwrap().len(), 7);
+    assert_eq!(monitor.current_watermark(0), None);
+    assert_eq!(monitor.current_watermark(1), Some(0.8));
+}
+
+#[cfg(feature = "recording")]
+#[test]
+fn mock_record_and_replay() {
+    use std::sync::Mutex;
+
+    /// Recording output shared with the test.
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let output = SharedBuffer::default();
+    let recorder = vk_mem::Recorder::new(output.clone()).unwrap();
+    let device =
+        vk_mem::mock::MockDevice::new(vk_mem::mock::MockDevice::default_memory_properties());
+    let allocator =
+        vk_mem::Allocator::new_mock(device.create_info().recorder(recorder.clone())).unwrap();
+    let allocator = Arc::new(allocator);
+    let allocation_info = vk_mem::AllocationCreateInfo {
+        usage: vk_mem::MemoryUsage::Auto,
+        ..Default::default()
+    };
+    let create_buffer = move |allocator: &vk_mem::Allocator| unsafe {
+        let buffer_info = spark::vk::BufferCreateInfo::builder()
+            .size(1024 * 1024)
+            .usage(spark::vk::BufferUsageFlags::STORAGE_BUFFER)
+            .build();
+        allocator
+            .create_buffer(&buffer_info, &allocation_info)
+            .unwrap()
+    };
+
+    unsafe {
+        let (buffer, allocation) = create_buffer(&allocator);
+        allocator.set_allocation_name(&allocation, "storage");
+        allocator
+            .flush_allocations(std::iter::once(&allocation), None, None)
+            .unwrap();
+        // Corruption detection isn't enabled, the failure is recorded all the same
+        assert!(allocator
+            .check_corruption(spark::vk::MemoryPropertyFlags::HOST_VISIBLE)
+            .is_err());
+        // Made on another thread, so the recording contains two thread indices
+        let (second_buffer, second_allocation) = std::thread::spawn({
+            let allocator = allocator.clone();
+            move || create_buffer(&allocator)
+        })
+        .join()
+        .unwrap();
+        allocator.destroy_buffer(buffer, allocation);
+
+        let aliasing_buffer = allocator
+            .create_aliasing_buffer(
+                &second_allocation,
+                &spark::vk::BufferCreateInfo::builder()
+                    .size(1024)
+                    .usage(spark::vk::BufferUsageFlags::STORAGE_BUFFER)
+                    .build(),
+            )
+            .unwrap();
+        allocator
+            .bind_buffer_memory(&second_allocation, aliasing_buffer)
+            .unwrap();
+        // No memory type is protected
+        assert!(allocator
+            .allocate_memory_for_buffer(
+                aliasing_buffer,
+                &vk_mem::AllocationCreateInfo {
+                    required_flags: spark::vk::MemoryPropertyFlags::PROTECTED,
+                    ..Default::default()
+                },
+            )
+            .is_err());
+        device.destroy_buffer(aliasing_buffer);
+
+        let defragmentation = allocator
+            .begin_defragmentation(&vk_mem::DefragmentationInfo {
+                flags: 0,
+                pool: std::ptr::null_mut(),
+                maxBytesPerPass: 0,
+                maxAllocationsPerPass: 0,
+            })
+            .unwrap();
+        while defragmentation.begin_pass(|_| {}) {}
+        defragmentation.end();
+
+        allocator.destroy_buffer(second_buffer, second_allocation);
+    }
+    drop(allocator);
+
+    let bytes = output.0.lock().unwrap().clone();
+    let records: Vec<vk_mem::Record> = vk_mem::RecordingReader::new(&bytes[..])
+        .unwrap()
+        .collect::<std::io::Result<_>>()
+        .unwrap();
+    let mut names: Vec<&str> = records.iter().map(|record| record.call.name()).collect();
+    // The number of defragmentation passes is up to VMA
+    names.dedup();
+    assert_eq!(
+        names,
+        [
+            "CreateAllocator",
+            "CreateBuffer",
+            "SetAllocationName",
+            "FlushAllocations",
+            "CheckCorruption",
+            "CreateBuffer",
+            "FreeMemory",
+            "CreateAliasingBuffer",
+            "BindBufferMemory",
+            "AllocateMemory",
+            "BeginDefragmentation",
+            "DefragmentationPass",
+            "EndDefragmentation",
+            "FreeMemory",
+            "DestroyAllocator"
+        ]
+    );
+    assert_ne!(records[1].thread, records[5].thread);
+    assert!(records.windows(2).all(|pair| pair[0].time <= pair[1].time));
+    match &records[2].call {
+        vk_mem::Call::SetAllocationName { name, .. } => assert_eq!(name, "storage"),
+        call => panic!("unexpected call {:?}", call),
+    }
+    match &records[9].call {
+        vk_mem::Call::AllocateMemory {
+            memory_requirements,
+            result,
+            ..
+        } => {
+            assert_eq!(memory_requirements.size, 0);
+            assert_eq!(*result, vk_mem::CallResult::NoSuitableMemoryType);
+        }
+        call => panic!("unexpected call {:?}", call),
+    }
+
+    let replay_device =
+        vk_mem::mock::MockDevice::new(vk_mem::mock::MockDevice::default_memory_properties());
+    let replay_allocator =
+        Arc::new(vk_mem::Allocator::new_mock(replay_device.create_info()).unwrap());
+    let record_count = records.len() as u64;
+    let stats = vk_mem::replay(&replay_allocator, records.into_iter().map(Ok)).unwrap();
+    // Binding and aliasing are counted, but not replayed
+    assert_eq!(stats.unreplayable_calls, 2);
+    assert_eq!(stats.calls, record_count - 2);
+    assert_eq!(stats.mismatched_results, 0);
+    // The failed allocation, whose requirements are unknown
+    assert_eq!(stats.skipped_calls, 1);
+    assert_eq!(stats.per_call["BeginDefragmentation"].count, 1);
+    assert_eq!(stats.per_call["FlushAllocations"].count, 1);
+    assert_eq!(stats.per_call["CheckCorruption"].count, 1);
+    assert!(!stats.per_call.contains_key("BindBufferMemory"));
+    assert_eq!(stats.per_call["CreateBuffer"].count, 2);
+    assert!(stats.peak_allocation_bytes >= 2 * 1024 * 1024);
+    assert_eq!(replay_device.buffer_count(), 0);
+
+    // Anything but a recording is rejected
+    assert!(vk_mem::RecordingReader::new(&b"not a recording"[..]).is_err());
+
+    // A corrupted string length fails to read instead of allocating it
+    let mut truncated = b"VKMEMREC".to_vec();
+    truncated.extend_from_slice(&vk_mem::RECORDING_VERSION.to_le_bytes());
+    truncated.push(14); // SetAllocationName
+    truncated.extend_from_slice(&[0; 4 + 8 + 8]);
+    truncated.extend_from_slice(&u32::MAX.to_le_bytes());
+    truncated.extend_from_slice(b"storage");
+    let mut reader = vk_mem::RecordingReader::new(&truncated[..]).unwrap();
+    assert!(reader.next().unwrap().is_err());
+}
+
+#[cfg(feature = "tracing")]
+#[test]
+fn mock_tracing_events() {
+    use std::sync::Mutex;
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Level, Metadata};
+
+    /// Collects the level and `operation` field of every event, and the names of new spans.
+    #[derive(Default)]
+    struct Collector {
+        spans: Mutex<Vec<&'static str>>,
+        events: Mutex<Vec<(Level, String)>>,
+    }
+
+    struct OperationVisitor(String);
+
+    impl Visit for OperationVisitor {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            if field.name() == "operation" {
+                self.0 = value.to_owned();
+            }
+        }
+
+        fn record_debug(&mut self, _field: &Field, _value: &dyn std::fmt::Debug) {}
+    }
+
+    impl tracing::Subscriber for Collector {
+        fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            let mut spans = self.spans.lock().unwrap();
+            spans.push(span.metadata().name());
+            Id::from_u64(spans.len() as u64)
+        }
+
+        fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+        fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let mut visitor = OperationVisitor(String::new());
+            event.record(&mut visitor);
+            let level = *event.metadata().level();
+            self.events.lock().unwrap().push((level, visitor.0));
+        }
+
+        fn enter(&self, _span: &Id) {}
+
+        fn exit(&self, _span: &Id) {}
+    }
+
+    let collector = Arc::new(Collector::default());
+    let (_device, allocator) = create_mock_allocator();
+    tracing::subscriber::with_default(collector.clone(), || unsafe {
+        let (buffer, mut allocation) = allocator
+            .create_buffer(
+                &spark::vk::BufferCreateInfo::builder()
+                    .size(4096)
+                    .usage(spark::vk::BufferUsageFlags::UNIFORM_BUFFER)
+                    .build(),
+                &vk_mem::AllocationCreateInfo {
+                    required_flags: spark::vk::MemoryPropertyFlags::HOST_VISIBLE,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        allocator.map_memory(&mut allocation).unwrap();
+        allocator.unmap_memory(&mut allocation);
+        // Copies map and unmap like any other caller
+        allocator
+            .copy_to_allocation(&allocation, 0, &[1, 2, 3, 4])
+            .unwrap();
+        allocator.destroy_buffer(buffer, allocation);
+
+        // Failures are reported as warnings
+        let huge = spark::vk::MemoryRequirements {
+            size: 1 << 50,
+            alignment: 256,
+            memory_type_bits: !0,
+        };
+        assert!(allocator
+            .allocate_memory(&huge, &vk_mem::AllocationCreateInfo::default())
+            .is_err());
+    });
+
+    assert_eq!(
+        *collector.spans.lock().unwrap(),
+        [
+            "vmaCreateBuffer",
+            "vmaMapMemory",
+            "vmaUnmapMemory",
+            "vmaMapMemory",
+            "vmaUnmapMemory",
+            "vmaDestroyBuffer",
+            "vmaAllocateMemory"
+        ]
+    );
+    let events = collector.events.lock().unwrap();
+    let operations: Vec<&str> = events.iter().map(|(_, operation)| &operation[..]).collect();
+    assert_eq!(
+        operations,
+        [
+            "vmaCreateBuffer",
+            "vmaMapMemory",
+            "vmaUnmapMemory",
+            "vmaMapMemory",
+            "vmaUnmapMemory",
+            "vmaDestroyBuffer",
+            "vmaAllocateMemory"
+        ]
+    );
+    assert_eq!(events[0].0, Level::DEBUG);
+    assert_eq!(events[6].0, Level::WARN);
+}
+
+#[test]
+fn openmetrics_render_synthetic_statistics() {
+    let statistics = vk_mem::Statistics {
+        block_count: 2,
+        allocation_count: 5,
+        block_bytes: 64 << 20,
+        allocation_bytes: 40 << 20,
+    };
+    let detailed = vk_mem::DetailedStatistics {
+        statistics,
+        unused_range_count: 3,
+        ..Default::default()
+    };
+    let snapshot = vk_mem::MetricsSnapshot {
+        budgets: vec![vk_mem::Budget {
+            statistics,
+            usage: 80 << 20,
+            budget: 1 << 30,
+        }],
+        statistics: vk_mem::TotalStatistics {
+            per_memory_type: vec![detailed, Default::default()],
+            per_heap: vec![detailed],
+            total: detailed,
+        },
+        memory_type_heaps: vec![0, 0],
+        pools: vec![
+            vk_mem::PoolMetrics {
+                name: "textures \"hdr\"".to_owned(),
+                statistics: detailed,
+            },
+            // Same name as the first pool
+            vk_mem::PoolMetrics {
+                name: "textures \"hdr\"".to_owned(),
+                statistics: Default::default(),
+            },
+        ],
+    };
+
+    let text = snapshot.render();
+    assert!(text.starts_with(
+        "# TYPE vk_mem_heap_budget_bytes gauge\n\
+         # UNIT vk_mem_heap_budget_bytes bytes\n\
+         # HELP vk_mem_heap_budget_bytes Estimated memory available to the program in the heap.\n\
+         vk_mem_heap_budget_bytes{heap=\"0\"} 1073741824\n\
+         # TYPE vk_mem_heap_usage_bytes gauge\n"
+    ));
+    assert!(text.ends_with("\n# EOF\n"));
+    let lines: Vec<&str> = text.lines().collect();
+    for line in [
+        "vk_mem_heap_usage_bytes{heap=\"0\"} 83886080",
+        "vk_mem_heap_allocations{heap=\"0\"} 5",
+        "# TYPE vk_mem_memory_type_blocks gauge",
+        "vk_mem_memory_type_block_bytes{memory_type=\"0\",heap=\"0\"} 67108864",
+        "vk_mem_memory_type_allocations{memory_type=\"1\",heap=\"0\"} 0",
+        "# HELP vk_mem_pool_unused_ranges Free ranges between allocations in the pool.",
+        "vk_mem_pool_unused_ranges{pool=\"textures \\\"hdr\\\"\",pool_index=\"0\"} 3",
+        "vk_mem_pool_allocation_bytes{pool=\"textures \\\"hdr\\\"\",pool_index=\"0\"} 41943040",
+        "vk_mem_pool_allocation_bytes{pool=\"textures \\\"hdr\\\"\",pool_index=\"1\"} 0",
+    ]
+    .iter()
+    {
+        assert!(lines.contains(line), "missing {:?} in\n{}", line, text);
+    }
+    // Counts have no unit
+    assert!(!text.contains("# UNIT vk_mem_heap_blocks"));
+    // Every sample has a distinct label set, even with duplicate pool names
+    let mut samples: Vec<&str> = lines
+        .iter()
+        .filter(|line| !line.starts_with('#'))
+        .map(|line| line.rsplit_once(' ').unwrap().0)
+        .collect();
+    let sample_count = samples.len();
+    samples.sort_unstable();
+    samples.dedup();
+    assert_eq!(samples.len(), sample_count);
+}
+
+#[test]
+fn mock_metrics_handler() {
+    let (_device, allocator) = create_mock_allocator();
+    let allocator = Arc::new(allocator);
+    let pool = Arc::new(
+        allocator
+            .create_pool(&vk_mem::PoolCreateInfo::new().memory_type_index(0))
+            .unwrap(),
+    );
+    pool.set_name(Some(std::ffi::CStr::from_bytes_with_nul(b"staging\0").unwrap()));
+    let exporter = Arc::new(vk_mem::MetricsExporter::new(allocator.clone()));
+    exporter.add_pool(&pool);
+    let handler = exporter.handler();
+
+    let response = handler();
+    assert_eq!(response.status, 200);
+    assert_eq!(response.content_type, vk_mem::OPENMETRICS_CONTENT_TYPE);
+    assert!(response
+        .body
+        .contains("vk_mem_pool_allocations{pool=\"staging\",pool_index=\"0\"} 0\n"));
+
+    // Dropped pools disappear from the output
+    drop(pool);
+    assert!(!handler().body.contains("pool=\"staging\""));
+}
@@ -0,0 +1,71 @@
+//! Renders the JSON produced by `vk_mem::Allocator::build_stats_string` as an SVG memory map.
+//!
+//! Usage: `vk-mem-dump-vis [INPUT.json] [-o OUTPUT.svg] [--width PIXELS]`
+//!
+//! Reads from stdin when no input is given, or when it is `-`, and writes to stdout when no
+//! output is given.
+
+use std::fs;
+use std::io::{self, Read, Write};
+use std::process;
+
+const USAGE: &str = "usage: vk-mem-dump-vis [INPUT.json] [-o OUTPUT.svg] [--width PIXELS]";
+
+fn main() {
+    if let Err(message) = run() {
+        eprintln!("vk-mem-dump-vis: {}", message);
+        process::exit(1);
+    }
+}
+
+fn run() -> Result<(), String> {
+    let mut input = None;
+    let mut output = None;
+    let mut options = vk_mem_dump::MemoryMapOptions::default();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" | "--output" => {
+                output = Some(args.next().ok_or("missing value for --output")?);
+            }
+            "--width" => {
+                let width = args.next().ok_or("missing value for --width")?;
+                options.width = width
+                    .parse()
+                    .map_err(|_| format!("invalid width: {}", width))?;
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            _ if input.is_none() => input = Some(arg),
+            _ => return Err(USAGE.to_owned()),
+        }
+    }
+
+    let json = match input.as_deref() {
+        None | Some("-") => {
+            let mut json = String::new();
+            io::stdin()
+                .read_to_string(&mut json)
+                .map_err(|error| format!("can't read stdin: {}", error))?;
+            json
+        }
+        Some(path) => {
+            fs::read_to_string(path).map_err(|error| format!("can't read {}: {}", path, error))?
+        }
+    };
+    let dump = vk_mem_dump::StatsDump::from_json(&json)
+        .map_err(|error| format!("invalid stats dump: {}", error))?;
+    let svg = vk_mem_dump::render_memory_map(&dump, &options);
+
+    match output {
+        Some(path) => {
+            fs::write(&path, svg).map_err(|error| format!("can't write {}: {}", path, error))
+        }
+        None => io::stdout()
+            .write_all(svg.as_bytes())
+            .map_err(|error| format!("can't write stdout: {}", error)),
+    }
+}
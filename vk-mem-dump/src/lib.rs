@@ -0,0 +1,9 @@
+//! Typed model of the JSON statistics dump of `vk-mem`, and an SVG memory map of it.
+//!
+//! Pure Rust, so the dump can be parsed and visualized without the C++ toolchain that
+//! building VMA needs. `vk-mem` re-exports everything here with its `stats_dump` feature.
+
+mod memory_map;
+mod stats_dump;
+pub use memory_map::*;
+pub use stats_dump::*;
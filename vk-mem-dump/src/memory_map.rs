@@ -0,0 +1,251 @@
+//! SVG rendering of a `StatsDump`, equivalent to VMA's `VmaDumpVis.py`.
+//!
+//! Every `vk::DeviceMemory` block and dedicated allocation is drawn as one row, scaled
+//! relative to the largest one, with its allocations colored by resource type. The legend lists
+//! the colors followed by every named allocation, and hovering an allocation shows its details.
+
+use std::fmt::{self, Write};
+
+use crate::{StatsDump, SuballocationInfo, SuballocationType};
+
+const MARGIN: u32 = 10;
+const LABEL_WIDTH: u32 = 260;
+const ROW_SPACING: u32 = 6;
+const LEGEND_ROW_HEIGHT: u32 = 20;
+const FONT_SIZE: u32 = 12;
+
+const SUBALLOCATION_TYPES: [SuballocationType; 6] = [
+    SuballocationType::Free,
+    SuballocationType::Buffer,
+    SuballocationType::ImageOptimal,
+    SuballocationType::ImageLinear,
+    SuballocationType::ImageUnknown,
+    SuballocationType::Unknown,
+];
+
+/// Parameters of `render_memory_map`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryMapOptions {
+    /// Width of the whole picture in pixels.
+    pub width: u32,
+    /// Height of a single block row in pixels.
+    pub row_height: u32,
+}
+
+impl Default for MemoryMapOptions {
+    fn default() -> Self {
+        MemoryMapOptions {
+            width: 1280,
+            row_height: 24,
+        }
+    }
+}
+
+/// Block or dedicated allocation drawn as one row.
+struct Row<'a> {
+    label: String,
+    size: u64,
+    suballocations: Vec<&'a SuballocationInfo>,
+}
+
+/// Renders the memory map of `dump` as an SVG document.
+///
+/// Blocks only contain allocations if the dump was built with `detailed_map`.
+pub fn render_memory_map(dump: &StatsDump, options: &MemoryMapOptions) -> String {
+    let mut svg = String::new();
+    write_memory_map(&mut svg, &rows(dump), options).expect("writing to a String can't fail");
+    svg
+}
+
+fn rows(dump: &StatsDump) -> Vec<Row<'_>> {
+    let mut rows = Vec::new();
+    let pools = dump
+        .default_pools
+        .iter()
+        .map(|(memory_type, pool)| (format!("Type {}", memory_type), pool))
+        .chain(dump.custom_pools.iter().flat_map(|(memory_type, pools)| {
+            pools.iter().map(move |pool| {
+                let name = pool.name.as_deref().unwrap_or("unnamed");
+                (format!("Type {}, pool {}", memory_type, name), pool)
+            })
+        }));
+    for (label, pool) in pools {
+        for (block_id, block) in &pool.blocks {
+            rows.push(Row {
+                label: format!("{}, block {}", label, block_id),
+                size: block.total_bytes,
+                suballocations: block.suballocations.iter().collect(),
+            });
+        }
+        for allocation in &pool.dedicated_allocations {
+            rows.push(Row {
+                label: format!("{}, dedicated", label),
+                size: allocation.size,
+                suballocations: vec![allocation],
+            });
+        }
+    }
+    rows
+}
+
+fn write_memory_map(svg: &mut String, rows: &[Row], options: &MemoryMapOptions) -> fmt::Result {
+    let bar_width = options
+        .width
+        .saturating_sub(LABEL_WIDTH + 2 * MARGIN)
+        .max(1);
+    let max_size = rows.iter().map(|row| row.size).max().unwrap_or(0).max(1);
+    let scale = bar_width as f64 / max_size as f64;
+    let rows_height = rows.len() as u32 * (options.row_height + ROW_SPACING);
+    let legend_top = MARGIN + rows_height + MARGIN;
+    let legend = legend(rows);
+    let height = legend_top + legend.len() as u32 * LEGEND_ROW_HEIGHT + MARGIN;
+
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" font-family="sans-serif" font-size="{}">"#,
+        options.width, height, FONT_SIZE
+    )?;
+    writeln!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#)?;
+
+    for (index, row) in rows.iter().enumerate() {
+        let top = MARGIN + index as u32 * (options.row_height + ROW_SPACING);
+        let left = MARGIN + LABEL_WIDTH;
+        writeln!(
+            svg,
+            r#"<text x="{}" y="{}" dominant-baseline="middle">{} ({})</text>"#,
+            MARGIN,
+            top + options.row_height / 2,
+            escape(&row.label),
+            format_size(row.size)
+        )?;
+        for suballocation in &row.suballocations {
+            let x = left as f64 + suballocation.offset as f64 * scale;
+            let width = (suballocation.size as f64 * scale).max(1.0);
+            writeln!(
+                svg,
+                r#"<rect x="{:.2}" y="{}" width="{:.2}" height="{}" fill="{}"><title>{}</title></rect>"#,
+                x,
+                top,
+                width,
+                options.row_height,
+                color(suballocation.kind),
+                escape(&tooltip(suballocation))
+            )?;
+        }
+        writeln!(
+            svg,
+            r#"<rect x="{}" y="{}" width="{:.2}" height="{}" fill="none" stroke="black"/>"#,
+            left,
+            top,
+            (row.size as f64 * scale).max(1.0),
+            options.row_height
+        )?;
+    }
+
+    for (index, (kind, text)) in legend.iter().enumerate() {
+        let top = legend_top + index as u32 * LEGEND_ROW_HEIGHT;
+        writeln!(
+            svg,
+            r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}" stroke="black"/>"#,
+            MARGIN,
+            top,
+            FONT_SIZE,
+            FONT_SIZE,
+            color(*kind)
+        )?;
+        writeln!(
+            svg,
+            r#"<text x="{}" y="{}" dominant-baseline="middle">{}</text>"#,
+            MARGIN + FONT_SIZE + 6,
+            top + FONT_SIZE / 2,
+            escape(text)
+        )?;
+    }
+
+    writeln!(svg, "</svg>")
+}
+
+/// Legend entries: every suballocation type, followed by every named allocation.
+fn legend(rows: &[Row]) -> Vec<(SuballocationType, String)> {
+    let types = SUBALLOCATION_TYPES
+        .iter()
+        .map(|kind| (*kind, type_name(*kind).to_owned()));
+    let names = rows.iter().flat_map(|row| {
+        row.suballocations.iter().filter_map(move |suballocation| {
+            let name = suballocation.name.as_ref()?;
+            let text = format!(
+                "{}: {}, offset {}, size {}",
+                name,
+                row.label,
+                suballocation.offset,
+                format_size(suballocation.size)
+            );
+            Some((suballocation.kind, text))
+        })
+    });
+    types.chain(names).collect()
+}
+
+fn color(kind: SuballocationType) -> &'static str {
+    match kind {
+        SuballocationType::Free => "#dcdcdc",
+        SuballocationType::Buffer => "#7fa8ff",
+        SuballocationType::ImageOptimal => "#7fd37f",
+        SuballocationType::ImageLinear => "#ffc966",
+        SuballocationType::ImageUnknown => "#c39bd3",
+        SuballocationType::Unknown => "#ff8c8c",
+    }
+}
+
+fn type_name(kind: SuballocationType) -> &'static str {
+    match kind {
+        SuballocationType::Free => "Free",
+        SuballocationType::Buffer => "Buffer",
+        SuballocationType::ImageOptimal => "Image (optimal)",
+        SuballocationType::ImageLinear => "Image (linear)",
+        SuballocationType::ImageUnknown => "Image (unknown tiling)",
+        SuballocationType::Unknown => "Unknown",
+    }
+}
+
+fn tooltip(suballocation: &SuballocationInfo) -> String {
+    let mut tooltip = String::from(type_name(suballocation.kind));
+    if let Some(name) = &suballocation.name {
+        tooltip.push_str(&format!(" \"{}\"", name));
+    }
+    tooltip.push_str(&format!(
+        ", offset {}, size {}",
+        suballocation.offset,
+        format_size(suballocation.size)
+    ));
+    tooltip
+}
+
+/// Same format as `vk_mem::Statistics` uses, e.g. `1.50 MiB`.
+fn format_size(size: u64) -> String {
+    const UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+    if size < 1024 {
+        return format!("{} B", size);
+    }
+    let mut value = size as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.2} {}", value, UNITS[unit])
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
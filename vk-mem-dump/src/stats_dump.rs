@@ -0,0 +1,238 @@
+//! Typed model of the JSON produced by `vk_mem::Allocator::build_stats_string`.
+//!
+//! ```no_run
+//! # fn dump(json: &str) -> serde_json::Result<()> {
+//! let dump = vk_mem_dump::StatsDump::from_json(json)?;
+//! for (memory_type, pool) in &dump.default_pools {
+//!     for (block_id, block) in &pool.blocks {
+//!         println!("type {} block {}: {} bytes unused", memory_type, block_id, block.unused_bytes);
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::de::{self, Deserializer, MapAccess, Visitor};
+use serde::Deserialize;
+
+/// Whole JSON dump of an allocator.
+///
+/// Heaps, memory types and blocks are keyed by their index or id. Memory types without any
+/// allocations are omitted by VMA, and so are the blocks and suballocations unless the dump
+/// was built with `detailed_map`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct StatsDump {
+    /// Properties of the physical device.
+    #[serde(default)]
+    pub general: Option<GeneralInfo>,
+    /// Statistics summed over all memory heaps.
+    pub total: DumpStatistics,
+    /// Memory heaps with the memory types inside them.
+    #[serde(rename = "MemoryInfo", default, deserialize_with = "indexed")]
+    pub heaps: BTreeMap<u32, HeapInfo>,
+    /// Default pools, keyed by memory type index.
+    #[serde(default, deserialize_with = "indexed")]
+    pub default_pools: BTreeMap<u32, PoolInfo>,
+    /// Custom pools, keyed by memory type index.
+    #[serde(default, deserialize_with = "indexed")]
+    pub custom_pools: BTreeMap<u32, Vec<PoolInfo>>,
+}
+
+/// Properties of the physical device the allocator was created for.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct GeneralInfo {
+    #[serde(rename = "API")]
+    pub api: String,
+    #[serde(rename = "apiVersion")]
+    pub api_version: String,
+    #[serde(rename = "GPU")]
+    pub gpu: String,
+    #[serde(rename = "deviceType")]
+    pub device_type: u32,
+    #[serde(rename = "maxMemoryAllocationCount")]
+    pub max_memory_allocation_count: u64,
+    #[serde(rename = "bufferImageGranularity")]
+    pub buffer_image_granularity: u64,
+    #[serde(rename = "nonCoherentAtomSize")]
+    pub non_coherent_atom_size: u64,
+    #[serde(rename = "memoryHeapCount")]
+    pub memory_heap_count: u32,
+    #[serde(rename = "memoryTypeCount")]
+    pub memory_type_count: u32,
+}
+
+/// Statistics as printed in the dump, see `vk_mem::DetailedStatistics`.
+///
+/// Minimums and maximums are only printed when there are at least two allocations or
+/// unused ranges to compare.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct DumpStatistics {
+    pub block_count: u32,
+    pub block_bytes: u64,
+    pub allocation_count: u32,
+    pub allocation_bytes: u64,
+    pub unused_range_count: u32,
+    pub allocation_size_min: Option<u64>,
+    pub allocation_size_max: Option<u64>,
+    pub unused_range_size_min: Option<u64>,
+    pub unused_range_size_max: Option<u64>,
+}
+
+/// Memory heap, keyed by heap index in `StatsDump::heaps`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct HeapInfo {
+    /// Names of the `vk::MemoryHeapFlags` bits, e.g. `DEVICE_LOCAL`.
+    #[serde(default)]
+    pub flags: Vec<String>,
+    pub size: u64,
+    #[serde(default)]
+    pub budget: Option<HeapBudget>,
+    pub stats: DumpStatistics,
+    /// Memory types located in this heap, keyed by memory type index.
+    #[serde(rename = "MemoryPools", default, deserialize_with = "indexed")]
+    pub memory_types: BTreeMap<u32, MemoryTypeInfo>,
+}
+
+/// Budget of a memory heap, see `vk_mem::Budget`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct HeapBudget {
+    pub budget_bytes: u64,
+    pub usage_bytes: u64,
+}
+
+/// Memory type, keyed by memory type index in `HeapInfo::memory_types`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct MemoryTypeInfo {
+    /// Names of the `vk::MemoryPropertyFlags` bits, e.g. `HOST_VISIBLE`.
+    #[serde(default)]
+    pub flags: Vec<String>,
+    pub stats: DumpStatistics,
+}
+
+/// Default or custom pool with its blocks and dedicated allocations.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct PoolInfo {
+    /// Pool id followed by the name set with `vk_mem::AllocatorPool::set_name`, custom pools only.
+    pub name: Option<String>,
+    pub preferred_block_size: Option<u64>,
+    /// `vk::DeviceMemory` blocks, keyed by block id.
+    #[serde(deserialize_with = "indexed")]
+    pub blocks: BTreeMap<u32, BlockInfo>,
+    pub dedicated_allocations: Vec<SuballocationInfo>,
+}
+
+/// Single `vk::DeviceMemory` block of a pool.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct BlockInfo {
+    pub map_ref_count: u32,
+    pub total_bytes: u64,
+    pub unused_bytes: u64,
+    /// Number of allocations in the block.
+    pub allocations: u32,
+    /// Number of free ranges in the block.
+    pub unused_ranges: u32,
+    /// Allocations and free ranges, ordered by offset.
+    pub suballocations: Vec<SuballocationInfo>,
+}
+
+/// Allocation or free range inside a block, or a dedicated allocation.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct SuballocationInfo {
+    /// Offset within the block, always 0 for dedicated allocations.
+    #[serde(default)]
+    pub offset: u64,
+    #[serde(rename = "Type")]
+    pub kind: SuballocationType,
+    pub size: u64,
+    /// Buffer or image usage flags of the resource, if known.
+    #[serde(default)]
+    pub usage: Option<u64>,
+    /// Name set with `vk_mem::Allocator::set_allocation_name`.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// User data pointer, printed as a hexadecimal string.
+    #[serde(default)]
+    pub custom_data: Option<String>,
+}
+
+/// Kind of resource occupying a suballocation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum SuballocationType {
+    Free,
+    Unknown,
+    Buffer,
+    ImageUnknown,
+    ImageLinear,
+    ImageOptimal,
+}
+
+impl StatsDump {
+    /// Parses the output of `vk_mem::Allocator::build_stats_string`.
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+
+    /// Iterates over the blocks of all default and custom pools, with their memory type index.
+    pub fn blocks(&self) -> impl Iterator<Item = (u32, &BlockInfo)> {
+        let default_pools = self
+            .default_pools
+            .iter()
+            .map(|(&memory_type, pool)| (memory_type, pool));
+        let custom_pools = self
+            .custom_pools
+            .iter()
+            .flat_map(|(&memory_type, pools)| pools.iter().map(move |pool| (memory_type, pool)));
+        default_pools
+            .chain(custom_pools)
+            .flat_map(|(memory_type, pool)| {
+                pool.blocks.values().map(move |block| (memory_type, block))
+            })
+    }
+}
+
+/// Deserializes a JSON object keyed like `"Heap 0"`, `"Type 3"` or `"12"`, keeping only the
+/// trailing index of every key.
+fn indexed<'de, D, T>(deserializer: D) -> Result<BTreeMap<u32, T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    struct IndexedVisitor<T>(PhantomData<T>);
+
+    impl<'de, T: Deserialize<'de>> Visitor<'de> for IndexedVisitor<T> {
+        type Value = BTreeMap<u32, T>;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("an object keyed by indices")
+        }
+
+        fn visit_map<M: MapAccess<'de>>(self, mut map: M) -> Result<Self::Value, M::Error> {
+            let mut result = BTreeMap::new();
+            while let Some((key, value)) = map.next_entry::<String, T>()? {
+                let index = key
+                    .rsplit(' ')
+                    .next()
+                    .and_then(|index| index.parse().ok())
+                    .ok_or_else(|| de::Error::custom(format!("key without index: {}", key)))?;
+                result.insert(index, value);
+            }
+            Ok(result)
+        }
+    }
+
+    deserializer.deserialize_map(IndexedVisitor(PhantomData))
+}
@@ -0,0 +1,61 @@
+extern crate vk_mem_dump;
+
+/// Dump of one block with a named buffer, shaped like the output of `build_stats_string`.
+const DUMP: &str = r#"{
+    "Total": {"BlockCount": 1, "BlockBytes": 65536, "AllocationCount": 1, "AllocationBytes": 1024,
+              "UnusedRangeCount": 1},
+    "MemoryInfo": {
+        "Heap 0": {
+            "Flags": ["DEVICE_LOCAL"],
+            "Size": 268435456,
+            "Stats": {"BlockCount": 1, "BlockBytes": 65536, "AllocationCount": 1,
+                      "AllocationBytes": 1024, "UnusedRangeCount": 1},
+            "MemoryPools": {
+                "Type 0": {
+                    "Flags": ["DEVICE_LOCAL"],
+                    "Stats": {"BlockCount": 1, "BlockBytes": 65536, "AllocationCount": 1,
+                              "AllocationBytes": 1024, "UnusedRangeCount": 1}
+                }
+            }
+        }
+    },
+    "DefaultPools": {
+        "Type 0": {
+            "PreferredBlockSize": 65536,
+            "Blocks": {
+                "0": {
+                    "MapRefCount": 0,
+                    "TotalBytes": 65536,
+                    "UnusedBytes": 64512,
+                    "Allocations": 1,
+                    "UnusedRanges": 1,
+                    "Suballocations": [
+                        {"Offset": 0, "Type": "BUFFER", "Size": 1024, "Usage": 130,
+                         "Name": "vertices & indices"},
+                        {"Offset": 1024, "Type": "FREE", "Size": 64512}
+                    ]
+                }
+            },
+            "DedicatedAllocations": []
+        }
+    }
+}"#;
+
+#[test]
+fn parse_and_render_synthetic_dump() {
+    let dump = vk_mem_dump::StatsDump::from_json(DUMP).unwrap();
+    assert_eq!(dump.heaps[&0].memory_types[&0].flags, ["DEVICE_LOCAL"]);
+    let blocks: Vec<_> = dump.blocks().collect();
+    assert_eq!(blocks.len(), 1);
+    assert_eq!(blocks[0].0, 0);
+    assert_eq!(
+        blocks[0].1.suballocations[0].kind,
+        vk_mem_dump::SuballocationType::Buffer
+    );
+
+    let svg = vk_mem_dump::render_memory_map(&dump, &vk_mem_dump::MemoryMapOptions::default());
+    assert!(svg.starts_with("<svg"));
+    assert!(svg.trim_end().ends_with("</svg>"));
+    assert!(svg.contains("vertices &amp; indices"));
+    assert!(svg.contains("1.00 KiB"));
+}
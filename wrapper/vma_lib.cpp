@@ -1,2 +1,8 @@
+// Defined by build.rs for the heavy_assert feature, since function-like macros
+// can't be passed on the command line by every compiler
+#ifdef VK_MEM_HEAVY_ASSERT
+#define VMA_HEAVY_ASSERT(expr) VMA_ASSERT(expr)
+#endif
+
 #define VMA_IMPLEMENTATION
 #include "vk_mem_alloc.h"